@@ -8,7 +8,11 @@ use tokio::sync::mpsc;
 use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
 use url::Url;
 
-use super::{AiError, AiProvider, AudioResponseRx, AudioSession, TextStream};
+use super::azure_vision::parse_usage;
+use super::{
+    AiError, AiProvider, AudioResponse, AudioResponseRx, AudioSession, GenerationConfig,
+    TextStream, TokenUsage, VisionRequest,
+};
 
 /// Azure OpenAI Realtime API audio client (WebSocket).
 pub struct AzureAudioClient {
@@ -31,17 +35,28 @@ pub struct RealtimeAudioSession {
 // ── helpers (also used by tests) ────────────────────────────────────
 
 /// Build the session.update JSON payload.
-fn build_session_config(system_prompt: &str) -> Value {
-    json!({
+fn build_session_config(system_prompt: &str, generation: &GenerationConfig) -> Value {
+    let transcription = match generation.transcription_model.trim() {
+        "" => Value::Null,
+        model => json!({ "model": model }),
+    };
+    let mut config = json!({
         "type": "session.update",
         "session": {
             "modalities": ["text"],
             "instructions": system_prompt,
             "input_audio_format": "pcm16",
-            "input_audio_transcription": { "model": "whisper-1" },
-            "turn_detection": null
+            "input_audio_transcription": transcription,
+            "turn_detection": null,
+            "max_response_output_tokens": generation.max_output_tokens
         }
-    })
+    });
+    // Realtime only accepts 0.6–1.2; reasoning effort and image detail
+    // don't apply here.
+    if let Some(temperature) = generation.temperature {
+        config["session"]["temperature"] = json!(temperature.clamp(0.6, 1.2));
+    }
+    config
 }
 
 /// Build an `input_audio_buffer.append` message from raw PCM bytes.
@@ -62,13 +77,58 @@ fn build_response_create() -> Value {
     json!({ "type": "response.create" })
 }
 
+/// Build the Realtime WebSocket URL for an endpoint. The Realtime API
+/// requires the openai.azure.com domain, so cognitiveservices hosts are
+/// rewritten; `http://` endpoints (local test servers) map to `ws://`.
+fn realtime_url(endpoint: &str, deployment: &str) -> Result<String, AiError> {
+    let url = Url::parse(endpoint)
+        .map_err(|e| AiError::ConnectionError(format!("bad endpoint URL: {e}")))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| AiError::ConnectionError("no host in endpoint URL".into()))?;
+
+    // Convert cognitiveservices.azure.com → openai.azure.com
+    let ws_host = host.replace(".cognitiveservices.azure.com", ".openai.azure.com");
+    let scheme = if url.scheme() == "http" { "ws" } else { "wss" };
+    let port = url.port().map(|p| format!(":{p}")).unwrap_or_default();
+
+    Ok(format!(
+        "{scheme}://{ws_host}{port}/openai/realtime?api-version=2025-04-01-preview&deployment={deployment}"
+    ))
+}
+
+/// Map a failed WebSocket handshake to an `AiError`, keeping auth and
+/// rate-limit rejections distinguishable from network failures.
+fn connect_error(e: tokio_tungstenite::tungstenite::Error) -> AiError {
+    use tokio_tungstenite::tungstenite::Error;
+
+    if let Error::Http(response) = &e {
+        let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok());
+        match response.status().as_u16() {
+            401 | 403 => return AiError::AuthError(format!("WebSocket connect: {e}")),
+            429 => {
+                return AiError::RateLimited {
+                    retry_after_ms: super::azure_vision::retry_after_ms(
+                        header("retry-after-ms"),
+                        header("retry-after"),
+                    ),
+                }
+            }
+            _ => {}
+        }
+    }
+    AiError::ConnectionError(format!("WebSocket connect: {e}"))
+}
+
 /// Parsed event from the Realtime API.
 #[derive(Debug, Clone, PartialEq)]
 enum AudioEvent {
     /// A text delta to forward.
     Delta(String),
-    /// The response turn is complete.
-    Done,
+    /// The response turn is complete, with the tokens it used.
+    Done(Option<TokenUsage>),
+    /// Transcription of the committed input audio.
+    Transcript(String),
     /// Nothing actionable (skip).
     Skip,
 }
@@ -87,18 +147,30 @@ fn parse_event(text: &str) -> Result<AudioEvent, AiError> {
                 .to_string();
             Ok(AudioEvent::Delta(delta))
         }
-        Some("response.done") => Ok(AudioEvent::Done),
+        Some("response.done") => Ok(AudioEvent::Done(
+            v.pointer("/response/usage").map(parse_usage),
+        )),
+        Some("conversation.item.input_audio_transcription.completed") => {
+            let transcript = v
+                .get("transcript")
+                .and_then(|t| t.as_str())
+                .unwrap_or("")
+                .trim()
+                .to_string();
+            Ok(AudioEvent::Transcript(transcript))
+        }
         Some("response.text.done") | Some("response.audio_transcript.done") => Ok(AudioEvent::Skip),
         Some("error") => {
             let msg = v
                 .pointer("/error/message")
                 .and_then(|m| m.as_str())
                 .unwrap_or("unknown error");
-            let code = v
-                .pointer("/error/code")
-                .and_then(|c| c.as_str())
-                .unwrap_or("");
-            Err(AiError::ModelError(format!("[{code}] {msg}")))
+            let code = v.pointer("/error/code").and_then(|c| c.as_str());
+            Err(AiError::ApiError {
+                status: None,
+                code: code.map(str::to_string),
+                message: msg.to_string(),
+            })
         }
         _ => {
             let event_type = v.get("type").and_then(|t| t.as_str()).unwrap_or("unknown");
@@ -114,8 +186,7 @@ fn parse_event(text: &str) -> Result<AudioEvent, AiError> {
 impl AiProvider for AzureAudioClient {
     async fn analyze_frame(
         &self,
-        _frame_data: &str,
-        _system_prompt: &str,
+        _request: VisionRequest<'_>,
     ) -> Result<Box<dyn TextStream>, AiError> {
         Err(AiError::ModelError(
             "AzureAudioClient does not support vision analysis".into(),
@@ -125,21 +196,9 @@ impl AiProvider for AzureAudioClient {
     async fn start_audio_stream(
         &self,
         system_prompt: &str,
+        generation: &GenerationConfig,
     ) -> Result<(Box<dyn AudioSession>, AudioResponseRx), AiError> {
-        // Build wss URL — Realtime API requires openai.azure.com domain
-        let host = Url::parse(&self.endpoint)
-            .map_err(|e| AiError::ConnectionError(format!("bad endpoint URL: {e}")))?
-            .host_str()
-            .ok_or_else(|| AiError::ConnectionError("no host in endpoint URL".into()))?
-            .to_string();
-
-        // Convert cognitiveservices.azure.com → openai.azure.com
-        let ws_host = host.replace(".cognitiveservices.azure.com", ".openai.azure.com");
-
-        let ws_url = format!(
-            "wss://{ws_host}/openai/realtime?api-version=2025-04-01-preview&deployment={deployment}",
-            deployment = self.deployment,
-        );
+        let ws_url = realtime_url(&self.endpoint, &self.deployment)?;
         log::info!("Audio WebSocket URL: {}", ws_url);
 
         let ws_url_display = ws_url.clone();
@@ -155,13 +214,13 @@ impl AiProvider for AzureAudioClient {
 
         let (ws_stream, _response) = tokio_tungstenite::connect_async(request)
             .await
-            .map_err(|e| AiError::ConnectionError(format!("WebSocket connect: {e}")))?;
+            .map_err(connect_error)?;
         log::info!("Audio WebSocket connected to {}", ws_url_display);
 
         let (mut ws_sink, mut ws_source) = ws_stream.split();
 
         // Send session config
-        let config = build_session_config(system_prompt);
+        let config = build_session_config(system_prompt, generation);
         ws_sink
             .send(Message::Text(config.to_string().into()))
             .await
@@ -170,7 +229,7 @@ impl AiProvider for AzureAudioClient {
         // Channel: caller → WebSocket sink
         let (send_tx, mut send_rx) = mpsc::channel::<Message>(64);
         // Channel: parsed events → caller
-        let (resp_tx, resp_rx) = mpsc::channel::<Result<String, AiError>>(64);
+        let (resp_tx, resp_rx) = mpsc::channel::<Result<AudioResponse, AiError>>(64);
         // Channel: close signal
         let (close_tx, mut close_rx) = mpsc::channel::<()>(1);
 
@@ -200,21 +259,28 @@ impl AiProvider for AzureAudioClient {
             while let Some(msg_result) = ws_source.next().await {
                 match msg_result {
                     Ok(Message::Text(text)) => {
-                        match parse_event(&text) {
-                            Ok(AudioEvent::Delta(delta)) => {
-                                if resp_tx.send(Ok(delta)).await.is_err() {
-                                    break;
+                        let response = match parse_event(&text) {
+                            Ok(AudioEvent::Delta(delta)) => AudioResponse::Delta(delta),
+                            Ok(AudioEvent::Done(usage)) => {
+                                if let Some(usage) = usage {
+                                    if resp_tx.send(Ok(AudioResponse::Usage(usage))).await.is_err()
+                                    {
+                                        break;
+                                    }
                                 }
+                                AudioResponse::Done
                             }
-                            Ok(AudioEvent::Done) => {
-                                // Send empty string to signal turn completion
-                                let _ = resp_tx.send(Ok(String::new())).await;
+                            Ok(AudioEvent::Transcript(t)) if !t.is_empty() => {
+                                AudioResponse::Transcript(t)
                             }
-                            Ok(AudioEvent::Skip) => { /* skip */ }
+                            Ok(_) => continue,
                             Err(e) => {
                                 let _ = resp_tx.send(Err(e)).await;
                                 break;
                             }
+                        };
+                        if resp_tx.send(Ok(response)).await.is_err() {
+                            break;
                         }
                     }
                     Ok(_) => { /* skip non-text messages */ }
@@ -296,7 +362,8 @@ mod tests {
 
     #[test]
     fn session_config_json_structure() {
-        let cfg = build_session_config("You are a helpful assistant.");
+        let cfg =
+            build_session_config("You are a helpful assistant.", &GenerationConfig::default());
         assert_eq!(cfg["type"], "session.update");
         let session = &cfg["session"];
         assert_eq!(session["modalities"][0], "text");
@@ -304,6 +371,45 @@ mod tests {
         assert_eq!(session["input_audio_format"], "pcm16");
         assert_eq!(session["input_audio_transcription"]["model"], "whisper-1");
         assert!(session["turn_detection"].is_null());
+        assert_eq!(session["max_response_output_tokens"], 300);
+        assert!(session.get("temperature").is_none());
+    }
+
+    #[test]
+    fn session_config_follows_generation_config() {
+        let generation = GenerationConfig {
+            max_output_tokens: 80,
+            temperature: Some(0.2),
+            transcription_model: "gpt-4o-mini-transcribe".into(),
+            ..Default::default()
+        };
+        let session = &build_session_config("p", &generation)["session"];
+        assert_eq!(session["max_response_output_tokens"], 80);
+        // Raised to the lowest temperature Realtime accepts
+        assert!((session["temperature"].as_f64().unwrap() - 0.6).abs() < 1e-6);
+        assert_eq!(
+            session["input_audio_transcription"]["model"],
+            "gpt-4o-mini-transcribe"
+        );
+
+        let untranscribed = GenerationConfig {
+            transcription_model: String::new(),
+            ..Default::default()
+        };
+        let session = &build_session_config("p", &untranscribed)["session"];
+        assert!(session["input_audio_transcription"].is_null());
+    }
+
+    #[test]
+    fn realtime_url_rewrites_host_and_scheme() {
+        let url = realtime_url("https://beme.cognitiveservices.azure.com/", "rt").unwrap();
+        assert_eq!(
+            url,
+            "wss://beme.openai.azure.com/openai/realtime?api-version=2025-04-01-preview&deployment=rt"
+        );
+
+        let local = realtime_url("http://127.0.0.1:8089", "rt").unwrap();
+        assert!(local.starts_with("ws://127.0.0.1:8089/openai/realtime?"));
     }
 
     #[test]
@@ -334,7 +440,23 @@ mod tests {
     fn parse_response_done_event() {
         let event = r#"{"type":"response.done","response":{}}"#;
         let result = parse_event(event).unwrap();
-        assert_eq!(result, AudioEvent::Done);
+        assert_eq!(result, AudioEvent::Done(None));
+    }
+
+    #[test]
+    fn parse_response_done_usage() {
+        let event = r#"{"type":"response.done","response":{"usage":{"total_tokens":310,"input_tokens":250,"output_tokens":60,"input_token_details":{"cached_tokens":64,"text_tokens":50,"audio_tokens":200},"output_token_details":{"text_tokens":60,"audio_tokens":0}}}}"#;
+        let result = parse_event(event).unwrap();
+        assert_eq!(
+            result,
+            AudioEvent::Done(Some(TokenUsage {
+                input_tokens: 250,
+                cached_input_tokens: 64,
+                audio_input_tokens: 200,
+                output_tokens: 60,
+                ..Default::default()
+            }))
+        );
     }
 
     #[test]
@@ -344,13 +466,22 @@ mod tests {
         let result = parse_event(event);
         assert!(result.is_err());
         let err = result.unwrap_err();
-        match err {
-            AiError::ModelError(msg) => {
-                assert!(msg.contains("rate limit exceeded"));
-                assert!(msg.contains("rate_limit"));
-            }
-            other => panic!("expected ModelError, got: {other:?}"),
-        }
+        assert_eq!(err.provider_code(), Some("rate_limit"));
+        assert_eq!(err.status(), None);
+        assert_eq!(
+            err.to_string(),
+            "API error [rate_limit]: rate limit exceeded"
+        );
+    }
+
+    #[test]
+    fn parse_input_transcription_event() {
+        let event = r#"{"type":"conversation.item.input_audio_transcription.completed","item_id":"item_1","content_index":0,"transcript":"What does it cost? "}"#;
+        let result = parse_event(event).unwrap();
+        assert_eq!(
+            result,
+            AudioEvent::Transcript("What does it cost?".to_string())
+        );
     }
 
     #[test]
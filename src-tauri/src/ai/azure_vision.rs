@@ -2,9 +2,12 @@
 use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::{json, Value};
-use std::sync::{Arc, Mutex};
 
-use super::{AiError, AiProvider, AudioResponseRx, AudioSession, TextStream};
+use super::structured::{self, SuggestionFormat};
+use super::{
+    AiError, AiProvider, AudioResponseRx, AudioSession, GenerationConfig, TextStream, TokenUsage,
+    VisionRequest,
+};
 
 pub struct AzureVisionClient {
     endpoint: String,
@@ -14,7 +17,6 @@ pub struct AzureVisionClient {
     client: Client,
     /// When true, use `Authorization: Bearer` instead of `api-key` header.
     use_bearer: bool,
-    previous_response_id: Arc<Mutex<Option<String>>>,
 }
 
 impl AzureVisionClient {
@@ -31,7 +33,6 @@ impl AzureVisionClient {
             system_prompt: system_prompt.into(),
             client: Client::new(),
             use_bearer: false,
-            previous_response_id: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -41,9 +42,8 @@ impl AzureVisionClient {
         self
     }
 
-    fn build_request_body(&self, frame_data: &str, system_prompt: &str) -> Value {
-        let previous_id = self.previous_response_id.lock().unwrap().clone();
-
+    fn build_request_body(&self, request: &VisionRequest<'_>) -> Value {
+        let generation = request.generation;
         let mut body = json!({
             "model": self.model,
             "input": [
@@ -51,41 +51,78 @@ impl AzureVisionClient {
                     "type": "message",
                     "role": "user",
                     "content": [
-                        { "type": "input_text", "text": "What do you see?" },
-                        { "type": "input_image", "image_url": format!("data:image/jpeg;base64,{}", frame_data) }
+                        { "type": "input_text", "text": request.question.unwrap_or(&generation.user_text) },
+                        {
+                            "type": "input_image",
+                            "image_url": format!("data:image/jpeg;base64,{}", request.frame_data),
+                            "detail": request.image_detail().as_str()
+                        }
                     ]
                 }
             ],
-            "instructions": system_prompt,
+            "instructions": request.system_prompt,
             "stream": true,
-            "max_output_tokens": 300,
+            "max_output_tokens": generation.max_output_tokens,
             "truncation": "auto"
         });
+        apply_sampling(&mut body, generation);
 
-        if let Some(prev_id) = previous_id {
+        if let Some(prev_id) = request.previous_response_id {
             body.as_object_mut()
                 .unwrap()
                 .insert("previous_response_id".into(), json!(prev_id));
         }
+        if request.format == SuggestionFormat::Structured {
+            body.as_object_mut().unwrap().insert(
+                "text".into(),
+                json!({
+                    "format": {
+                        "type": "json_schema",
+                        "name": structured::SCHEMA_NAME,
+                        "schema": structured::json_schema(),
+                        "strict": true
+                    }
+                }),
+            );
+        }
 
         body
     }
-}
 
-#[async_trait]
-impl AiProvider for AzureVisionClient {
-    async fn analyze_frame(
+    /// Body for a text-only follow-up that continues `previous_response_id`.
+    fn build_follow_up_body(
         &self,
-        frame_data: &str,
+        previous_response_id: &str,
+        text: &str,
         system_prompt: &str,
-    ) -> Result<Box<dyn TextStream>, AiError> {
+        generation: &GenerationConfig,
+    ) -> Value {
+        let mut body = json!({
+            "model": self.model,
+            "input": [
+                {
+                    "type": "message",
+                    "role": "user",
+                    "content": [ { "type": "input_text", "text": text } ]
+                }
+            ],
+            "instructions": system_prompt,
+            "previous_response_id": previous_response_id,
+            "stream": true,
+            "max_output_tokens": generation.max_output_tokens,
+            "truncation": "auto"
+        });
+        apply_sampling(&mut body, generation);
+        body
+    }
+
+    /// POST to the Responses API. Any HTTP status is returned as-is.
+    async fn post(&self, body: &Value) -> Result<reqwest::Response, AiError> {
         let url = format!(
             "{}/openai/v1/responses?api-version=preview",
             self.endpoint.trim_end_matches('/'),
         );
 
-        let body = self.build_request_body(frame_data, system_prompt);
-
         let mut req = self
             .client
             .post(&url)
@@ -97,75 +134,62 @@ impl AiProvider for AzureVisionClient {
             req.header("api-key", &self.api_key)
         };
 
-        let response = req
-            .json(&body)
+        req.json(body)
             .send()
             .await
-            .map_err(|e| AiError::ConnectionError(e.to_string()))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_body = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "failed to read error body".into());
-
-            // If the previous_response_id is stale/expired, clear it and retry once
-            if status.as_u16() == 400 && error_body.contains("previous_response_not_found") {
-                log::warn!("Stale previous_response_id detected, clearing and retrying");
-                *self.previous_response_id.lock().unwrap() = None;
-                let retry_body = self.build_request_body(frame_data, system_prompt);
-                let mut retry_req = self
-                    .client
-                    .post(&url)
-                    .header("Content-Type", "application/json");
-                retry_req = if self.use_bearer {
-                    retry_req.header("Authorization", format!("Bearer {}", self.api_key))
-                } else {
-                    retry_req.header("api-key", &self.api_key)
-                };
-                let retry_response = retry_req
-                    .json(&retry_body)
-                    .send()
-                    .await
-                    .map_err(|e| AiError::ConnectionError(e.to_string()))?;
-                let retry_status = retry_response.status();
-                if !retry_status.is_success() {
-                    let retry_error = retry_response.text().await.unwrap_or_default();
-                    return Err(AiError::ConnectionError(format!(
-                        "HTTP {}: {}",
-                        retry_status, retry_error
-                    )));
-                }
-                return Ok(Box::new(ResponsesTextStream::new(
-                    retry_response,
-                    Arc::clone(&self.previous_response_id),
-                )));
-            }
+            .map_err(|e| AiError::ConnectionError(e.to_string()))
+    }
+}
 
-            if status.as_u16() == 401 || status.as_u16() == 403 {
-                return Err(AiError::AuthError(error_body));
-            }
-            if status.as_u16() == 429 {
-                return Err(AiError::RateLimited {
-                    retry_after_ms: 1000,
-                });
+#[async_trait]
+impl AiProvider for AzureVisionClient {
+    async fn analyze_frame(
+        &self,
+        request: VisionRequest<'_>,
+    ) -> Result<Box<dyn TextStream>, AiError> {
+        let body = self.build_request_body(&request);
+        let response = self.post(&body).await?;
+        if response.status().is_success() {
+            return Ok(Box::new(ResponsesTextStream::new(response)));
+        }
+
+        let failure = Failure::read(response).await;
+        // If the previous_response_id is stale/expired, retry once without it.
+        // The caller's thread continues from the new response.
+        if failure.status == 400
+            && failure.body.contains("previous_response_not_found")
+            && request.previous_response_id.is_some()
+        {
+            log::warn!("Stale previous_response_id detected, retrying without it");
+            let retry_body = self.build_request_body(&request.continuing(None));
+            let retry_response = self.post(&retry_body).await?;
+            if !retry_response.status().is_success() {
+                return Err(Failure::read(retry_response).await.into_error());
             }
-            return Err(AiError::ConnectionError(format!(
-                "HTTP {}: {}",
-                status, error_body
-            )));
+            return Ok(Box::new(ResponsesTextStream::new(retry_response)));
         }
+        Err(failure.into_error())
+    }
 
-        Ok(Box::new(ResponsesTextStream::new(
-            response,
-            Arc::clone(&self.previous_response_id),
-        )))
+    async fn follow_up(
+        &self,
+        previous_response_id: &str,
+        text: &str,
+        system_prompt: &str,
+        generation: &GenerationConfig,
+    ) -> Result<Box<dyn TextStream>, AiError> {
+        let body = self.build_follow_up_body(previous_response_id, text, system_prompt, generation);
+        let response = self.post(&body).await?;
+        if !response.status().is_success() {
+            return Err(Failure::read(response).await.into_error());
+        }
+        Ok(Box::new(ResponsesTextStream::new(response)))
     }
 
     async fn start_audio_stream(
         &self,
         _system_prompt: &str,
+        _generation: &GenerationConfig,
     ) -> Result<(Box<dyn AudioSession>, AudioResponseRx), AiError> {
         Err(AiError::ModelError(
             "Audio streaming not supported by AzureVisionClient".into(),
@@ -177,21 +201,128 @@ impl AiProvider for AzureVisionClient {
     }
 }
 
+/// Add the optional sampling fields; left out, the model uses its defaults.
+fn apply_sampling(body: &mut Value, generation: &GenerationConfig) {
+    let body = body.as_object_mut().unwrap();
+    if let Some(temperature) = generation.temperature {
+        body.insert("temperature".into(), json!(temperature));
+    }
+    if let Some(effort) = generation.reasoning_effort {
+        body.insert("reasoning".into(), json!({ "effort": effort.as_str() }));
+    }
+}
+
+/// A non-2xx response, read for error reporting.
+struct Failure {
+    status: u16,
+    retry_after_ms: u64,
+    body: String,
+}
+
+impl Failure {
+    async fn read(response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let headers = response.headers();
+        let retry_after_ms = retry_after_ms(
+            headers.get("retry-after-ms").and_then(|v| v.to_str().ok()),
+            headers.get("retry-after").and_then(|v| v.to_str().ok()),
+        );
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "failed to read error body".into());
+        Self {
+            status,
+            retry_after_ms,
+            body,
+        }
+    }
+
+    fn into_error(self) -> AiError {
+        match self.status {
+            401 | 403 => AiError::AuthError(self.body),
+            429 => AiError::RateLimited {
+                retry_after_ms: self.retry_after_ms,
+            },
+            status => api_error(status, &self.body),
+        }
+    }
+}
+
+/// An `ApiError` for a failed HTTP response, taking the code and message
+/// from Azure's `{"error":{"code","message"}}` body when it has one.
+fn api_error(status: u16, body: &str) -> AiError {
+    let parsed: Option<Value> = serde_json::from_str(body).ok();
+    let field = |name: &str| {
+        parsed
+            .as_ref()
+            .and_then(|v| v.pointer(&format!("/error/{name}")))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    AiError::ApiError {
+        status: Some(status),
+        code: field("code"),
+        message: field("message").unwrap_or_else(|| format!("HTTP {}: {}", status, body)),
+    }
+}
+
+/// Back-off to report for a 429, from Azure's `retry-after-ms` header or the
+/// standard `Retry-After` (seconds). Defaults to one second.
+pub(crate) fn retry_after_ms(retry_after_ms: Option<&str>, retry_after: Option<&str>) -> u64 {
+    retry_after_ms
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .or_else(|| {
+            retry_after
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .map(|secs| (secs * 1000.0) as u64)
+        })
+        .unwrap_or(1000)
+}
+
+/// Token counts from a `usage` object. The Responses API nests the breakdown
+/// under `input_tokens_details`/`output_tokens_details`, Realtime under
+/// `input_token_details`/`output_token_details`; either is read.
+pub(crate) fn parse_usage(usage: &Value) -> TokenUsage {
+    let count = |paths: &[&str]| {
+        paths
+            .iter()
+            .find_map(|p| usage.pointer(p).and_then(Value::as_u64))
+            .unwrap_or(0)
+    };
+    TokenUsage {
+        input_tokens: count(&["/input_tokens"]),
+        cached_input_tokens: count(&[
+            "/input_tokens_details/cached_tokens",
+            "/input_token_details/cached_tokens",
+        ]),
+        image_tokens: count(&["/input_token_details/image_tokens"]),
+        audio_input_tokens: count(&["/input_token_details/audio_tokens"]),
+        output_tokens: count(&["/output_tokens"]),
+        reasoning_tokens: count(&["/output_tokens_details/reasoning_tokens"]),
+        audio_output_tokens: count(&["/output_token_details/audio_tokens"]),
+    }
+}
+
 /// Streaming SSE reader for Azure OpenAI Responses API
 pub struct ResponsesTextStream {
     buffer: String,
     done: bool,
     response: Option<reqwest::Response>,
-    previous_response_id: Arc<Mutex<Option<String>>>,
+    /// Id of this response, once `response.created` has arrived.
+    response_id: Option<String>,
+    /// From `response.completed`, the stream's last event.
+    usage: Option<TokenUsage>,
 }
 
 impl ResponsesTextStream {
-    fn new(response: reqwest::Response, previous_response_id: Arc<Mutex<Option<String>>>) -> Self {
+    fn new(response: reqwest::Response) -> Self {
         Self {
             buffer: String::new(),
             done: false,
             response: Some(response),
-            previous_response_id,
+            response_id: None,
+            usage: None,
         }
     }
 }
@@ -200,13 +331,13 @@ impl ResponsesTextStream {
 /// Returns:
 ///   `ParseResult::Delta(text)` — a text chunk to emit
 ///   `ParseResult::ResponseId(id)` — capture the response ID
-///   `ParseResult::Done` — stream finished
+///   `ParseResult::Done(usage)` — stream finished, with the tokens it used
 ///   `ParseResult::Skip` — skip this event
 ///   `ParseResult::Error(e)` — parse error
 enum ParseResult {
     Delta(String),
     ResponseId(String),
-    Done,
+    Done(Option<TokenUsage>),
     Skip,
     Error(AiError),
 }
@@ -214,7 +345,7 @@ enum ParseResult {
 fn parse_sse_data(data: &str) -> ParseResult {
     let trimmed = data.trim();
     if trimmed == "[DONE]" {
-        return ParseResult::Done;
+        return ParseResult::Done(None);
     }
 
     let parsed: Value = match serde_json::from_str(trimmed) {
@@ -245,7 +376,11 @@ fn parse_sse_data(data: &str) -> ParseResult {
                 ParseResult::Skip
             }
         }
-        "response.output_text.done" | "response.completed" => ParseResult::Done,
+        // The text is complete at `output_text.done`, but the usage only
+        // comes with the response's final event.
+        "response.completed" | "response.incomplete" => {
+            ParseResult::Done(parsed.pointer("/response/usage").map(parse_usage))
+        }
         _ => ParseResult::Skip,
     }
 }
@@ -273,10 +408,11 @@ impl TextStream for ResponsesTextStream {
                     match parse_sse_data(data) {
                         ParseResult::Delta(text) => return Some(Ok(text)),
                         ParseResult::ResponseId(id) => {
-                            *self.previous_response_id.lock().unwrap() = Some(id);
+                            self.response_id = Some(id);
                             continue;
                         }
-                        ParseResult::Done => {
+                        ParseResult::Done(usage) => {
+                            self.usage = usage;
                             self.done = true;
                             return None;
                         }
@@ -312,11 +448,10 @@ impl TextStream for ResponsesTextStream {
                         if let Some(data) = remaining.strip_prefix("data: ") {
                             match parse_sse_data(data) {
                                 ParseResult::Delta(text) => return Some(Ok(text)),
-                                ParseResult::ResponseId(id) => {
-                                    *self.previous_response_id.lock().unwrap() = Some(id);
-                                }
+                                ParseResult::ResponseId(id) => self.response_id = Some(id),
+                                ParseResult::Done(usage) => self.usage = usage,
                                 ParseResult::Error(e) => return Some(Err(e)),
-                                _ => {}
+                                ParseResult::Skip => {}
                             }
                         }
                     }
@@ -332,11 +467,20 @@ impl TextStream for ResponsesTextStream {
             }
         }
     }
+
+    fn response_id(&self) -> Option<String> {
+        self.response_id.clone()
+    }
+
+    fn usage(&self) -> Option<TokenUsage> {
+        self.usage
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ai::{ImageDetail, ReasoningEffort};
 
     #[test]
     fn test_request_body_structure() {
@@ -347,7 +491,7 @@ mod tests {
             "default prompt",
         );
 
-        let body = client.build_request_body("base64data", "You are helpful.");
+        let body = client.build_request_body(&VisionRequest::new("base64data", "You are helpful."));
 
         // Verify top-level fields
         assert_eq!(body["stream"], json!(true));
@@ -371,6 +515,53 @@ mod tests {
         assert_eq!(content[0]["text"], "What do you see?");
         assert_eq!(content[1]["type"], "input_image");
         assert_eq!(content[1]["image_url"], "data:image/jpeg;base64,base64data");
+        assert_eq!(content[1]["detail"], "auto");
+
+        // Sampling fields are left to the model by default
+        assert!(body.get("temperature").is_none());
+        assert!(body.get("reasoning").is_none());
+    }
+
+    #[test]
+    fn test_request_body_generation_config() {
+        let client = AzureVisionClient::new(
+            "https://test.openai.azure.com",
+            "test-key",
+            "o4-mini",
+            "default prompt",
+        );
+        let generation = GenerationConfig {
+            max_output_tokens: 120,
+            temperature: Some(0.2),
+            reasoning_effort: Some(ReasoningEffort::Low),
+            image_detail: ImageDetail::Low,
+            user_text: "Anything to fix?".into(),
+            ..Default::default()
+        };
+
+        let body = client
+            .build_request_body(&VisionRequest::new("img", "prompt").with_generation(&generation));
+        assert_eq!(body["max_output_tokens"], 120);
+        assert!((body["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert_eq!(body["reasoning"]["effort"], "low");
+        let content = &body["input"][0]["content"];
+        assert_eq!(content[0]["text"], "Anything to fix?");
+        assert_eq!(content[1]["detail"], "low");
+
+        // A question replaces the configured text; the rest still applies
+        let asked = client.build_request_body(
+            &VisionRequest::new("img", "prompt")
+                .with_question(Some("What's this error?"))
+                .with_generation(&generation),
+        );
+        assert_eq!(
+            asked["input"][0]["content"][0]["text"],
+            "What's this error?"
+        );
+
+        let follow_up = client.build_follow_up_body("resp_1", "Why?", "prompt", &generation);
+        assert_eq!(follow_up["max_output_tokens"], 120);
+        assert_eq!(follow_up["reasoning"]["effort"], "low");
     }
 
     #[test]
@@ -382,12 +573,32 @@ mod tests {
             "default prompt",
         );
 
-        *client.previous_response_id.lock().unwrap() = Some("resp_abc123".into());
-
-        let body = client.build_request_body("img", "prompt");
+        let request = VisionRequest::new("img", "prompt").continuing(Some("resp_abc123"));
+        let body = client.build_request_body(&request);
         assert_eq!(body["previous_response_id"], "resp_abc123");
     }
 
+    #[test]
+    fn test_request_body_structured_format() {
+        let client = AzureVisionClient::new(
+            "https://test.openai.azure.com",
+            "test-key",
+            "gpt-4o",
+            "default prompt",
+        );
+
+        let text = client.build_request_body(&VisionRequest::new("img", "prompt"));
+        assert!(text.get("text").is_none());
+
+        let request = VisionRequest::new("img", "prompt").with_format(SuggestionFormat::Structured);
+        let body = client.build_request_body(&request);
+        let format = &body["text"]["format"];
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["name"], "next_action");
+        assert_eq!(format["strict"], true);
+        assert_eq!(format["schema"]["required"][0], "action");
+    }
+
     #[test]
     fn test_parse_sse_data_delta() {
         let data = r#"{"type":"response.output_text.delta","output_index":0,"content_index":0,"delta":"Hello"}"#;
@@ -409,7 +620,7 @@ mod tests {
     #[test]
     fn test_parse_sse_data_done() {
         let data = "[DONE]";
-        assert!(matches!(parse_sse_data(data), ParseResult::Done));
+        assert!(matches!(parse_sse_data(data), ParseResult::Done(None)));
     }
 
     #[test]
@@ -426,14 +637,33 @@ mod tests {
 
     #[test]
     fn test_parse_sse_data_output_text_done() {
+        // Usage is still to come in `response.completed`
         let data = r#"{"type":"response.output_text.done","text":"Hello world"}"#;
-        assert!(matches!(parse_sse_data(data), ParseResult::Done));
+        assert!(matches!(parse_sse_data(data), ParseResult::Skip));
     }
 
     #[test]
     fn test_parse_sse_data_completed() {
         let data = r#"{"type":"response.completed","response":{"id":"resp_abc123"}}"#;
-        assert!(matches!(parse_sse_data(data), ParseResult::Done));
+        assert!(matches!(parse_sse_data(data), ParseResult::Done(None)));
+    }
+
+    #[test]
+    fn test_parse_sse_data_completed_usage() {
+        let data = r#"{"type":"response.completed","response":{"id":"resp_abc123","usage":{"input_tokens":1200,"input_tokens_details":{"cached_tokens":1024},"output_tokens":80,"output_tokens_details":{"reasoning_tokens":32},"total_tokens":1280}}}"#;
+        match parse_sse_data(data) {
+            ParseResult::Done(Some(usage)) => assert_eq!(
+                usage,
+                TokenUsage {
+                    input_tokens: 1200,
+                    cached_input_tokens: 1024,
+                    output_tokens: 80,
+                    reasoning_tokens: 32,
+                    ..Default::default()
+                }
+            ),
+            other => panic!("expected Done, got {:?}", std::mem::discriminant(&other)),
+        }
     }
 
     #[test]
@@ -448,6 +678,17 @@ mod tests {
         assert!(matches!(parse_sse_data(data), ParseResult::Error(_)));
     }
 
+    #[test]
+    fn test_retry_after_parsing() {
+        assert_eq!(retry_after_ms(Some("250"), Some("5")), 250);
+        assert_eq!(retry_after_ms(None, Some("5")), 5000);
+        assert_eq!(
+            retry_after_ms(None, Some("Wed, 21 Oct 2015 07:28:00 GMT")),
+            1000
+        );
+        assert_eq!(retry_after_ms(None, None), 1000);
+    }
+
     #[test]
     fn test_client_name() {
         let client = AzureVisionClient::new("https://test.openai.azure.com", "k", "d", "p");
@@ -463,7 +704,7 @@ mod tests {
             if let Some(data) = line.strip_prefix("data: ") {
                 match parse_sse_data(data) {
                     ParseResult::Delta(text) => results.push(text),
-                    ParseResult::Done => break,
+                    ParseResult::Done(_) => break,
                     _ => {}
                 }
             }
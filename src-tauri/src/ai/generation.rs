@@ -0,0 +1,148 @@
+// generation.rs — Knobs for what each model request costs and how it answers.
+//
+// `GenerationConfig` lives in `Settings` and travels with every request, so
+// output length, sampling, reasoning effort, image detail and the audio
+// transcription model can be tuned without recompiling. Providers map the
+// fields onto their own APIs and ignore the ones that don't apply.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::BemeError;
+
+/// The Responses API rejects `max_output_tokens` below this.
+pub const MIN_OUTPUT_TOKENS: u32 = 16;
+
+/// How hard a reasoning model thinks before answering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+}
+
+/// Resolution the model looks at a frame in. `Low` is a fixed, small token
+/// cost; `High` tiles the image and costs more the larger it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    /// Let the model choose.
+    #[default]
+    Auto,
+    Low,
+    High,
+}
+
+impl ImageDetail {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImageDetail::Auto => "auto",
+            ImageDetail::Low => "low",
+            ImageDetail::High => "high",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GenerationConfig {
+    /// Longest reply, in tokens (for reasoning models, including reasoning).
+    pub max_output_tokens: u32,
+    /// Sampling temperature, 0–2; `None` uses the model's default. Reasoning
+    /// models don't accept one.
+    pub temperature: Option<f32>,
+    /// Only for reasoning models; `None` leaves it out of the request.
+    pub reasoning_effort: Option<ReasoningEffort>,
+    pub image_detail: ImageDetail,
+    /// Sent with each frame when the user hasn't asked anything specific.
+    pub user_text: String,
+    /// Model that transcribes the audio stream; empty turns transcription off.
+    pub transcription_model: String,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            max_output_tokens: 300,
+            temperature: None,
+            reasoning_effort: None,
+            image_detail: ImageDetail::Auto,
+            user_text: "What do you see?".into(),
+            transcription_model: "whisper-1".into(),
+        }
+    }
+}
+
+impl GenerationConfig {
+    /// Reject values every provider would refuse.
+    pub fn validate(&self) -> Result<(), BemeError> {
+        if self.max_output_tokens < MIN_OUTPUT_TOKENS {
+            return Err(BemeError::InvalidInput(format!(
+                "max output tokens must be at least {}",
+                MIN_OUTPUT_TOKENS
+            )));
+        }
+        if let Some(t) = self.temperature {
+            if !(0.0..=2.0).contains(&t) {
+                return Err(BemeError::InvalidInput(format!(
+                    "temperature must be between 0 and 2, not {}",
+                    t
+                )));
+            }
+        }
+        if self.user_text.trim().is_empty() {
+            return Err(BemeError::InvalidInput(
+                "the text sent with each frame is empty".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_their_defaults() {
+        let config: GenerationConfig =
+            serde_json::from_str(r#"{"maxOutputTokens": 120, "reasoningEffort": "low"}"#).unwrap();
+        assert_eq!(config.max_output_tokens, 120);
+        assert_eq!(config.reasoning_effort, Some(ReasoningEffort::Low));
+        assert_eq!(config.image_detail, ImageDetail::Auto);
+        assert_eq!(config.user_text, "What do you see?");
+        assert_eq!(config.transcription_model, "whisper-1");
+        assert!(GenerationConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        let base = GenerationConfig::default();
+        for bad in [
+            GenerationConfig {
+                max_output_tokens: 8,
+                ..base.clone()
+            },
+            GenerationConfig {
+                temperature: Some(2.5),
+                ..base.clone()
+            },
+            GenerationConfig {
+                user_text: "  ".into(),
+                ..base.clone()
+            },
+        ] {
+            assert!(bad.validate().is_err(), "{bad:?}");
+        }
+    }
+}
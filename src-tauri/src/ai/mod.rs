@@ -3,7 +3,12 @@ use async_trait::async_trait;
 
 pub mod azure_audio;
 pub mod azure_vision;
+pub mod generation;
+pub mod scripted;
+pub mod structured;
 pub mod types;
+pub use generation::{GenerationConfig, ImageDetail, ReasoningEffort};
+pub use structured::SuggestionFormat;
 pub use types::*;
 
 /// Trait for AI providers (Azure OpenAI, Gemini, etc.)
@@ -11,20 +16,35 @@ pub use types::*;
 #[async_trait]
 pub trait AiProvider: Send + Sync {
     /// Analyze a screen capture frame and return a text suggestion.
-    /// `frame_data` is base64-encoded JPEG.
-    /// `system_prompt` is the user's configured prompt.
+    /// Providers keep no conversation state of their own: the request says
+    /// which earlier response (if any) to continue.
     /// Returns a stream of text chunks (for SSE/streaming responses).
     async fn analyze_frame(
         &self,
-        frame_data: &str,
-        system_prompt: &str,
+        request: VisionRequest<'_>,
     ) -> Result<Box<dyn TextStream>, AiError>;
 
+    /// Continue the conversation from an earlier response with a text-only
+    /// user message (e.g. "why?"), streaming the reply.
+    async fn follow_up(
+        &self,
+        _previous_response_id: &str,
+        _text: &str,
+        _system_prompt: &str,
+        _generation: &GenerationConfig,
+    ) -> Result<Box<dyn TextStream>, AiError> {
+        Err(AiError::ModelError(format!(
+            "{} does not support follow-up questions",
+            self.name()
+        )))
+    }
+
     /// Start an audio streaming session.
     /// Returns a session handle for sending audio, plus a receiver for text responses.
     async fn start_audio_stream(
         &self,
         system_prompt: &str,
+        generation: &GenerationConfig,
     ) -> Result<(Box<dyn AudioSession>, AudioResponseRx), AiError>;
 
     /// Provider name for logging/display
@@ -0,0 +1,647 @@
+// scripted.rs — Fixture-driven AiProvider for tests and demos.
+//
+// Replays canned responses from a JSON script instead of calling a model, so
+// the full app (and integration tests) can run without Azure credentials.
+// A script looks like:
+//
+//   {
+//     "vision": [
+//       { "chunks": ["Click ", "Save."], "chunkDelayMs": 50 },
+//       { "error": { "kind": "rateLimited", "retryAfterMs": 2000 } },
+//       { "chunks": ["Half an "], "error": { "kind": "connection", "message": "reset" } }
+//     ],
+//     "audio": {
+//       "turns": [ { "afterChunks": 8, "chunks": ["Ask about ", "pricing."] } ]
+//     }
+//   }
+//
+// Vision responses (and follow-ups) are used in order and wrap around; the
+// n-th one reports response id "scripted_n". An `error` with no
+// `chunks` fails the request itself; with chunks it is raised mid-stream.
+// Error kinds: connection, auth, rateLimited, model, invalidResponse, and
+// api (with optional `status` and `code`).
+// Audio turns fire once the session has received `afterChunks` more audio
+// chunks: the turn's `transcript` (if any), its chunks as deltas, then done.
+// Responses and turns may report a `usage` object, with the API's field names
+// (`{ "input_tokens": 900, "output_tokens": 40 }`).
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::{
+    AiError, AiProvider, AudioResponse, AudioResponseRx, AudioSession, GenerationConfig,
+    ImageDetail, TextStream, TokenUsage, VisionRequest,
+};
+
+/// Provider id used in settings to select this provider.
+pub const PROVIDER_ID: &str = "scripted";
+
+/// Requests kept in the request log; older ones are dropped.
+const REQUEST_LOG_LEN: usize = 100;
+
+/// Top-level fixture.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Script {
+    pub vision: Vec<ScriptedResponse>,
+    pub audio: ScriptedAudio,
+}
+
+/// One canned vision response.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScriptedResponse {
+    /// Wait before the request resolves.
+    pub delay_ms: u64,
+    /// Text chunks yielded by the stream.
+    pub chunks: Vec<String>,
+    /// Wait before each chunk.
+    pub chunk_delay_ms: u64,
+    /// Fail the request (no chunks) or the stream after the chunks.
+    pub error: Option<ScriptedError>,
+    /// Tokens reported once the stream ends.
+    pub usage: Option<TokenUsage>,
+}
+
+/// Audio session behaviour.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScriptedAudio {
+    /// Fail `start_audio_stream` with this error.
+    pub connect_error: Option<ScriptedError>,
+    /// Responses played in order as audio arrives.
+    pub turns: Vec<ScriptedAudioTurn>,
+}
+
+/// One canned audio response turn.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScriptedAudioTurn {
+    /// Audio chunks to receive (since the previous turn) before responding.
+    pub after_chunks: usize,
+    /// What was "said" in the audio, reported before the reply.
+    pub transcript: Option<String>,
+    pub chunks: Vec<String>,
+    pub chunk_delay_ms: u64,
+    /// Sent after the chunks instead of the turn-done signal; ends the session.
+    pub error: Option<ScriptedError>,
+    /// Reported just before the turn-done signal.
+    pub usage: Option<TokenUsage>,
+}
+
+/// Serialized form of [`AiError`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ScriptedError {
+    Connection {
+        message: String,
+    },
+    Auth {
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    RateLimited {
+        retry_after_ms: u64,
+    },
+    Model {
+        message: String,
+    },
+    InvalidResponse {
+        message: String,
+    },
+    Api {
+        #[serde(default)]
+        status: Option<u16>,
+        #[serde(default)]
+        code: Option<String>,
+        message: String,
+    },
+}
+
+impl From<&ScriptedError> for AiError {
+    fn from(e: &ScriptedError) -> Self {
+        match e {
+            ScriptedError::Connection { message } => AiError::ConnectionError(message.clone()),
+            ScriptedError::Auth { message } => AiError::AuthError(message.clone()),
+            ScriptedError::RateLimited { retry_after_ms } => AiError::RateLimited {
+                retry_after_ms: *retry_after_ms,
+            },
+            ScriptedError::Model { message } => AiError::ModelError(message.clone()),
+            ScriptedError::InvalidResponse { message } => AiError::InvalidResponse(message.clone()),
+            ScriptedError::Api {
+                status,
+                code,
+                message,
+            } => AiError::ApiError {
+                status: *status,
+                code: code.clone(),
+                message: message.clone(),
+            },
+        }
+    }
+}
+
+/// What a request to the provider carried, as kept in its request log.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptedRequest {
+    Frame {
+        previous_response_id: Option<String>,
+        detail: ImageDetail,
+        system_prompt: String,
+        generation: GenerationConfig,
+    },
+    FollowUp {
+        previous_response_id: String,
+        text: String,
+        system_prompt: String,
+        generation: GenerationConfig,
+    },
+    AudioSession {
+        system_prompt: String,
+        generation: GenerationConfig,
+    },
+}
+
+impl ScriptedRequest {
+    pub fn system_prompt(&self) -> &str {
+        match self {
+            Self::Frame { system_prompt, .. }
+            | Self::FollowUp { system_prompt, .. }
+            | Self::AudioSession { system_prompt, .. } => system_prompt,
+        }
+    }
+
+    pub fn generation(&self) -> &GenerationConfig {
+        match self {
+            Self::Frame { generation, .. }
+            | Self::FollowUp { generation, .. }
+            | Self::AudioSession { generation, .. } => generation,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScriptedProvider {
+    script: Script,
+    /// Number of vision requests (frames and follow-ups) so far; picks the next response.
+    frames: AtomicUsize,
+    /// Audio chunks received across all sessions.
+    audio_chunks: Arc<AtomicUsize>,
+    /// The latest requests, oldest first, for tests to inspect.
+    requests: Mutex<VecDeque<ScriptedRequest>>,
+}
+
+impl ScriptedProvider {
+    pub fn new(script: Script) -> Self {
+        Self {
+            script,
+            frames: AtomicUsize::new(0),
+            audio_chunks: Arc::new(AtomicUsize::new(0)),
+            requests: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Parse a script from JSON text.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let script = serde_json::from_str(json).map_err(|e| format!("Invalid script: {e}"))?;
+        Ok(Self::new(script))
+    }
+
+    /// Load a script from a JSON file.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    /// How many vision requests (frames and follow-ups) have been made.
+    pub fn frames_analyzed(&self) -> usize {
+        self.frames.load(Ordering::SeqCst)
+    }
+
+    /// How many audio chunks have been received.
+    pub fn audio_chunks_received(&self) -> usize {
+        self.audio_chunks.load(Ordering::SeqCst)
+    }
+
+    /// The latest requests (frames, follow-ups and audio sessions), in
+    /// order. Only the last `REQUEST_LOG_LEN` are kept.
+    pub fn requests(&self) -> Vec<ScriptedRequest> {
+        self.requests.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// `(previous_response_id, text)` of each follow-up in the log.
+    pub fn follow_ups(&self) -> Vec<(String, String)> {
+        self.requests()
+            .into_iter()
+            .filter_map(|r| match r {
+                ScriptedRequest::FollowUp {
+                    previous_response_id,
+                    text,
+                    ..
+                } => Some((previous_response_id, text)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The system prompt each request in the log was sent with.
+    pub fn system_prompts(&self) -> Vec<String> {
+        self.requests()
+            .iter()
+            .map(|r| r.system_prompt().to_string())
+            .collect()
+    }
+
+    /// The generation config each request in the log was sent with.
+    pub fn generations(&self) -> Vec<GenerationConfig> {
+        self.requests()
+            .iter()
+            .map(|r| r.generation().clone())
+            .collect()
+    }
+
+    /// The response each frame in the log continued from.
+    pub fn frame_parents(&self) -> Vec<Option<String>> {
+        self.requests()
+            .into_iter()
+            .filter_map(|r| match r {
+                ScriptedRequest::Frame {
+                    previous_response_id,
+                    ..
+                } => Some(previous_response_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The detail level each frame in the log was sent at.
+    pub fn frame_details(&self) -> Vec<ImageDetail> {
+        self.requests()
+            .into_iter()
+            .filter_map(|r| match r {
+                ScriptedRequest::Frame { detail, .. } => Some(detail),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn log_request(&self, request: ScriptedRequest) {
+        let mut log = self.requests.lock().unwrap();
+        if log.len() == REQUEST_LOG_LEN {
+            log.pop_front();
+        }
+        log.push_back(request);
+    }
+
+    /// Play the next vision response.
+    async fn next_response(&self) -> Result<Box<dyn TextStream>, AiError> {
+        let n = self.frames.fetch_add(1, Ordering::SeqCst);
+        if self.script.vision.is_empty() {
+            return Ok(Box::new(ScriptedTextStream::default()));
+        }
+        let response = self.script.vision[n % self.script.vision.len()].clone();
+
+        sleep_ms(response.delay_ms).await;
+        if let (true, Some(e)) = (response.chunks.is_empty(), &response.error) {
+            return Err(e.into());
+        }
+        Ok(Box::new(ScriptedTextStream {
+            chunks: response.chunks.into(),
+            chunk_delay_ms: response.chunk_delay_ms,
+            error: response.error,
+            response_id: Some(format!("scripted_{}", n + 1)),
+            usage: response.usage,
+        }))
+    }
+}
+
+// ── AiProvider impl ─────────────────────────────────────────────────
+
+#[async_trait]
+impl AiProvider for ScriptedProvider {
+    async fn analyze_frame(
+        &self,
+        request: VisionRequest<'_>,
+    ) -> Result<Box<dyn TextStream>, AiError> {
+        self.log_request(ScriptedRequest::Frame {
+            previous_response_id: request.previous_response_id.map(str::to_string),
+            detail: request.image_detail(),
+            system_prompt: request.system_prompt.to_string(),
+            generation: request.generation.clone(),
+        });
+        self.next_response().await
+    }
+
+    async fn follow_up(
+        &self,
+        previous_response_id: &str,
+        text: &str,
+        system_prompt: &str,
+        generation: &GenerationConfig,
+    ) -> Result<Box<dyn TextStream>, AiError> {
+        self.log_request(ScriptedRequest::FollowUp {
+            previous_response_id: previous_response_id.to_string(),
+            text: text.to_string(),
+            system_prompt: system_prompt.to_string(),
+            generation: generation.clone(),
+        });
+        self.next_response().await
+    }
+
+    async fn start_audio_stream(
+        &self,
+        system_prompt: &str,
+        generation: &GenerationConfig,
+    ) -> Result<(Box<dyn AudioSession>, AudioResponseRx), AiError> {
+        self.log_request(ScriptedRequest::AudioSession {
+            system_prompt: system_prompt.to_string(),
+            generation: generation.clone(),
+        });
+        if let Some(e) = &self.script.audio.connect_error {
+            return Err(e.into());
+        }
+        let (tx, rx) = mpsc::channel(64);
+        let session = ScriptedAudioSession {
+            turns: self.script.audio.turns.clone().into(),
+            since_last_turn: 0,
+            received: Arc::clone(&self.audio_chunks),
+            tx: Some(tx),
+        };
+        Ok((Box::new(session), rx))
+    }
+
+    fn name(&self) -> &str {
+        "Scripted"
+    }
+}
+
+// ── TextStream impl ─────────────────────────────────────────────────
+
+#[derive(Default)]
+struct ScriptedTextStream {
+    chunks: VecDeque<String>,
+    chunk_delay_ms: u64,
+    error: Option<ScriptedError>,
+    response_id: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+#[async_trait]
+impl TextStream for ScriptedTextStream {
+    async fn next_chunk(&mut self) -> Option<Result<String, AiError>> {
+        if let Some(chunk) = self.chunks.pop_front() {
+            sleep_ms(self.chunk_delay_ms).await;
+            return Some(Ok(chunk));
+        }
+        let e = self.error.take()?;
+        // Like the real API, a stream that fails reports no usage
+        self.usage = None;
+        Some(Err((&e).into()))
+    }
+
+    fn response_id(&self) -> Option<String> {
+        self.response_id.clone()
+    }
+
+    fn usage(&self) -> Option<TokenUsage> {
+        self.usage
+    }
+}
+
+// ── AudioSession impl ───────────────────────────────────────────────
+
+struct ScriptedAudioSession {
+    turns: VecDeque<ScriptedAudioTurn>,
+    since_last_turn: usize,
+    received: Arc<AtomicUsize>,
+    tx: Option<mpsc::Sender<Result<AudioResponse, AiError>>>,
+}
+
+#[async_trait]
+impl AudioSession for ScriptedAudioSession {
+    async fn send_audio(&mut self, _audio_data: &[u8]) -> Result<(), AiError> {
+        let Some(tx) = self.tx.as_ref() else {
+            return Err(AiError::ConnectionError("Session closed".into()));
+        };
+        self.received.fetch_add(1, Ordering::SeqCst);
+        self.since_last_turn += 1;
+
+        let due = self
+            .turns
+            .front()
+            .is_some_and(|t| self.since_last_turn >= t.after_chunks);
+        if due {
+            let turn = self.turns.pop_front().unwrap_or_default();
+            self.since_last_turn = 0;
+            let ends_session = turn.error.is_some();
+            play_turn(turn, tx);
+            if ends_session {
+                self.tx = None;
+            }
+        }
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<(), AiError> {
+        self.tx = None;
+        Ok(())
+    }
+}
+
+/// Push one turn's chunks (and its done signal or error) onto the response
+/// channel. Delays happen in a spawned task so `send_audio` never stalls.
+fn play_turn(turn: ScriptedAudioTurn, tx: &mpsc::Sender<Result<AudioResponse, AiError>>) {
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        if let Some(transcript) = turn.transcript {
+            if tx
+                .send(Ok(AudioResponse::Transcript(transcript)))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+        for chunk in turn.chunks {
+            sleep_ms(turn.chunk_delay_ms).await;
+            if tx.send(Ok(AudioResponse::Delta(chunk))).await.is_err() {
+                return;
+            }
+        }
+        if let (None, Some(usage)) = (&turn.error, turn.usage) {
+            if tx.send(Ok(AudioResponse::Usage(usage))).await.is_err() {
+                return;
+            }
+        }
+        let last = match &turn.error {
+            Some(e) => Err(e.into()),
+            None => Ok(AudioResponse::Done),
+        };
+        let _ = tx.send(last).await;
+    });
+}
+
+async fn sleep_ms(ms: u64) {
+    if ms > 0 {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn collect(stream: &mut Box<dyn TextStream>) -> Vec<Result<String, String>> {
+        let mut out = Vec::new();
+        while let Some(item) = stream.next_chunk().await {
+            out.push(item.map_err(|e| e.to_string()));
+        }
+        out
+    }
+
+    #[test]
+    fn parses_error_kinds() {
+        let p = ScriptedProvider::from_json(
+            r#"{ "vision": [
+                { "error": { "kind": "auth", "message": "bad key" } },
+                { "error": { "kind": "rateLimited", "retryAfterMs": 1500 } }
+            ] }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            AiError::from(p.script.vision[0].error.as_ref().unwrap()),
+            AiError::AuthError(m) if m == "bad key"
+        ));
+        assert!(matches!(
+            AiError::from(p.script.vision[1].error.as_ref().unwrap()),
+            AiError::RateLimited {
+                retry_after_ms: 1500
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_script() {
+        let err = ScriptedProvider::from_json(r#"{ "vision": 3 }"#).unwrap_err();
+        assert!(err.starts_with("Invalid script"));
+    }
+
+    #[tokio::test]
+    async fn vision_responses_cycle_in_order() {
+        let p = ScriptedProvider::from_json(
+            r#"{ "vision": [ { "chunks": ["a", "b"] }, { "chunks": ["c"] } ] }"#,
+        )
+        .unwrap();
+        for expected in [vec!["a", "b"], vec!["c"], vec!["a", "b"]] {
+            let mut s = p.analyze_frame(VisionRequest::new("", "")).await.unwrap();
+            let got: Vec<String> = collect(&mut s)
+                .await
+                .into_iter()
+                .map(|r| r.unwrap())
+                .collect();
+            assert_eq!(got, expected);
+        }
+        assert_eq!(p.frames_analyzed(), 3);
+    }
+
+    #[tokio::test]
+    async fn request_and_mid_stream_errors() {
+        let p = ScriptedProvider::from_json(
+            r#"{ "vision": [
+                { "error": { "kind": "rateLimited", "retryAfterMs": 10 } },
+                { "chunks": ["partial"], "error": { "kind": "connection", "message": "reset" } }
+            ] }"#,
+        )
+        .unwrap();
+
+        let err = p
+            .analyze_frame(VisionRequest::new("", ""))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, AiError::RateLimited { retry_after_ms: 10 }));
+
+        let mut s = p.analyze_frame(VisionRequest::new("", "")).await.unwrap();
+        let items = collect(&mut s).await;
+        assert_eq!(items[0], Ok("partial".to_string()));
+        assert!(items[1].as_ref().unwrap_err().contains("reset"));
+        assert_eq!(items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn audio_turns_fire_after_chunks() {
+        let p = ScriptedProvider::from_json(
+            r#"{ "audio": { "turns": [
+                { "afterChunks": 2, "transcript": "hello?", "chunks": ["hi"] },
+                { "afterChunks": 1, "chunks": ["bye"] }
+            ] } }"#,
+        )
+        .unwrap();
+        let (mut session, mut rx) = p
+            .start_audio_stream("", &GenerationConfig::default())
+            .await
+            .unwrap();
+
+        session.send_audio(&[0; 4]).await.unwrap();
+        assert!(rx.try_recv().is_err());
+        session.send_audio(&[0; 4]).await.unwrap();
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap(),
+            AudioResponse::Transcript("hello?".into())
+        );
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap(),
+            AudioResponse::Delta("hi".into())
+        );
+        assert_eq!(rx.recv().await.unwrap().unwrap(), AudioResponse::Done);
+
+        session.send_audio(&[0; 4]).await.unwrap();
+        assert_eq!(
+            rx.recv().await.unwrap().unwrap(),
+            AudioResponse::Delta("bye".into())
+        );
+        assert_eq!(rx.recv().await.unwrap().unwrap(), AudioResponse::Done);
+
+        session.close().await.unwrap();
+        assert!(rx.recv().await.is_none());
+        assert!(session.send_audio(&[0]).await.is_err());
+        assert_eq!(p.audio_chunks_received(), 3);
+    }
+
+    #[tokio::test]
+    async fn audio_connect_error() {
+        let p = ScriptedProvider::from_json(
+            r#"{ "audio": { "connectError": { "kind": "auth", "message": "denied" } } }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            p.start_audio_stream("", &GenerationConfig::default())
+                .await
+                .err()
+                .unwrap(),
+            AiError::AuthError(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn request_log_keeps_only_the_latest() {
+        let p = ScriptedProvider::from_json("{}").unwrap();
+        let generation = GenerationConfig::default();
+        for i in 0..REQUEST_LOG_LEN + 5 {
+            p.follow_up("resp", &i.to_string(), "prompt", &generation)
+                .await
+                .unwrap();
+        }
+        let follow_ups = p.follow_ups();
+        assert_eq!(follow_ups.len(), REQUEST_LOG_LEN);
+        assert_eq!(follow_ups[0].1, "5");
+        assert_eq!(p.frames_analyzed(), REQUEST_LOG_LEN + 5);
+    }
+}
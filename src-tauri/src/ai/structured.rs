@@ -0,0 +1,146 @@
+// structured.rs — Suggestions as typed fields instead of free prose.
+//
+// In `SuggestionFormat::Structured` the vision request carries a JSON schema
+// (Responses API `text.format`), so the model answers with one object per
+// frame: the action, why, how sure it is, how urgent, and which app it's
+// about. `StructuredSuggestion::parse` checks and normalizes that object.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::AiError;
+
+/// Name the schema is registered under in the request.
+pub const SCHEMA_NAME: &str = "next_action";
+
+/// How vision answers are requested and reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SuggestionFormat {
+    /// Free prose, streamed as it arrives.
+    #[default]
+    Text,
+    /// One JSON object matching [`json_schema`], reported when complete.
+    Structured,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    Medium,
+    High,
+}
+
+/// One suggested next action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructuredSuggestion {
+    pub action: String,
+    pub rationale: String,
+    /// 0.0–1.0.
+    pub confidence: f64,
+    pub urgency: Urgency,
+    /// Application the action is for, when the model can tell.
+    pub target_app: Option<String>,
+}
+
+impl StructuredSuggestion {
+    /// Parse the model's JSON answer. Trims the text fields, clamps
+    /// `confidence` to 0–1 and treats a blank `target_app` as unknown.
+    pub fn parse(text: &str) -> Result<Self, AiError> {
+        let mut s: Self = serde_json::from_str(text.trim())
+            .map_err(|e| AiError::InvalidResponse(format!("structured suggestion: {}", e)))?;
+        s.action = s.action.trim().to_string();
+        s.rationale = s.rationale.trim().to_string();
+        if s.action.is_empty() {
+            return Err(AiError::InvalidResponse(
+                "structured suggestion has no action".into(),
+            ));
+        }
+        s.confidence = if s.confidence.is_finite() {
+            s.confidence.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        s.target_app = s
+            .target_app
+            .map(|app| app.trim().to_string())
+            .filter(|app| !app.is_empty());
+        Ok(s)
+    }
+}
+
+/// Strict JSON schema for [`StructuredSuggestion`].
+pub fn json_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "action": {
+                "type": "string",
+                "description": "The single best next action, as a short imperative sentence."
+            },
+            "rationale": {
+                "type": "string",
+                "description": "Why this action, based on what is on screen."
+            },
+            "confidence": {
+                "type": "number",
+                "description": "How sure you are this is the right action, from 0 to 1."
+            },
+            "urgency": {
+                "type": "string",
+                "enum": ["low", "medium", "high"],
+                "description": "How soon the user should act."
+            },
+            "target_app": {
+                "type": ["string", "null"],
+                "description": "The application the action is for, or null if unclear."
+            }
+        },
+        "required": ["action", "rationale", "confidence", "urgency", "target_app"],
+        "additionalProperties": false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_normalizes_an_answer() {
+        let s = StructuredSuggestion::parse(
+            r#" {"action":" Press Ctrl+S ","rationale":"Unsaved changes.","confidence":1.4,"urgency":"high","target_app":"  "} "#,
+        )
+        .unwrap();
+        assert_eq!(s.action, "Press Ctrl+S");
+        assert_eq!(s.confidence, 1.0);
+        assert_eq!(s.urgency, Urgency::High);
+        assert_eq!(s.target_app, None);
+    }
+
+    #[test]
+    fn rejects_malformed_or_empty_answers() {
+        assert!(matches!(
+            StructuredSuggestion::parse("Press Ctrl+S"),
+            Err(AiError::InvalidResponse(_))
+        ));
+        assert!(StructuredSuggestion::parse(
+            r#"{"action":"","rationale":"","confidence":0.5,"urgency":"low","target_app":null}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn schema_requires_every_field() {
+        let schema = json_schema();
+        let props = schema["properties"].as_object().unwrap();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        assert_eq!(required.len(), props.len());
+        assert!(props.keys().all(|k| required.contains(&k.as_str())));
+    }
+}
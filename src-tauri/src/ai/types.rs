@@ -1,9 +1,61 @@
 #![allow(dead_code)]
 use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
 use tokio::sync::mpsc;
 
-/// Type alias for the channel that delivers parsed text responses from an audio session.
-pub type AudioResponseRx = mpsc::Receiver<Result<String, AiError>>;
+use super::generation::{GenerationConfig, ImageDetail};
+use super::structured::SuggestionFormat;
+
+/// What `VisionRequest::new` asks with until told otherwise.
+static DEFAULT_GENERATION: LazyLock<GenerationConfig> = LazyLock::new(GenerationConfig::default);
+
+/// Type alias for the channel that delivers parsed responses from an audio session.
+pub type AudioResponseRx = mpsc::Receiver<Result<AudioResponse, AiError>>;
+
+/// One item from an audio session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioResponse {
+    /// Part of the model's reply.
+    Delta(String),
+    /// The reply turn is complete.
+    Done,
+    /// What was said in the audio the model just heard.
+    Transcript(String),
+    /// Tokens the reply turn used; sent just before its `Done`.
+    Usage(TokenUsage),
+}
+
+/// Tokens one response used, from the API's `usage` object. Input and output
+/// counts are totals; the other fields say how much of them was cached,
+/// image, audio or reasoning tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    /// Not broken out by the Responses API; frames fill in beme's estimate.
+    pub image_tokens: u64,
+    pub audio_input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub audio_output_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.image_tokens += other.image_tokens;
+        self.audio_input_tokens += other.audio_input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+        self.audio_output_tokens += other.audio_output_tokens;
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
 
 /// A previous interaction for context
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +92,98 @@ pub enum AiError {
     ModelError(String),
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
+    /// The service rejected the request: an HTTP error status, or a
+    /// Realtime `error` event with its error code.
+    #[error("API error{}: {message}", code.as_ref().map(|c| format!(" [{c}]")).unwrap_or_default())]
+    ApiError {
+        status: Option<u16>,
+        code: Option<String>,
+        message: String,
+    },
+}
+
+impl AiError {
+    /// HTTP status behind the error, when known.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            AiError::RateLimited { .. } => Some(429),
+            AiError::ApiError { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// The service's own error code (e.g. Realtime's `rate_limit_exceeded`).
+    pub fn provider_code(&self) -> Option<&str> {
+        match self {
+            AiError::ApiError { code, .. } => code.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// One frame to analyze, and where it sits in the conversation.
+#[derive(Debug, Clone, Copy)]
+pub struct VisionRequest<'a> {
+    /// Base64-encoded JPEG.
+    pub frame_data: &'a str,
+    /// The user's configured prompt.
+    pub system_prompt: &'a str,
+    /// What the user asked about this frame, if anything; providers fall
+    /// back to `generation.user_text`.
+    pub question: Option<&'a str>,
+    /// Response to continue from, so the model sees earlier frames. `None`
+    /// starts a fresh conversation.
+    pub previous_response_id: Option<&'a str>,
+    /// Free prose or a structured answer.
+    pub format: SuggestionFormat,
+    /// Output length, sampling and image detail.
+    pub generation: &'a GenerationConfig,
+    /// Detail level for this frame only, instead of `generation.image_detail`.
+    pub detail: Option<ImageDetail>,
+}
+
+impl<'a> VisionRequest<'a> {
+    pub fn new(frame_data: &'a str, system_prompt: &'a str) -> Self {
+        Self {
+            frame_data,
+            system_prompt,
+            question: None,
+            previous_response_id: None,
+            format: SuggestionFormat::Text,
+            generation: &DEFAULT_GENERATION,
+            detail: None,
+        }
+    }
+
+    pub fn with_question(mut self, question: Option<&'a str>) -> Self {
+        self.question = question;
+        self
+    }
+
+    pub fn continuing(mut self, previous_response_id: Option<&'a str>) -> Self {
+        self.previous_response_id = previous_response_id;
+        self
+    }
+
+    pub fn with_format(mut self, format: SuggestionFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_generation(mut self, generation: &'a GenerationConfig) -> Self {
+        self.generation = generation;
+        self
+    }
+
+    pub fn with_detail(mut self, detail: Option<ImageDetail>) -> Self {
+        self.detail = detail;
+        self
+    }
+
+    /// The detail level the frame is sent at.
+    pub fn image_detail(&self) -> ImageDetail {
+        self.detail.unwrap_or(self.generation.image_detail)
+    }
 }
 
 /// Trait for streaming text responses (chunk by chunk)
@@ -47,6 +191,18 @@ pub enum AiError {
 pub trait TextStream: Send {
     /// Get the next text chunk. Returns None when the stream is complete.
     async fn next_chunk(&mut self) -> Option<Result<String, AiError>>;
+
+    /// The provider's id for this response, for continuing the conversation
+    /// with `AiProvider::follow_up`. Known once the stream has started.
+    fn response_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Tokens the response used, if the provider reported them. Known once
+    /// `next_chunk` has returned `None`.
+    fn usage(&self) -> Option<TokenUsage> {
+        None
+    }
 }
 
 /// Trait for bidirectional audio sessions
@@ -0,0 +1,259 @@
+//! beme-cli — headless screen/audio capture with suggestions on stdout.
+//!
+//! Uses the same `settings.toml` as the app (or `--settings PATH`) and the
+//! same capture → StreamManager pipeline; only the event sink differs.
+//!
+//!   beme-cli                 # screen capture, plain text
+//!   beme-cli --audio --json  # audio only, one JSON object per event
+//!
+//! Ctrl-C stops capture and exits.
+
+use beme_lib::capture::audio::AudioCapture;
+use beme_lib::capture::screen::ScreenCapture;
+use beme_lib::error::BemeError;
+use beme_lib::events::EventSink;
+use beme_lib::limits::LimitHit;
+use beme_lib::settings::{self, Settings};
+use beme_lib::stream_manager::StreamManager;
+use serde_json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+const USAGE: &str = "\
+Usage: beme-cli [OPTIONS]
+
+Options:
+  --screen           Capture the screen (default when neither is given)
+  --audio            Capture system audio
+  --json             Print events as JSON lines instead of plain text
+  --settings PATH    Settings file (default: the app's settings.toml)
+  --monitor ID       Monitor to capture (see the app's monitor list)
+  --device NAME      Audio output device to capture from (default: the app's)
+  -h, --help         Show this help";
+
+#[derive(Debug, Default)]
+struct Args {
+    screen: bool,
+    audio: bool,
+    json: bool,
+    settings: Option<PathBuf>,
+    monitor: Option<u32>,
+    device: Option<String>,
+}
+
+fn parse_args(mut argv: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut args = Args::default();
+    while let Some(arg) = argv.next() {
+        let mut value = |name: &str| argv.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--screen" => args.screen = true,
+            "--audio" => args.audio = true,
+            "--json" => args.json = true,
+            "--settings" => args.settings = Some(PathBuf::from(value(&arg)?)),
+            "--monitor" => {
+                let id = value(&arg)?;
+                args.monitor = Some(id.parse().map_err(|_| format!("bad monitor id: {}", id))?);
+            }
+            "--device" => args.device = Some(value(&arg)?),
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("unknown argument: {}", other)),
+        }
+    }
+    if !args.screen && !args.audio {
+        args.screen = true;
+    }
+    Ok(Some(args))
+}
+
+/// Prints `ai:*` events to the terminal. Capture events (frames, levels,
+/// chunks) are internal plumbing here and are dropped.
+struct StdoutSink {
+    json: bool,
+    /// Id of the suggestion currently being streamed in plain mode.
+    current: Mutex<Option<u64>>,
+}
+
+impl EventSink for StdoutSink {
+    fn emit(&self, event: &str, payload: Value) {
+        if !event.starts_with("ai:") {
+            return;
+        }
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+
+        if self.json {
+            let line = serde_json::json!({ "event": event, "payload": payload });
+            let _ = writeln!(out, "{}", line);
+            let _ = out.flush();
+            return;
+        }
+
+        match event {
+            "ai:suggestion" => {
+                let id = payload["id"].as_u64();
+                let mut current = self.current.lock().unwrap();
+                if *current != id {
+                    if current.is_some() {
+                        let _ = writeln!(out);
+                    }
+                    let source = payload["source"].as_str().unwrap_or("ai");
+                    let _ = write!(out, "[{}] ", source);
+                    *current = id;
+                }
+                let _ = write!(out, "{}", payload["text"].as_str().unwrap_or(""));
+                if payload["done"].as_bool() == Some(true) {
+                    let _ = writeln!(out);
+                    *current = None;
+                }
+                let _ = out.flush();
+            }
+            "ai:error" => {
+                eprintln!("error: {}", payload["message"].as_str().unwrap_or(""));
+            }
+            "ai:audio-status" => {
+                let status = payload["status"].as_str().unwrap_or("");
+                match payload["message"].as_str() {
+                    Some(message) => eprintln!("audio: {} ({})", status, message),
+                    None => eprintln!("audio: {}", status),
+                }
+            }
+            "ai:limit" => {
+                let state = payload["state"].as_str().unwrap_or("");
+                let hit = serde_json::from_value::<LimitHit>(payload["limit"].clone()).ok();
+                match (payload["override_secs"].as_u64(), hit) {
+                    (Some(secs), _) => eprintln!("limit: {} for {}s", state, secs),
+                    (None, Some(hit)) => eprintln!("limit: {} — {}", state, hit.describe()),
+                    (None, None) => eprintln!("limit: {}", state),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("beme-cli: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(args).await {
+        eprintln!("beme-cli: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), BemeError> {
+    let path = match args.settings {
+        Some(path) => path,
+        None => settings::default_settings_path()
+            .ok_or_else(|| BemeError::Settings("no config directory on this system".into()))?,
+    };
+    let s = Settings::load_from_path(&path)?;
+
+    let sink = Arc::new(StdoutSink {
+        json: args.json,
+        current: Mutex::new(None),
+    });
+    let sm = Arc::new(StreamManager::with_event_sink(sink));
+
+    // Today's spend is shared with the app
+    let usage_log = settings::default_data_dir()
+        .ok_or_else(|| BemeError::Settings("no data directory on this system".into()))
+        .and_then(|dir| {
+            std::fs::create_dir_all(&dir)?;
+            sm.open_usage_log(&dir.join("usage.json"))
+        });
+    if let Err(e) = usage_log {
+        log::warn!("Usage totals won't be kept: {}", e);
+    }
+
+    beme_lib::configure_ai_from_settings(&sm, &s)?;
+    if !sm.is_configured() {
+        return Err(BemeError::NotConfigured(format!(
+            "AI provider (set endpoint and apiKey, or provider = \"scripted\", in {})",
+            path.display()
+        )));
+    }
+
+    let interval_ms = (s.capture_interval * 1000.0).max(100.0) as u64;
+    let screen = ScreenCapture::new(interval_ms, s.screenshot_max_width, 75);
+    let audio = AudioCapture::new(24000, 250);
+
+    if args.screen {
+        screen.set_monitor(args.monitor);
+        screen.start(sm.event_sink(), Some(Arc::clone(&sm))).await;
+    }
+    if args.audio {
+        s.apply_audio_device(&audio);
+        if let Some(device) = args.device {
+            audio.set_device(Some(device));
+        }
+        sm.start_audio_session().await?;
+        audio.start(sm.event_sink(), Some(Arc::clone(&sm))).await;
+    }
+
+    tokio::signal::ctrl_c().await?;
+
+    screen.stop().await;
+    audio.stop().await;
+    if args.audio {
+        sm.stop_audio_session().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn defaults_to_screen_capture() {
+        let args = parse(&[]).unwrap().unwrap();
+        assert!(args.screen);
+        assert!(!args.audio);
+        assert!(!args.json);
+    }
+
+    #[test]
+    fn parses_flags_and_values() {
+        let args = parse(&[
+            "--audio",
+            "--json",
+            "--device",
+            "Speakers",
+            "--monitor",
+            "2",
+        ])
+        .unwrap()
+        .unwrap();
+        assert!(!args.screen);
+        assert!(args.audio);
+        assert!(args.json);
+        assert_eq!(args.device.as_deref(), Some("Speakers"));
+        assert_eq!(args.monitor, Some(2));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["--settings"]).is_err());
+        assert!(parse(&["--monitor", "main"]).is_err());
+        assert!(parse(&["--help"]).unwrap().is_none());
+    }
+}
@@ -0,0 +1,321 @@
+// budget.rs — Keeps image tokens under a per-minute budget.
+//
+// What a frame costs depends only on its pixel size and the `detail` level
+// it's sent at, so the cost can be worked out before the frame is encoded.
+// `TokenBudget` remembers what was sent in the last minute and, before each
+// capture, picks the largest frame that fits: smaller widths first (with
+// lower JPEG quality, for a smaller upload), then low detail once even the
+// smallest width is too expensive. Each frame gets the budget's share for
+// one capture interval, so even a screen that changes every tick stays
+// under it — unless a low-detail frame alone costs more than that share.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::ai::ImageDetail;
+use crate::error::BemeError;
+
+/// Window the budget is counted over.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Each step down keeps this much of the previous width.
+const WIDTH_STEP: f64 = 0.75;
+
+/// Fixed cost of any image, and the whole cost at low detail.
+const BASE_TOKENS: u32 = 85;
+/// Cost of each 512 px tile at high detail.
+const TILE_TOKENS: u32 = 170;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BudgetSettings {
+    /// Image tokens to spend per minute at most; 0 = no budget.
+    pub image_tokens_per_minute: u32,
+    /// Frames are never shrunk below this width.
+    pub min_width: u32,
+    /// JPEG quality for the smallest frames (1–100).
+    pub min_jpeg_quality: u8,
+}
+
+impl Default for BudgetSettings {
+    fn default() -> Self {
+        Self {
+            image_tokens_per_minute: 0,
+            min_width: 512,
+            min_jpeg_quality: 40,
+        }
+    }
+}
+
+impl BudgetSettings {
+    pub fn validate(&self) -> Result<(), BemeError> {
+        if self.min_width < 64 {
+            return Err(BemeError::InvalidInput(
+                "minimum frame width must be at least 64 px".into(),
+            ));
+        }
+        if !(1..=100).contains(&self.min_jpeg_quality) {
+            return Err(BemeError::InvalidInput(
+                "minimum JPEG quality must be between 1 and 100".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Largest size a frame may be encoded at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameSize {
+    pub max_width: u32,
+    pub jpeg_quality: u8,
+}
+
+/// How to encode and send one frame, and what it will cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramePlan {
+    pub size: FrameSize,
+    /// Dimensions after downscaling.
+    pub width: u32,
+    pub height: u32,
+    pub detail: ImageDetail,
+    pub image_tokens: u32,
+}
+
+impl FramePlan {
+    /// A frame `source` pixels large, encoded at `size` and sent at `detail`.
+    pub fn new(source: (u32, u32), size: FrameSize, detail: ImageDetail) -> Self {
+        let (width, height) = scaled_size(source.0, source.1, size.max_width);
+        Self {
+            size,
+            width,
+            height,
+            detail,
+            image_tokens: image_tokens(width, height, detail),
+        }
+    }
+
+    /// The same frame sent at another detail level.
+    pub fn with_detail(self, detail: ImageDetail) -> Self {
+        Self {
+            detail,
+            image_tokens: image_tokens(self.width, self.height, detail),
+            ..self
+        }
+    }
+}
+
+/// Size of a `width`×`height` image after downscaling to at most
+/// `max_width` wide (aspect ratio kept, never upscaled).
+pub fn scaled_size(width: u32, height: u32, max_width: u32) -> (u32, u32) {
+    if width <= max_width {
+        return (width, height);
+    }
+    let ratio = max_width as f64 / width as f64;
+    (max_width, (height as f64 * ratio).round() as u32)
+}
+
+/// Input tokens the model charges for an image. At high detail the image is
+/// fit within 2048×2048, then its short side scaled down to 768, and each
+/// 512 px tile is counted. `Auto` is counted as high, since the model may
+/// choose it.
+pub fn image_tokens(width: u32, height: u32, detail: ImageDetail) -> u32 {
+    if detail == ImageDetail::Low || width == 0 || height == 0 {
+        return BASE_TOKENS;
+    }
+    let (mut w, mut h) = (width as f64, height as f64);
+    let fit = (2048.0 / w.max(h)).min(1.0);
+    w *= fit;
+    h *= fit;
+    let short = (768.0 / w.min(h)).min(1.0);
+    w *= short;
+    h *= short;
+    let tiles = (w / 512.0).ceil() as u32 * (h / 512.0).ceil() as u32;
+    BASE_TOKENS + TILE_TOKENS * tiles
+}
+
+/// Image tokens sent in the last minute, and the plan for the next frame.
+#[derive(Debug, Default)]
+pub struct TokenBudget {
+    settings: BudgetSettings,
+    /// When each frame in the window was sent, and what it cost.
+    sent: VecDeque<(Instant, u32)>,
+}
+
+impl TokenBudget {
+    pub fn new(settings: BudgetSettings) -> Self {
+        Self {
+            settings,
+            sent: VecDeque::new(),
+        }
+    }
+
+    pub fn settings(&self) -> &BudgetSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: BudgetSettings) {
+        self.settings = settings;
+    }
+
+    /// Image tokens sent in the minute before `now`.
+    pub fn spent(&mut self, now: Instant) -> u32 {
+        self.expire(now);
+        self.sent.iter().map(|(_, tokens)| tokens).sum()
+    }
+
+    /// Count a frame sent at `now`.
+    pub fn record(&mut self, now: Instant, tokens: u32) {
+        self.expire(now);
+        self.sent.push_back((now, tokens));
+    }
+
+    /// The largest frame from a `source`-sized screen that fits the budget,
+    /// for a capture loop taking a frame every `interval`: the budget's share
+    /// for one interval, and never more than what's left of it.
+    pub fn plan(
+        &mut self,
+        now: Instant,
+        source: (u32, u32),
+        base: FrameSize,
+        detail: ImageDetail,
+        interval: Duration,
+    ) -> FramePlan {
+        let full = FramePlan::new(source, base, detail);
+        let budget = self.settings.image_tokens_per_minute;
+        if budget == 0 {
+            return full;
+        }
+        let share = (budget as f64 * interval.as_secs_f64() / WINDOW.as_secs_f64()) as u32;
+        let allowance = share.min(budget.saturating_sub(self.spent(now)));
+        if full.image_tokens <= allowance {
+            return full;
+        }
+
+        let start = base.max_width.min(source.0);
+        let min_width = self.settings.min_width.min(start);
+        let min_quality = self.settings.min_jpeg_quality.min(base.jpeg_quality);
+        let size_at = |width: u32| FrameSize {
+            max_width: width,
+            jpeg_quality: ((base.jpeg_quality as f64 * width as f64 / start as f64) as u8)
+                .max(min_quality),
+        };
+        let mut width = start;
+        while width > min_width {
+            width = ((width as f64 * WIDTH_STEP) as u32).max(min_width);
+            let plan = FramePlan::new(source, size_at(width), detail);
+            if plan.image_tokens <= allowance {
+                return plan;
+            }
+        }
+        FramePlan::new(source, size_at(min_width), ImageDetail::Low)
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(at, _)) = self.sent.front() {
+            if now.duration_since(at) < WINDOW {
+                break;
+            }
+            self.sent.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: FrameSize = FrameSize {
+        max_width: 1024,
+        jpeg_quality: 75,
+    };
+
+    const INTERVAL: Duration = Duration::from_secs(2);
+
+    fn budget(tokens_per_minute: u32) -> TokenBudget {
+        TokenBudget::new(BudgetSettings {
+            image_tokens_per_minute: tokens_per_minute,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn counts_tokens_like_the_model() {
+        assert_eq!(image_tokens(1024, 576, ImageDetail::Low), 85);
+        // 1024×576: 2×2 tiles
+        assert_eq!(image_tokens(1024, 576, ImageDetail::High), 765);
+        assert_eq!(image_tokens(1024, 576, ImageDetail::Auto), 765);
+        // 4096×2160 fits to 2048×1080, then 1456×768: 3×2 tiles
+        assert_eq!(image_tokens(4096, 2160, ImageDetail::High), 1105);
+        assert_eq!(image_tokens(512, 288, ImageDetail::High), 255);
+        assert_eq!(scaled_size(2560, 1440, 1024), (1024, 576));
+        assert_eq!(scaled_size(800, 600, 1024), (800, 600));
+    }
+
+    #[test]
+    fn no_budget_sends_full_frames() {
+        let mut b = budget(0);
+        let now = Instant::now();
+        for _ in 0..100 {
+            let plan = b.plan(now, (2560, 1440), BASE, ImageDetail::High, INTERVAL);
+            assert_eq!(plan.size, BASE);
+            b.record(now, plan.image_tokens);
+        }
+    }
+
+    #[test]
+    fn shrinks_frames_to_stay_under_budget() {
+        // A frame every 2 s: 30 a minute, 765 tokens each at 1024 px
+        let start = Instant::now();
+        let minute_of_frames = |b: &mut TokenBudget| {
+            let mut widths = Vec::new();
+            for i in 0..30 {
+                let now = start + INTERVAL * i;
+                let plan = b.plan(now, (2560, 1440), BASE, ImageDetail::High, INTERVAL);
+                widths.push(plan.size.max_width);
+                b.record(now, plan.image_tokens);
+            }
+            widths
+        };
+
+        let mut roomy = budget(30_000);
+        assert!(minute_of_frames(&mut roomy).iter().all(|&w| w == 1024));
+
+        let mut tight = budget(9000);
+        let widths = minute_of_frames(&mut tight);
+        assert!(widths.iter().all(|&w| w == 512), "{widths:?}");
+        let last = start + INTERVAL * 29;
+        assert!(tight.spent(last) <= 9000, "{}", tight.spent(last));
+
+        // Once the window has passed, nothing counts against it
+        assert_eq!(tight.spent(start + Duration::from_secs(200)), 0);
+    }
+
+    #[test]
+    fn falls_back_to_low_detail_with_lower_quality() {
+        let mut b = budget(1000);
+        let plan = b.plan(
+            Instant::now(),
+            (2560, 1440),
+            BASE,
+            ImageDetail::High,
+            INTERVAL,
+        );
+        assert_eq!(plan.detail, ImageDetail::Low);
+        assert_eq!(plan.image_tokens, 85);
+        assert_eq!(plan.size.max_width, 512);
+        assert_eq!(plan.size.jpeg_quality, 40);
+        assert_eq!(plan.with_detail(ImageDetail::High).image_tokens, 255);
+    }
+
+    #[test]
+    fn what_is_left_caps_the_next_frame() {
+        let mut b = budget(30_000);
+        let now = Instant::now();
+        b.record(now, 29_500);
+        let plan = b.plan(now, (2560, 1440), BASE, ImageDetail::High, INTERVAL);
+        assert_eq!(plan.image_tokens, 425);
+        assert_eq!(plan.size.max_width, 768);
+        assert_eq!(plan.size.jpeg_quality, 56);
+    }
+}
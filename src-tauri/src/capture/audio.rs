@@ -5,13 +5,22 @@
 // Audio is chunked into ~250ms segments, converted to PCM 16-bit @ 24kHz,
 // and emitted as Tauri events for the UI audio meter and AI processing.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use base64::Engine as _;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use serde::Serialize;
-use tauri::Emitter;
+use cpal::Sample as _;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::BemeError;
+use crate::events::EventSink;
+
+use super::recorder::{AudioRecorder, RecordingOptions};
+use super::resample::Resampler;
+use super::ring_buffer::{sample_ring, RingConsumer, RingProducer};
+use super::task::{ExitHook, TaskSlot};
 
 // ─── Device enumeration ────────────────────────────────────────────────────────
 
@@ -22,10 +31,36 @@ pub struct AudioDeviceInfo {
     pub name: String,
     /// Whether this is the system default output device
     pub is_default: bool,
+    /// The config used when the user hasn't picked one
+    pub default_config: Option<AudioStreamConfig>,
+    /// Every config range the device reports
+    pub supported_configs: Vec<SupportedAudioConfig>,
+}
+
+/// A rate/channel combination the user can pick for a device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioStreamConfig {
+    /// Sample rate in Hz
+    pub sample_rate: u32,
+    /// Number of interleaved channels
+    pub channels: u16,
+}
+
+/// One supported config range, as reported by cpal.
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedAudioConfig {
+    /// Number of interleaved channels
+    pub channels: u16,
+    /// Lowest supported sample rate in Hz
+    pub min_sample_rate: u32,
+    /// Highest supported sample rate in Hz
+    pub max_sample_rate: u32,
+    /// Native sample format, e.g. "f32", "i16", "u8"
+    pub sample_format: String,
 }
 
 /// List all available audio output devices.
-pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
+pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, BemeError> {
     let host = cpal::default_host();
     let default_name = host
         .default_output_device()
@@ -34,12 +69,31 @@ pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
 
     let devices: Vec<AudioDeviceInfo> = host
         .output_devices()
-        .map_err(|e| format!("Failed to enumerate audio devices: {e}"))?
+        .map_err(|e| BemeError::AudioDevice(format!("enumerate devices: {e}")))?
         .filter_map(|d| {
             let name = d.name().ok()?;
+            let default_config = d.default_output_config().ok().map(|c| AudioStreamConfig {
+                sample_rate: c.sample_rate().0,
+                channels: c.channels(),
+            });
+            let supported_configs = d
+                .supported_output_configs()
+                .map(|ranges| {
+                    ranges
+                        .map(|r| SupportedAudioConfig {
+                            channels: r.channels(),
+                            min_sample_rate: r.min_sample_rate().0,
+                            max_sample_rate: r.max_sample_rate().0,
+                            sample_format: r.sample_format().to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
             Some(AudioDeviceInfo {
                 is_default: name == default_name,
                 name,
+                default_config,
+                supported_configs,
             })
         })
         .collect();
@@ -47,6 +101,35 @@ pub fn list_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
     Ok(devices)
 }
 
+/// Pick the stream config for `device`: the user's choice if one was made,
+/// otherwise the device default. Errors if the device can't do the choice.
+fn resolve_stream_config(
+    device: &cpal::Device,
+    preferred: Option<&AudioStreamConfig>,
+) -> Result<cpal::SupportedStreamConfig, Box<dyn std::error::Error>> {
+    let Some(want) = preferred else {
+        return Ok(device.default_output_config()?);
+    };
+
+    device
+        .supported_output_configs()?
+        .filter(|r| {
+            r.channels() == want.channels
+                && r.min_sample_rate().0 <= want.sample_rate
+                && want.sample_rate <= r.max_sample_rate().0
+        })
+        // Among matching ranges prefer the format cpal would pick by default.
+        .max_by(|a, b| a.cmp_default_heuristics(b))
+        .map(|r| r.with_sample_rate(cpal::SampleRate(want.sample_rate)))
+        .ok_or_else(|| {
+            format!(
+                "Device does not support {}Hz with {} channel(s)",
+                want.sample_rate, want.channels
+            )
+            .into()
+        })
+}
+
 // ─── Event Payloads ────────────────────────────────────────────────────────────
 
 /// Payload for the `capture:audio-level` event.
@@ -73,6 +156,22 @@ pub struct AudioChunkPayload {
     pub duration_ms: u32,
 }
 
+/// Payload for the `capture:audio-stats` event, emitted about once a second.
+/// Non-zero drop counts mean the drain loop fell behind the audio callback.
+#[derive(Clone, Serialize)]
+pub struct AudioStatsPayload {
+    /// Samples dropped because the ring buffer was full, since capture started
+    pub dropped_samples: u64,
+    /// Samples dropped since the previous stats event
+    pub dropped_since_last: u64,
+    /// Samples waiting in the ring buffer when the stats were taken
+    pub buffered_samples: usize,
+    /// Ring buffer capacity in samples
+    pub capacity: usize,
+    /// ISO-8601 timestamp
+    pub timestamp: String,
+}
+
 // ─── AudioCapture ──────────────────────────────────────────────────────────────
 
 /// Manages system audio capture via WASAPI loopback.
@@ -80,19 +179,23 @@ pub struct AudioChunkPayload {
 /// # Usage
 /// ```ignore
 /// let capture = AudioCapture::new(24000, 250);
-/// capture.toggle();                       // start
-/// capture.start_loop(app_handle.clone());  // runs on a background thread
-/// capture.toggle();                       // stop
+/// capture.start(events, None).await;    // runs on a background thread
+/// capture.stop().await;                   // returns once the thread exits
 /// ```
 pub struct AudioCapture {
-    /// Shared flag — `true` while capturing, `false` to stop.
-    is_capturing: Arc<AtomicBool>,
+    /// The running capture thread, if any.
+    task: TaskSlot,
     /// Target output sample rate (Hz). Default: 24 000.
     sample_rate: u32,
     /// How many milliseconds of audio per chunk. Default: 250.
     chunk_ms: u32,
     /// Selected audio device name. `None` means use system default.
     selected_device: std::sync::Mutex<Option<String>>,
+    /// User-chosen stream config per device name. Devices without an entry
+    /// use their default output config.
+    device_configs: std::sync::Mutex<HashMap<String, AudioStreamConfig>>,
+    /// When set, each capture session is also written to WAV segments.
+    recording: std::sync::Mutex<Option<RecordingOptions>>,
 }
 
 impl AudioCapture {
@@ -102,28 +205,19 @@ impl AudioCapture {
     /// * `chunk_ms`    — how often to emit a chunk, in milliseconds (e.g. 250)
     pub fn new(sample_rate: u32, chunk_ms: u32) -> Self {
         Self {
-            is_capturing: Arc::new(AtomicBool::new(false)),
+            task: TaskSlot::default(),
             sample_rate,
             chunk_ms,
             selected_device: std::sync::Mutex::new(None),
+            device_configs: std::sync::Mutex::new(HashMap::new()),
+            recording: std::sync::Mutex::new(None),
         }
     }
 
-    /// Flip the capturing flag. Returns `true` if capturing is now **on**.
-    pub fn toggle(&self) -> bool {
-        // fetch_xor flips the bool and returns the *previous* value
-        let was_capturing = self.is_capturing.fetch_xor(true, Ordering::SeqCst);
-        let now_capturing = !was_capturing;
-        log::info!(
-            "Audio capture toggled → {}",
-            if now_capturing { "ON" } else { "OFF" }
-        );
-        now_capturing
-    }
-
-    /// Returns `true` if audio capture is currently active.
+    /// Returns `true` if the capture thread is running. Goes back to `false`
+    /// on its own if the thread exits early (e.g. the device disappeared).
     pub fn is_capturing(&self) -> bool {
-        self.is_capturing.load(Ordering::SeqCst)
+        self.task.is_running()
     }
 
     /// Select which audio device to use. Pass `None` for default.
@@ -137,53 +231,122 @@ impl AudioCapture {
         self.selected_device.lock().unwrap().clone()
     }
 
-    /// Start the audio capture loop on a **background thread**.
+    /// Choose the stream config for a device. Pass `None` to go back to the
+    /// device default. Takes effect the next time capture starts.
+    pub fn set_device_config(&self, device_name: String, config: Option<AudioStreamConfig>) {
+        log::info!("Audio config for '{}' changed to {:?}", device_name, config);
+        let mut configs = self.device_configs.lock().unwrap();
+        match config {
+            Some(c) => configs.insert(device_name, c),
+            None => configs.remove(&device_name),
+        };
+    }
+
+    /// Replace every device's chosen stream config, e.g. with the ones saved
+    /// in settings. Takes effect the next time capture starts.
+    pub fn set_device_configs(&self, configs: HashMap<String, AudioStreamConfig>) {
+        *self.device_configs.lock().unwrap() = configs;
+    }
+
+    /// Enable (`Some`) or disable (`None`) recording to WAV files.
+    /// Takes effect the next time capture starts.
+    pub fn set_recording(&self, options: Option<RecordingOptions>) {
+        log::info!(
+            "Audio recording {}",
+            if options.is_some() {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+        *self.recording.lock().unwrap() = options;
+    }
+
+    /// Whether captured audio will be recorded to disk.
+    pub fn is_recording_enabled(&self) -> bool {
+        self.recording.lock().unwrap().is_some()
+    }
+
+    /// Get the stream config chosen for a device, if any.
+    pub fn device_config(&self, device_name: &str) -> Option<AudioStreamConfig> {
+        self.device_configs
+            .lock()
+            .unwrap()
+            .get(device_name)
+            .cloned()
+    }
+
+    /// Start the audio capture loop on a **background thread**. Returns
+    /// `false` if it was already running (only one loop ever runs at a time).
     ///
     /// This function spawns a `std::thread` (not a tokio task) because cpal
-    /// streams are `!Send` on some backends. The thread will keep running
-    /// until `is_capturing` is set to `false` (via [`toggle`]).
+    /// streams are `!Send` on some backends. The thread keeps running until
+    /// [`stop`](Self::stop) cancels it.
     ///
     /// Events emitted:
     /// - `capture:audio-level`  — every `chunk_ms` with the RMS level
     /// - `capture:audio-chunk`  — every `chunk_ms` with base64-encoded PCM data
-    pub fn start_loop(
+    /// - `capture:audio-stats`  — about once a second with ring-buffer drop counts
+    pub async fn start(
         &self,
-        app_handle: tauri::AppHandle,
+        events: Arc<dyn EventSink>,
         stream_manager: Option<Arc<crate::stream_manager::StreamManager>>,
-    ) {
-        let is_capturing = Arc::clone(&self.is_capturing);
+    ) -> bool {
         let sample_rate = self.sample_rate;
         let chunk_ms = self.chunk_ms;
         let rt_handle = tokio::runtime::Handle::current();
         let selected_device = self.selected_device_name();
+        let device_configs = self.device_configs.lock().unwrap().clone();
+        let recording = self.recording.lock().unwrap().clone();
+
+        self.task
+            .start(move |cancel, guard| {
+                std::thread::spawn(move || {
+                    let _guard = guard;
+                    if let Err(e) = run_capture_loop(
+                        cancel,
+                        events,
+                        sample_rate,
+                        chunk_ms,
+                        stream_manager,
+                        rt_handle,
+                        selected_device,
+                        device_configs,
+                        recording,
+                    ) {
+                        log::error!("Audio capture loop failed: {e}");
+                    }
+                });
+            })
+            .await
+    }
 
-        std::thread::spawn(move || {
-            if let Err(e) = run_capture_loop(
-                is_capturing,
-                app_handle,
-                sample_rate,
-                chunk_ms,
-                stream_manager,
-                rt_handle,
-                selected_device,
-            ) {
-                log::error!("Audio capture loop failed: {e}");
-            }
-        });
+    /// Stop the capture thread and wait for it to exit. Returns `false` if
+    /// it wasn't running.
+    pub async fn stop(&self) -> bool {
+        self.task.stop().await
+    }
+
+    /// Run `hook` when the capture loop exits without being stopped.
+    pub fn on_loop_exit(&self, hook: ExitHook) {
+        self.task.set_on_exit(hook);
     }
 }
 
 // ─── Internal capture loop ─────────────────────────────────────────────────────
 
 /// The actual capture loop. Runs on a dedicated OS thread.
+#[allow(clippy::too_many_arguments)]
 fn run_capture_loop(
-    is_capturing: Arc<AtomicBool>,
-    app_handle: tauri::AppHandle,
+    cancel: CancellationToken,
+    events: Arc<dyn EventSink>,
     target_rate: u32,
     chunk_ms: u32,
     stream_manager: Option<Arc<crate::stream_manager::StreamManager>>,
     rt_handle: tokio::runtime::Handle,
     selected_device: Option<String>,
+    device_configs: HashMap<String, AudioStreamConfig>,
+    recording: Option<RecordingOptions>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // 1. Pick the WASAPI host & output device.
     //    On Windows, building an *input* stream on an *output* device gives us
@@ -198,10 +361,12 @@ fn run_capture_loop(
             .ok_or("No default output device found")?
     };
 
-    log::info!("Audio capture device: {:?}", device.name()?);
+    let device_name = device.name()?;
+    log::info!("Audio capture device: {:?}", device_name);
 
-    // 2. Get the device's default output config so we know its native format.
-    let supported_config = device.default_output_config()?;
+    // 2. Use the config the user chose for this device, or its default
+    //    output config, so we know its native format.
+    let supported_config = resolve_stream_config(&device, device_configs.get(&device_name))?;
     let device_sample_rate = supported_config.sample_rate().0;
     let device_channels = supported_config.channels() as usize;
     let sample_format = supported_config.sample_format();
@@ -218,63 +383,31 @@ fn run_capture_loop(
     let device_samples_per_chunk =
         (device_sample_rate as usize * chunk_ms as usize) / 1000 * device_channels;
 
-    // Shared buffer: the cpal callback pushes samples here, the drain loop reads them.
-    let buffer: Arc<std::sync::Mutex<Vec<f32>>> = Arc::new(std::sync::Mutex::new(
-        Vec::with_capacity(device_samples_per_chunk * 2),
-    ));
-
-    let buffer_writer = Arc::clone(&buffer);
+    // Lock-free ring: the cpal callback pushes samples here, the drain loop
+    // pops them. Sized for ~2s of audio so a late drain doesn't drop anything.
+    let ring_capacity =
+        (device_sample_rate as usize * device_channels * 2).max(device_samples_per_chunk * 4);
+    let (producer, mut consumer) = sample_ring(ring_capacity);
 
     // 4. Build the stream config from the device's supported config.
     let stream_config: cpal::StreamConfig = supported_config.into();
 
     // 5. Build the input stream (loopback on Windows WASAPI).
-    //    We convert every sample format to f32 for uniform processing.
+    //    Every sample format is converted to f32 for uniform processing.
+    //    (cpal 0.15 doesn't expose 24-bit formats; backends deliver those
+    //    padded to I32.)
+    use cpal::SampleFormat as F;
     let stream = match sample_format {
-        cpal::SampleFormat::F32 => device.build_input_stream(
-            &stream_config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if let Ok(mut buf) = buffer_writer.lock() {
-                    buf.extend_from_slice(data);
-                }
-            },
-            |err| log::error!("Audio stream error: {err}"),
-            None, // no timeout
-        )?,
-        cpal::SampleFormat::I16 => {
-            let buf_w = Arc::clone(&buffer);
-            device.build_input_stream(
-                &stream_config,
-                move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                    // Convert i16 → f32 (range -1.0..1.0)
-                    let floats: Vec<f32> =
-                        data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
-                    if let Ok(mut buf) = buf_w.lock() {
-                        buf.extend_from_slice(&floats);
-                    }
-                },
-                |err| log::error!("Audio stream error: {err}"),
-                None,
-            )?
-        }
-        cpal::SampleFormat::U16 => {
-            let buf_w = Arc::clone(&buffer);
-            device.build_input_stream(
-                &stream_config,
-                move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                    // Convert u16 → f32 (range -1.0..1.0)
-                    let floats: Vec<f32> = data
-                        .iter()
-                        .map(|&s| (s as f32 / u16::MAX as f32) * 2.0 - 1.0)
-                        .collect();
-                    if let Ok(mut buf) = buf_w.lock() {
-                        buf.extend_from_slice(&floats);
-                    }
-                },
-                |err| log::error!("Audio stream error: {err}"),
-                None,
-            )?
-        }
+        F::I8 => build_ring_stream::<i8>(&device, &stream_config, producer)?,
+        F::I16 => build_ring_stream::<i16>(&device, &stream_config, producer)?,
+        F::I32 => build_ring_stream::<i32>(&device, &stream_config, producer)?,
+        F::I64 => build_ring_stream::<i64>(&device, &stream_config, producer)?,
+        F::U8 => build_ring_stream::<u8>(&device, &stream_config, producer)?,
+        F::U16 => build_ring_stream::<u16>(&device, &stream_config, producer)?,
+        F::U32 => build_ring_stream::<u32>(&device, &stream_config, producer)?,
+        F::U64 => build_ring_stream::<u64>(&device, &stream_config, producer)?,
+        F::F32 => build_ring_stream::<f32>(&device, &stream_config, producer)?,
+        F::F64 => build_ring_stream::<f64>(&device, &stream_config, producer)?,
         other => return Err(format!("Unsupported sample format: {other:?}").into()),
     };
 
@@ -287,15 +420,45 @@ fn run_capture_loop(
     //    compute RMS, and emit Tauri events.
     let chunk_duration = std::time::Duration::from_millis(chunk_ms as u64);
 
+    // The resampler keeps filter history between chunks, so it lives for the
+    // whole capture session rather than being rebuilt per chunk.
+    let mut resampler = Resampler::new(device_sample_rate, target_rate);
+
+    // Reused every chunk so the drain loop doesn't allocate either.
+    let mut raw_samples: Vec<f32> = Vec::with_capacity(ring_capacity);
+    let mut stats = StatsReporter::new(chunk_ms);
+
+    // Optional tee to disk. A recording failure is logged but never stops
+    // the live stream.
+    let mut recorder = recording.and_then(|opts| match AudioRecorder::start(opts, target_rate) {
+        Ok(r) => {
+            log::info!("Audio recording to {}", r.session_dir().display());
+            Some(r)
+        }
+        Err(e) => {
+            log::error!("Audio recording could not start: {e}");
+            None
+        }
+    });
+
     let mut first_chunk_logged = false;
-    while is_capturing.load(Ordering::SeqCst) {
-        std::thread::sleep(chunk_duration);
+    loop {
+        // Wait out the chunk, waking straight away if capture is stopped so
+        // `stop` doesn't have to wait for a whole chunk.
+        let stopped = rt_handle.block_on(async {
+            tokio::time::timeout(chunk_duration, cancel.cancelled())
+                .await
+                .is_ok()
+        });
+        if stopped {
+            break;
+        }
 
-        // Pull all accumulated samples out of the shared buffer.
-        let raw_samples: Vec<f32> = {
-            let mut buf = buffer.lock().unwrap();
-            buf.drain(..).collect()
-        };
+        // Pull all accumulated samples out of the ring.
+        raw_samples.clear();
+        consumer.pop_into(&mut raw_samples);
+
+        stats.tick(&consumer, &*events);
 
         if raw_samples.is_empty() {
             continue;
@@ -304,14 +467,19 @@ fn run_capture_loop(
         // a) Down-mix to mono by averaging channels.
         let mono = downmix_to_mono(&raw_samples, device_channels);
 
-        // b) Resample from device rate → target rate (simple linear interpolation).
-        let resampled = resample(&mono, device_sample_rate, target_rate);
+        // b) Resample from device rate → target rate (band-limited, stateful).
+        let resampled = resampler.process(&mono);
 
         // c) Convert f32 → i16 PCM samples.
-        let pcm_i16: Vec<i16> = resampled
-            .iter()
-            .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
-            .collect();
+        let pcm_i16 = quantize_to_i16(&resampled);
+
+        // Tee the processed PCM to the session recording, if enabled.
+        if let Some(rec) = recorder.as_mut() {
+            if let Err(e) = rec.write(&pcm_i16) {
+                log::error!("Audio recording stopped: {e}");
+                recorder = None;
+            }
+        }
 
         // d) Compute RMS level for the UI meter.
         let rms = compute_rms(&pcm_i16);
@@ -324,9 +492,7 @@ fn run_capture_loop(
             level: rms,
             timestamp: timestamp.clone(),
         };
-        if let Err(e) = app_handle.emit("capture:audio-level", &level_payload) {
-            log::debug!("Failed to emit audio-level: {e}");
-        }
+        events.send("capture:audio-level", &level_payload);
 
         // g) Encode PCM bytes as base64 and emit `capture:audio-chunk`.
         let pcm_bytes = pcm_i16_to_bytes(&pcm_i16);
@@ -338,9 +504,7 @@ fn run_capture_loop(
             sample_rate: target_rate,
             duration_ms: chunk_ms,
         };
-        if let Err(e) = app_handle.emit("capture:audio-chunk", &chunk_payload) {
-            log::debug!("Failed to emit audio-chunk: {e}");
-        }
+        events.send("capture:audio-chunk", &chunk_payload);
 
         // Forward raw PCM to AI pipeline if a session is active
         if let Some(ref sm) = stream_manager {
@@ -364,11 +528,94 @@ fn run_capture_loop(
         );
     }
 
-    // 8. Capture was toggled off — the stream is dropped here automatically.
+    // 8. Capture was stopped — write out the filter tail the resampler was
+    //    holding back, close the recording and let the stream drop here
+    //    automatically.
+    if let Some(mut rec) = recorder {
+        if let Err(e) = rec.write(&quantize_to_i16(&resampler.flush())) {
+            log::error!("Audio recording: {e}");
+        }
+        if let Err(e) = rec.finish() {
+            log::error!("Audio recording: {e}");
+        }
+    }
     log::info!("Audio capture loop stopped");
     Ok(())
 }
 
+/// Build an input stream whose callback converts `T` samples to f32 and
+/// pushes them into the ring. The callback runs on the real-time audio
+/// thread: it converts in-place and never locks or allocates.
+fn build_ring_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut producer: RingProducer,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: cpal::SizedSample,
+    f32: cpal::FromSample<T>,
+{
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            producer.push_iter(data.iter().map(|&s| sample_to_f32(s)));
+        },
+        |err| log::error!("Audio stream error: {err}"),
+        None, // no timeout
+    )
+}
+
+/// Convert any cpal sample to f32 in the range -1.0..1.0.
+/// Unsigned formats are centred on their midpoint.
+fn sample_to_f32<T>(sample: T) -> f32
+where
+    T: cpal::Sample,
+    f32: cpal::FromSample<T>,
+{
+    sample.to_sample::<f32>()
+}
+
+/// Emits `capture:audio-stats` roughly once per second from the drain loop.
+struct StatsReporter {
+    chunks_per_report: u32,
+    chunks_since_report: u32,
+    last_dropped: u64,
+}
+
+impl StatsReporter {
+    fn new(chunk_ms: u32) -> Self {
+        Self {
+            chunks_per_report: (1000 / chunk_ms.max(1)).max(1),
+            chunks_since_report: 0,
+            last_dropped: 0,
+        }
+    }
+
+    fn tick(&mut self, consumer: &RingConsumer, events: &dyn EventSink) {
+        self.chunks_since_report += 1;
+        if self.chunks_since_report < self.chunks_per_report {
+            return;
+        }
+        self.chunks_since_report = 0;
+
+        let dropped = consumer.dropped();
+        let dropped_since_last = dropped - self.last_dropped;
+        self.last_dropped = dropped;
+        if dropped_since_last > 0 {
+            log::warn!("Audio ring overflow: {dropped_since_last} samples dropped");
+        }
+
+        let payload = AudioStatsPayload {
+            dropped_samples: dropped,
+            dropped_since_last,
+            buffered_samples: consumer.len(),
+            capacity: consumer.capacity(),
+            timestamp: now_iso8601(),
+        };
+        events.send("capture:audio-stats", &payload);
+    }
+}
+
 // ─── DSP helpers ───────────────────────────────────────────────────────────────
 
 /// Down-mix interleaved multi-channel audio to mono by averaging channels.
@@ -382,33 +629,12 @@ fn downmix_to_mono(interleaved: &[f32], channels: usize) -> Vec<f32> {
         .collect()
 }
 
-/// Resample audio using simple linear interpolation.
-/// Good enough for speech/AI; not audiophile-grade.
-fn resample(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-    if from_rate == to_rate {
-        return input.to_vec();
-    }
-
-    let ratio = from_rate as f64 / to_rate as f64;
-    let output_len = ((input.len() as f64) / ratio).ceil() as usize;
-    let mut output = Vec::with_capacity(output_len);
-
-    for i in 0..output_len {
-        let src_pos = i as f64 * ratio;
-        let idx = src_pos as usize;
-        let frac = src_pos - idx as f64;
-
-        let sample = if idx + 1 < input.len() {
-            // Linear interpolation between two neighbouring samples
-            input[idx] as f64 * (1.0 - frac) + input[idx + 1] as f64 * frac
-        } else {
-            input.get(idx).copied().unwrap_or(0.0) as f64
-        };
-
-        output.push(sample as f32);
-    }
-
-    output
+/// Convert f32 samples in -1.0..1.0 to i16 PCM, clipping anything outside.
+fn quantize_to_i16(samples: &[f32]) -> Vec<i16> {
+    samples
+        .iter()
+        .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect()
 }
 
 /// Compute the RMS (Root Mean Square) of PCM i16 samples.
@@ -519,23 +745,29 @@ mod tests {
     }
 
     #[test]
-    fn resample_same_rate_is_identity() {
-        let input = vec![0.1, 0.2, 0.3, 0.4];
-        let output = resample(&input, 48000, 48000);
-        assert_eq!(input, output);
+    fn sample_conversion_covers_signed_formats() {
+        assert_eq!(sample_to_f32(0i8), 0.0);
+        assert_eq!(sample_to_f32(i8::MIN), -1.0);
+        assert_eq!(sample_to_f32(i16::MIN), -1.0);
+        assert_eq!(sample_to_f32(i32::MIN), -1.0);
+        assert_eq!(sample_to_f32(i64::MIN), -1.0);
+        assert!((sample_to_f32(i32::MAX) - 1.0).abs() < 1e-6);
     }
 
     #[test]
-    fn resample_halves_length_when_doubling_ratio() {
-        // 48kHz → 24kHz should roughly halve the number of samples
-        let input: Vec<f32> = (0..480).map(|i| (i as f32) / 480.0).collect();
-        let output = resample(&input, 48000, 24000);
-        // Allow ±1 sample for rounding
-        assert!(
-            (output.len() as i32 - 240).abs() <= 1,
-            "Expected ~240 samples, got {}",
-            output.len()
-        );
+    fn sample_conversion_centres_unsigned_formats() {
+        assert_eq!(sample_to_f32(128u8), 0.0);
+        assert_eq!(sample_to_f32(0u8), -1.0);
+        assert_eq!(sample_to_f32(32768u16), 0.0);
+        assert_eq!(sample_to_f32(1u32 << 31), 0.0);
+        assert_eq!(sample_to_f32(0u64), -1.0);
+        assert!((sample_to_f32(u16::MAX) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn sample_conversion_passes_floats_through() {
+        assert_eq!(sample_to_f32(0.25f32), 0.25);
+        assert_eq!(sample_to_f32(-0.5f64), -0.5);
     }
 
     #[test]
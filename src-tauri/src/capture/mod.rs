@@ -1,6 +1,21 @@
 /// The `capture` module handles all media capture for beme.
-/// Currently only screen capture is implemented; audio capture will follow
-/// once the `cpal` dependency is added.
+/// Screen capture via `xcap`, system audio capture via `cpal`, and the DSP,
+/// buffering and recording pieces behind them.
 pub mod screen;
 
 pub mod audio;
+pub mod recorder;
+pub mod resample;
+pub mod ring_buffer;
+pub mod task;
+
+use serde::Serialize;
+
+/// Which capture loops are actually running, returned by `capture_status`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CaptureStatus {
+    pub screen: bool,
+    pub audio: bool,
+    /// Whether audio is included when capture starts.
+    pub audio_enabled: bool,
+}
@@ -0,0 +1,292 @@
+// recorder.rs — Tees the processed 24kHz PCM stream into WAV files on disk.
+//
+// Each capture session gets its own directory under the recordings root,
+// holding numbered segments (`segment-000.wav`, `segment-001.wav`, …). A new
+// segment starts whenever the current one reaches the configured duration or
+// size, and with `max_segments` set the oldest segments are deleted so a
+// long-running session keeps a rolling window instead of filling the disk.
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+/// Size of the canonical WAV header hound writes for 16-bit PCM.
+const WAV_HEADER_BYTES: u64 = 44;
+
+/// Where and how much to record.
+#[derive(Debug, Clone)]
+pub struct RecordingOptions {
+    /// Root directory; each session creates a subdirectory here.
+    pub dir: PathBuf,
+    /// Start a new segment after this many seconds. 0 = no time limit.
+    pub max_segment_secs: u32,
+    /// Start a new segment before the file exceeds this size. 0 = no limit.
+    pub max_segment_bytes: u64,
+    /// Keep only the newest N segments. 0 = keep everything.
+    pub max_segments: u32,
+}
+
+/// Writes mono 16-bit PCM to rolling WAV segments for one session.
+pub struct AudioRecorder {
+    session_dir: PathBuf,
+    sample_rate: u32,
+    options: RecordingOptions,
+    writer: Option<WavWriter<BufWriter<File>>>,
+    /// Index of the next segment to open.
+    next_segment: u32,
+    /// Samples written to the current segment.
+    segment_samples: u64,
+    /// Segments still on disk, oldest first.
+    segments: VecDeque<PathBuf>,
+}
+
+impl AudioRecorder {
+    /// Create a new session directory under `options.dir` and open the
+    /// first segment.
+    pub fn start(options: RecordingOptions, sample_rate: u32) -> Result<Self, String> {
+        let session_dir = options.dir.join(format!("session-{}", session_stamp()));
+        fs::create_dir_all(&session_dir)
+            .map_err(|e| format!("create {}: {e}", session_dir.display()))?;
+
+        let mut recorder = Self {
+            session_dir,
+            sample_rate,
+            options,
+            writer: None,
+            next_segment: 0,
+            segment_samples: 0,
+            segments: VecDeque::new(),
+        };
+        recorder.rotate()?;
+        Ok(recorder)
+    }
+
+    /// Directory holding this session's segments.
+    pub fn session_dir(&self) -> &Path {
+        &self.session_dir
+    }
+
+    /// Append samples, rolling over to a new segment at the limits.
+    pub fn write(&mut self, mut samples: &[i16]) -> Result<(), String> {
+        while !samples.is_empty() {
+            let room = self.segment_capacity().saturating_sub(self.segment_samples);
+            if room == 0 {
+                self.rotate()?;
+                continue;
+            }
+
+            let n = (room.min(samples.len() as u64)) as usize;
+            let writer = self
+                .writer
+                .as_mut()
+                .ok_or_else(|| "recorder already finished".to_string())?;
+            for &s in &samples[..n] {
+                writer
+                    .write_sample(s)
+                    .map_err(|e| format!("write wav sample: {e}"))?;
+            }
+            self.segment_samples += n as u64;
+            samples = &samples[n..];
+        }
+        Ok(())
+    }
+
+    /// Flush and close the current segment. Further writes fail.
+    pub fn finish(&mut self) -> Result<(), String> {
+        if let Some(writer) = self.writer.take() {
+            writer
+                .finalize()
+                .map_err(|e| format!("finalize wav: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Maximum samples per segment given the time and size limits.
+    fn segment_capacity(&self) -> u64 {
+        let by_time = match self.options.max_segment_secs {
+            0 => u64::MAX,
+            secs => secs as u64 * self.sample_rate as u64,
+        };
+        let by_size = match self.options.max_segment_bytes {
+            0 => u64::MAX,
+            bytes => (bytes.saturating_sub(WAV_HEADER_BYTES) / 2).max(1),
+        };
+        by_time.min(by_size)
+    }
+
+    /// Close the current segment, open the next one, and prune old ones.
+    fn rotate(&mut self) -> Result<(), String> {
+        self.finish()?;
+
+        let path = self
+            .session_dir
+            .join(format!("segment-{:03}.wav", self.next_segment));
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let writer = WavWriter::create(&path, spec)
+            .map_err(|e| format!("create {}: {e}", path.display()))?;
+        log::debug!("Audio recording segment opened: {}", path.display());
+
+        self.writer = Some(writer);
+        self.next_segment += 1;
+        self.segment_samples = 0;
+        self.segments.push_back(path);
+
+        let keep = self.options.max_segments as usize;
+        while keep > 0 && self.segments.len() > keep {
+            if let Some(old) = self.segments.pop_front() {
+                if let Err(e) = fs::remove_file(&old) {
+                    log::warn!("Failed to prune {}: {e}", old.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AudioRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            log::error!("Audio recording: {e}");
+        }
+    }
+}
+
+/// Filesystem-safe UTC timestamp with millisecond precision, e.g.
+/// `20250101T120000.123Z`, so sessions sort chronologically.
+fn session_stamp() -> String {
+    let dur = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = dur.as_secs();
+    let t = secs % 86400;
+    let (y, m, d) = super::screen::epoch_days_to_ymd((secs / 86400) as i64);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}.{:03}Z",
+        y,
+        m,
+        d,
+        t / 3600,
+        (t % 3600) / 60,
+        t % 60,
+        dur.subsec_millis()
+    )
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(dir: &Path) -> RecordingOptions {
+        RecordingOptions {
+            dir: dir.to_path_buf(),
+            max_segment_secs: 0,
+            max_segment_bytes: 0,
+            max_segments: 0,
+        }
+    }
+
+    fn read_wav(path: &Path) -> (WavSpec, Vec<i16>) {
+        let mut reader = hound::WavReader::open(path).unwrap();
+        let spec = reader.spec();
+        let samples = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        (spec, samples)
+    }
+
+    fn segment_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn writes_mono_16bit_wav() {
+        let root = tempfile::tempdir().unwrap();
+        let mut rec = AudioRecorder::start(options(root.path()), 24000).unwrap();
+        rec.write(&[0, 1, -1, i16::MAX, i16::MIN]).unwrap();
+        rec.finish().unwrap();
+
+        let files = segment_files(rec.session_dir());
+        assert_eq!(files.len(), 1);
+        let (spec, samples) = read_wav(&files[0]);
+        assert_eq!(spec.channels, 1);
+        assert_eq!(spec.sample_rate, 24000);
+        assert_eq!(spec.bits_per_sample, 16);
+        assert_eq!(samples, vec![0, 1, -1, i16::MAX, i16::MIN]);
+    }
+
+    #[test]
+    fn rotates_on_duration_limit() {
+        let root = tempfile::tempdir().unwrap();
+        let mut opts = options(root.path());
+        opts.max_segment_secs = 1;
+        // 10 samples/s keeps the test tiny: 25 samples → 10 + 10 + 5.
+        let mut rec = AudioRecorder::start(opts, 10).unwrap();
+        let input: Vec<i16> = (0..25).collect();
+        rec.write(&input[..7]).unwrap();
+        rec.write(&input[7..]).unwrap();
+        rec.finish().unwrap();
+
+        let files = segment_files(rec.session_dir());
+        let lens: Vec<usize> = files.iter().map(|f| read_wav(f).1.len()).collect();
+        assert_eq!(lens, vec![10, 10, 5]);
+
+        // Concatenating the segments gives back the original stream.
+        let joined: Vec<i16> = files.iter().flat_map(|f| read_wav(f).1).collect();
+        assert_eq!(joined, input);
+    }
+
+    #[test]
+    fn rotates_on_size_limit() {
+        let root = tempfile::tempdir().unwrap();
+        let mut opts = options(root.path());
+        opts.max_segment_bytes = WAV_HEADER_BYTES + 8; // 4 samples per file
+        let mut rec = AudioRecorder::start(opts, 24000).unwrap();
+        rec.write(&[1; 10]).unwrap();
+        rec.finish().unwrap();
+
+        let files = segment_files(rec.session_dir());
+        assert_eq!(files.len(), 3);
+        for f in &files {
+            assert!(fs::metadata(f).unwrap().len() <= WAV_HEADER_BYTES + 8);
+        }
+    }
+
+    #[test]
+    fn prunes_oldest_segments() {
+        let root = tempfile::tempdir().unwrap();
+        let mut opts = options(root.path());
+        opts.max_segment_secs = 1;
+        opts.max_segments = 2;
+        let mut rec = AudioRecorder::start(opts, 10).unwrap();
+        let input: Vec<i16> = (0..45).collect();
+        rec.write(&input).unwrap();
+        rec.finish().unwrap();
+
+        let names: Vec<String> = segment_files(rec.session_dir())
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["segment-003.wav", "segment-004.wav"]);
+    }
+
+    #[test]
+    fn session_stamp_is_filesystem_safe() {
+        let stamp = session_stamp();
+        assert_eq!(stamp.len(), 20, "unexpected stamp {stamp}");
+        assert!(stamp.ends_with('Z'));
+        assert!(!stamp.contains(':'));
+    }
+}
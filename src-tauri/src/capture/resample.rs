@@ -0,0 +1,367 @@
+// resample.rs — Stateful windowed-sinc (polyphase) sample-rate converter.
+//
+// Audio arrives in ~250ms chunks, so the resampler keeps the tail of each
+// chunk as filter history: feeding it chunk by chunk produces exactly the
+// same output as resampling the whole stream at once, with no clicks at
+// chunk boundaries. The low-pass cutoff sits just below the lower of the two
+// Nyquist frequencies, so content the 24kHz target cannot represent is
+// filtered out instead of aliasing down into the speech band.
+
+use std::f64::consts::PI;
+
+/// Zero crossings of the sinc kernel on each side of the centre tap.
+/// More crossings give a sharper transition band at the cost of more taps.
+const ZERO_CROSSINGS: f64 = 24.0;
+
+/// Cutoff as a fraction of the lower Nyquist frequency. The remaining 10%
+/// is the transition band, so the stopband starts close to Nyquist.
+const ROLLOFF: f64 = 0.9;
+
+/// Kaiser window shape. β = 9 gives roughly 90 dB of stopband attenuation.
+const KAISER_BETA: f64 = 9.0;
+
+/// Upper bound on precomputed filter phases. Rate pairs whose reduced ratio
+/// needs more phases than this snap to the nearest lower phase.
+const MAX_PHASES: usize = 4096;
+
+/// Band-limited resampler that carries state across calls to [`process`].
+///
+/// Output sample `k` corresponds exactly to input time `k / to_rate`, so the
+/// output lines up with a signal generated natively at the target rate.
+///
+/// [`process`]: Resampler::process
+pub struct Resampler {
+    from_rate: u32,
+    to_rate: u32,
+    /// Distance between output instants in input samples, as the reduced
+    /// fraction `step_int + step_frac / den`.
+    step_int: usize,
+    step_frac: usize,
+    den: usize,
+    /// Number of rows in `table`.
+    phases: usize,
+    /// Taps on each side of the interpolation point.
+    half_taps: usize,
+    /// `phases × 2·half_taps` filter coefficients, one row per phase.
+    table: Vec<f32>,
+    /// Input not yet fully consumed; `history[pos]` is the sample at or just
+    /// before the next output instant.
+    history: Vec<f32>,
+    pos: usize,
+    pos_frac: usize,
+    /// Totals used by [`flush`](Resampler::flush) to emit the exact tail.
+    input_len: u64,
+    output_len: u64,
+}
+
+impl Resampler {
+    /// Create a resampler converting `from_rate` Hz to `to_rate` Hz.
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        assert!(
+            from_rate > 0 && to_rate > 0,
+            "sample rates must be non-zero"
+        );
+
+        let g = gcd(from_rate as usize, to_rate as usize);
+        let num = from_rate as usize / g;
+        let den = to_rate as usize / g;
+        let phases = den.min(MAX_PHASES);
+
+        // Cutoff in cycles per input sample.
+        let ratio = (to_rate as f64 / from_rate as f64).min(1.0);
+        let cutoff = 0.5 * ratio * ROLLOFF;
+        let half_len = ZERO_CROSSINGS / (2.0 * cutoff);
+        let half_taps = half_len.ceil() as usize;
+
+        let table = if from_rate == to_rate {
+            Vec::new()
+        } else {
+            build_table(phases, half_taps, half_len, cutoff)
+        };
+
+        Self {
+            from_rate,
+            to_rate,
+            step_int: num / den,
+            step_frac: num % den,
+            den,
+            phases,
+            half_taps,
+            table,
+            // Prime with silence so the first output is centred on input 0.
+            history: vec![0.0; half_taps - 1],
+            pos: half_taps - 1,
+            pos_frac: 0,
+            input_len: 0,
+            output_len: 0,
+        }
+    }
+
+    /// Resample the next block of mono input.
+    ///
+    /// The last few input samples are held back until enough look-ahead has
+    /// arrived, so the output of a single call may be a handful of samples
+    /// shorter than `input.len() * to / from`. Nothing is lost — those
+    /// samples come out of the next call (or [`flush`](Resampler::flush)).
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        self.input_len += input.len() as u64;
+
+        if self.from_rate == self.to_rate {
+            self.output_len += input.len() as u64;
+            return input.to_vec();
+        }
+
+        self.history.extend_from_slice(input);
+
+        let taps = 2 * self.half_taps;
+        let mut output = Vec::with_capacity(
+            (input.len() as u64 * self.to_rate as u64 / self.from_rate as u64) as usize + 1,
+        );
+
+        while self.pos + self.half_taps < self.history.len() {
+            let start = self.pos + 1 - self.half_taps;
+            let phase = self.pos_frac * self.phases / self.den;
+            let coeffs = &self.table[phase * taps..(phase + 1) * taps];
+            let window = &self.history[start..start + taps];
+            output.push(coeffs.iter().zip(window).map(|(c, x)| c * x).sum());
+
+            self.pos += self.step_int;
+            self.pos_frac += self.step_frac;
+            if self.pos_frac >= self.den {
+                self.pos_frac -= self.den;
+                self.pos += 1;
+            }
+        }
+
+        // Drop input that no future output instant can reach.
+        let consumed = (self.pos + 1 - self.half_taps).min(self.history.len());
+        self.history.drain(..consumed);
+        self.pos -= consumed;
+
+        self.output_len += output.len() as u64;
+        output
+    }
+
+    /// Emit the samples still held back for look-ahead, padding with silence.
+    ///
+    /// After `flush`, the total output length is `ceil(input * to / from)`.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let expected = (self.input_len * self.to_rate as u64).div_ceil(self.from_rate as u64);
+        let pending = expected.saturating_sub(self.output_len) as usize;
+        if pending == 0 {
+            return Vec::new();
+        }
+
+        let mut tail = self.process(&vec![0.0; self.half_taps]);
+        tail.truncate(pending);
+        self.input_len -= self.half_taps as u64;
+        self.output_len = expected;
+        tail
+    }
+}
+
+/// Resample a complete buffer in one go (convenience for offline use).
+pub fn resample(input: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let mut resampler = Resampler::new(from_rate, to_rate);
+    let mut output = resampler.process(input);
+    output.extend(resampler.flush());
+    output
+}
+
+/// Build the polyphase coefficient table.
+///
+/// Row `p` holds the kernel for an output instant `p / phases` of an input
+/// sample past the newest "past" tap. Each row is normalised to unity DC gain.
+fn build_table(phases: usize, half_taps: usize, half_len: f64, cutoff: f64) -> Vec<f32> {
+    let taps = 2 * half_taps;
+    let norm = bessel_i0(KAISER_BETA);
+    let mut table = Vec::with_capacity(phases * taps);
+
+    for p in 0..phases {
+        let frac = p as f64 / phases as f64;
+        let row: Vec<f64> = (0..taps)
+            .map(|j| {
+                let t = j as f64 - (half_taps as f64 - 1.0) - frac;
+                let x = t / half_len;
+                if x.abs() >= 1.0 {
+                    return 0.0;
+                }
+                let window = bessel_i0(KAISER_BETA * (1.0 - x * x).sqrt()) / norm;
+                2.0 * cutoff * sinc(2.0 * cutoff * t) * window
+            })
+            .collect();
+        let sum: f64 = row.iter().sum();
+        table.extend(row.iter().map(|&c| (c / sum) as f32));
+    }
+
+    table
+}
+
+/// Normalised sinc: sin(πx) / (πx).
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Zeroth-order modified Bessel function of the first kind (series form).
+fn bessel_i0(x: f64) -> f64 {
+    let half = x / 2.0;
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..50 {
+        term *= half / k as f64;
+        let t2 = term * term;
+        sum += t2;
+        if t2 < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generate `secs` seconds of a sine tone.
+    fn sine(freq: f64, rate: u32, secs: f64, amplitude: f64) -> Vec<f32> {
+        let n = (rate as f64 * secs) as usize;
+        (0..n)
+            .map(|i| (amplitude * (2.0 * PI * freq * i as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    /// Amplitude of the `freq` component of `signal` (Goertzel algorithm).
+    fn tone_amplitude(signal: &[f32], freq: f64, rate: u32) -> f64 {
+        let w = 2.0 * PI * freq / rate as f64;
+        let coeff = 2.0 * w.cos();
+        let (mut s1, mut s2) = (0.0f64, 0.0f64);
+        for &x in signal {
+            let s0 = x as f64 + coeff * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        let power = s1 * s1 + s2 * s2 - coeff * s1 * s2;
+        2.0 * power.max(0.0).sqrt() / signal.len() as f64
+    }
+
+    fn rms(signal: &[f32]) -> f64 {
+        (signal.iter().map(|&x| (x as f64).powi(2)).sum::<f64>() / signal.len() as f64).sqrt()
+    }
+
+    /// Trim the filter's start-up and tail transients.
+    fn steady(signal: &[f32]) -> &[f32] {
+        &signal[500..signal.len() - 500]
+    }
+
+    #[test]
+    fn resample_same_rate_is_identity() {
+        let input = vec![0.1, 0.2, 0.3, 0.4];
+        let output = resample(&input, 48000, 48000);
+        assert_eq!(input, output);
+    }
+
+    #[test]
+    fn resample_halves_length_when_doubling_ratio() {
+        // 48kHz → 24kHz should halve the number of samples
+        let input: Vec<f32> = (0..480).map(|i| (i as f32) / 480.0).collect();
+        let output = resample(&input, 48000, 24000);
+        assert_eq!(output.len(), 240);
+    }
+
+    #[test]
+    fn output_length_matches_ratio_for_uneven_rates() {
+        let input = vec![0.0f32; 44100];
+        assert_eq!(resample(&input, 44100, 24000).len(), 24000);
+        let input = vec![0.0f32; 16001];
+        assert_eq!(resample(&input, 16000, 24000).len(), 24002);
+    }
+
+    #[test]
+    fn passband_tone_matches_reference_signal() {
+        // A 1kHz tone resampled 44.1k → 24k should match the same tone
+        // generated natively at 24kHz, sample for sample.
+        let input = sine(1000.0, 44100, 1.0, 0.5);
+        let output = resample(&input, 44100, 24000);
+        let reference = sine(1000.0, 24000, 1.0, 0.5);
+
+        assert_eq!(output.len(), reference.len());
+        let max_err = steady(&output)
+            .iter()
+            .zip(steady(&reference))
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_err < 1e-3, "max error vs reference = {max_err}");
+    }
+
+    #[test]
+    fn passband_amplitude_is_preserved() {
+        for freq in [300.0, 1000.0, 4000.0, 8000.0] {
+            let output = resample(&sine(freq, 48000, 1.0, 0.5), 48000, 24000);
+            let amp = tone_amplitude(steady(&output), freq, 24000);
+            assert!((amp - 0.5).abs() < 0.005, "{freq}Hz amplitude = {amp}");
+        }
+    }
+
+    #[test]
+    fn tones_above_target_nyquist_do_not_alias() {
+        // 15kHz cannot be represented at 24kHz. Naive decimation folds it
+        // down to 9kHz; the anti-aliasing filter must remove it instead.
+        let output = resample(&sine(15000.0, 48000, 1.0, 0.5), 48000, 24000);
+        let alias = tone_amplitude(steady(&output), 9000.0, 24000);
+        let attenuation_db = 20.0 * (alias / 0.5).log10();
+        assert!(attenuation_db < -70.0, "alias at {attenuation_db:.1} dB");
+        assert!(rms(steady(&output)) < 1e-3);
+    }
+
+    #[test]
+    fn chunked_processing_matches_one_shot() {
+        // 250ms chunks at 44.1kHz, as delivered by the capture loop.
+        let input: Vec<f32> = sine(440.0, 44100, 2.0, 0.3)
+            .iter()
+            .zip(sine(3100.0, 44100, 2.0, 0.2))
+            .map(|(a, b)| a + b)
+            .collect();
+        let whole = resample(&input, 44100, 24000);
+
+        let mut resampler = Resampler::new(44100, 24000);
+        let mut chunked = Vec::new();
+        for chunk in input.chunks(11025) {
+            chunked.extend(resampler.process(chunk));
+        }
+        chunked.extend(resampler.flush());
+
+        assert_eq!(whole.len(), chunked.len());
+        let max_diff = whole
+            .iter()
+            .zip(&chunked)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_diff < 1e-6, "chunk boundary mismatch = {max_diff}");
+    }
+
+    #[test]
+    fn upsampling_preserves_tone() {
+        let output = resample(&sine(1000.0, 16000, 1.0, 0.5), 16000, 24000);
+        let reference = sine(1000.0, 24000, 1.0, 0.5);
+        assert_eq!(output.len(), reference.len());
+        let max_err = steady(&output)
+            .iter()
+            .zip(steady(&reference))
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_err < 1e-3, "max error vs reference = {max_err}");
+    }
+}
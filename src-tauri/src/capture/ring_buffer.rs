@@ -0,0 +1,221 @@
+// ring_buffer.rs — Lock-free single-producer/single-consumer sample queue.
+//
+// The cpal data callback runs on a real-time audio thread where taking a
+// lock or allocating can stall the device and cause dropouts. This queue is
+// preallocated once, never blocks, and never allocates: the producer writes
+// as many samples as fit and counts the rest as dropped, the drain loop pops
+// whatever has accumulated every chunk.
+//
+// Samples are stored as `AtomicU32` bit patterns so the whole thing is safe
+// Rust — no `unsafe` cell juggling needed for `f32` payloads.
+
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Shared {
+    slots: Box<[AtomicU32]>,
+    /// Total samples ever written. Only the producer stores to it.
+    head: AtomicUsize,
+    /// Total samples ever read. Only the consumer stores to it.
+    tail: AtomicUsize,
+    /// Samples discarded because the queue was full.
+    dropped: AtomicU64,
+}
+
+impl Shared {
+    fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        head.wrapping_sub(tail)
+    }
+}
+
+/// Writing half — owned by the audio callback.
+pub struct RingProducer {
+    shared: Arc<Shared>,
+}
+
+/// Reading half — owned by the drain loop.
+pub struct RingConsumer {
+    shared: Arc<Shared>,
+}
+
+/// Create a queue holding up to `capacity` samples.
+pub fn sample_ring(capacity: usize) -> (RingProducer, RingConsumer) {
+    assert!(capacity > 0, "ring capacity must be non-zero");
+    let slots = (0..capacity).map(|_| AtomicU32::new(0)).collect();
+    let shared = Arc::new(Shared {
+        slots,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        dropped: AtomicU64::new(0),
+    });
+    (
+        RingProducer {
+            shared: Arc::clone(&shared),
+        },
+        RingConsumer { shared },
+    )
+}
+
+impl RingProducer {
+    /// Push samples without blocking. Samples that don't fit are dropped and
+    /// added to the overflow counter. Returns how many were stored.
+    pub fn push_iter<I>(&mut self, samples: I) -> usize
+    where
+        I: ExactSizeIterator<Item = f32>,
+    {
+        let shared = &*self.shared;
+        let capacity = shared.slots.len();
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        let free = capacity - head.wrapping_sub(tail);
+
+        let total = samples.len();
+        let mut written = 0;
+        for sample in samples.take(free) {
+            shared.slots[head.wrapping_add(written) % capacity]
+                .store(sample.to_bits(), Ordering::Relaxed);
+            written += 1;
+        }
+        shared
+            .head
+            .store(head.wrapping_add(written), Ordering::Release);
+
+        if written < total {
+            shared
+                .dropped
+                .fetch_add((total - written) as u64, Ordering::Relaxed);
+        }
+        written
+    }
+}
+
+impl RingConsumer {
+    /// Move every queued sample onto the end of `out`. Returns the count.
+    pub fn pop_into(&mut self, out: &mut Vec<f32>) -> usize {
+        let shared = &*self.shared;
+        let capacity = shared.slots.len();
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        let available = head.wrapping_sub(tail);
+
+        out.reserve(available);
+        for i in 0..available {
+            let bits = shared.slots[tail.wrapping_add(i) % capacity].load(Ordering::Relaxed);
+            out.push(f32::from_bits(bits));
+        }
+        shared
+            .tail
+            .store(tail.wrapping_add(available), Ordering::Release);
+        available
+    }
+
+    /// Samples currently queued.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Whether no samples are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of samples the queue can hold.
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+
+    /// Total samples dropped by the producer since the queue was created.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_come_out_in_order() {
+        let (mut tx, mut rx) = sample_ring(8);
+        assert_eq!(tx.push_iter([1.0, 2.0, 3.0].into_iter()), 3);
+        assert_eq!(rx.len(), 3);
+
+        let mut out = Vec::new();
+        assert_eq!(rx.pop_into(&mut out), 3);
+        assert_eq!(out, vec![1.0, 2.0, 3.0]);
+        assert_eq!(rx.len(), 0);
+    }
+
+    #[test]
+    fn overflow_is_dropped_and_counted() {
+        let (mut tx, mut rx) = sample_ring(4);
+        assert_eq!(tx.push_iter([1.0, 2.0, 3.0, 4.0, 5.0, 6.0].into_iter()), 4);
+        assert_eq!(rx.dropped(), 2);
+
+        // Newest samples are the ones lost; the queue keeps the oldest.
+        let mut out = Vec::new();
+        rx.pop_into(&mut out);
+        assert_eq!(out, vec![1.0, 2.0, 3.0, 4.0]);
+
+        // A full queue drops everything.
+        tx.push_iter([0.0; 4].into_iter());
+        tx.push_iter([9.0; 3].into_iter());
+        assert_eq!(rx.dropped(), 5);
+    }
+
+    #[test]
+    fn wraps_around_the_buffer() {
+        let (mut tx, mut rx) = sample_ring(5);
+        let mut out = Vec::new();
+        for round in 0..10 {
+            let base = round as f32 * 3.0;
+            tx.push_iter([base, base + 1.0, base + 2.0].into_iter());
+            out.clear();
+            rx.pop_into(&mut out);
+            assert_eq!(out, vec![base, base + 1.0, base + 2.0]);
+        }
+        assert_eq!(rx.dropped(), 0);
+    }
+
+    #[test]
+    fn push_iter_converts_without_intermediate_vec() {
+        let (mut tx, mut rx) = sample_ring(4);
+        let raw: [i16; 2] = [i16::MAX, 0];
+        tx.push_iter(raw.iter().map(|&s| s as f32 / i16::MAX as f32));
+        let mut out = Vec::new();
+        rx.pop_into(&mut out);
+        assert_eq!(out, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn concurrent_producer_and_consumer_lose_nothing() {
+        const TOTAL: usize = 200_000;
+        let (mut tx, mut rx) = sample_ring(1024);
+
+        let producer = std::thread::spawn(move || {
+            let mut next = 0usize;
+            while next < TOTAL {
+                let end = (next + 64).min(TOTAL);
+                let written = tx.push_iter((next..end).map(|i| i as f32));
+                next += written;
+                if written == 0 {
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut out = Vec::with_capacity(TOTAL);
+        while out.len() < TOTAL {
+            if rx.pop_into(&mut out) == 0 {
+                std::thread::yield_now();
+            }
+        }
+        producer.join().unwrap();
+
+        assert!(out.iter().enumerate().all(|(i, &s)| s == i as f32));
+    }
+}
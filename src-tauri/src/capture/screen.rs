@@ -1,14 +1,18 @@
 // screen.rs — Captures the primary monitor, downscales, JPEG-encodes,
-// base64-encodes, and emits Tauri events. Includes perceptual-hash
+// base64-encodes, and emits `capture:frame` events. Includes perceptual-hash
 // frame diffing so unchanged screens are skipped.
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use image::codecs::jpeg::JpegEncoder;
 use image::{imageops, DynamicImage, GenericImageView, GrayImage};
 use serde::Serialize;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter};
+use std::time::Duration;
+
+use super::task::{ExitHook, TaskSlot};
+use crate::budget::{scaled_size, FramePlan, FrameSize};
+use crate::error::BemeError;
+use crate::events::EventSink;
 
 // ── Monitor info ────────────────────────────────────────────────────────
 /// Describes a connected display, returned by the `list_monitors` command.
@@ -27,8 +31,9 @@ pub struct MonitorInfo {
 }
 
 /// Enumerate all connected monitors.
-pub fn list_monitors() -> Result<Vec<MonitorInfo>, String> {
-    let monitors = xcap::Monitor::all().map_err(|e| format!("enumerate monitors: {e}"))?;
+pub fn list_monitors() -> Result<Vec<MonitorInfo>, BemeError> {
+    let monitors =
+        xcap::Monitor::all().map_err(|e| BemeError::Capture(format!("enumerate monitors: {e}")))?;
     Ok(monitors
         .iter()
         .map(|m| MonitorInfo {
@@ -41,6 +46,31 @@ pub fn list_monitors() -> Result<Vec<MonitorInfo>, String> {
         .collect())
 }
 
+/// The window the user is (most likely) working in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FocusedWindow {
+    /// Application / process name
+    pub app_name: String,
+    pub title: String,
+    pub process_id: u32,
+}
+
+/// The frontmost window not belonging to process `skip_pid` (normally
+/// beme itself). xcap 0.1 has no focus API, but lists windows topmost
+/// first, so this is the first visible, titled one.
+pub fn focused_window(skip_pid: u32) -> Result<Option<FocusedWindow>, BemeError> {
+    let windows =
+        xcap::Window::all().map_err(|e| BemeError::Capture(format!("enumerate windows: {e}")))?;
+    Ok(windows
+        .iter()
+        .find(|w| !w.is_minimized() && !w.title().is_empty() && w.process_id() != skip_pid)
+        .map(|w| FocusedWindow {
+            app_name: w.app_name().to_string(),
+            title: w.title().to_string(),
+            process_id: w.process_id(),
+        }))
+}
+
 // ── Event payload ───────────────────────────────────────────────────────
 /// The JSON payload emitted on every new frame via the `capture:frame` event.
 #[derive(Clone, Serialize)]
@@ -62,15 +92,15 @@ pub struct FramePayload {
 /// Holds capture configuration and runtime state.
 ///
 /// ## Ownership & thread-safety
-/// * `is_capturing` is an `Arc<AtomicBool>` — a thread-safe boolean that
-///   can be shared across threads without a lock.
-/// * `last_hash` uses a `Mutex` because we only need interior mutability
-///   and the critical section is tiny.
+/// * `task` tracks the one running capture loop (see [`TaskSlot`]); starting
+///   while it runs is a no-op and stopping cancels it immediately.
+/// * `last_hash` is an `Arc<Mutex>` shared with the loop so diffing carries
+///   over when capture is restarted.
 /// * The struct itself is wrapped in `Arc` when stored in Tauri managed state
 ///   so multiple commands can reference it.
 pub struct ScreenCapture {
-    /// Whether the capture loop is currently running.
-    is_capturing: Arc<AtomicBool>,
+    /// The running capture loop, if any.
+    task: TaskSlot,
     /// Milliseconds between successive screen captures.
     interval_ms: u64,
     /// Maximum width in pixels; images wider than this are downscaled.
@@ -78,7 +108,7 @@ pub struct ScreenCapture {
     /// JPEG compression quality (1–100).
     jpeg_quality: u8,
     /// Perceptual hash of the most recently emitted frame, used for diffing.
-    last_hash: Mutex<u64>,
+    last_hash: Arc<Mutex<u64>>,
     /// xcap monitor ID to capture. `None` means primary (or first available).
     selected_monitor_id: Mutex<Option<u32>>,
 }
@@ -89,11 +119,11 @@ impl ScreenCapture {
     /// Typical defaults: `interval_ms = 2000`, `max_width = 1024`, `jpeg_quality = 75`.
     pub fn new(interval_ms: u64, max_width: u32, jpeg_quality: u8) -> Self {
         Self {
-            is_capturing: Arc::new(AtomicBool::new(false)),
+            task: TaskSlot::default(),
             interval_ms,
             max_width,
             jpeg_quality,
-            last_hash: Mutex::new(0),
+            last_hash: Arc::new(Mutex::new(0)),
             selected_monitor_id: Mutex::new(None),
         }
     }
@@ -103,124 +133,168 @@ impl ScreenCapture {
         *self.selected_monitor_id.lock().unwrap() = id;
     }
 
+    /// Name of the monitor that would be captured, if it can be found.
+    pub fn monitor_name(&self) -> Option<String> {
+        find_monitor(self.selected_monitor_id())
+            .ok()
+            .map(|m| m.name().to_string())
+    }
+
     /// Get the currently selected monitor ID.
     pub fn selected_monitor_id(&self) -> Option<u32> {
         *self.selected_monitor_id.lock().unwrap()
     }
 
-    /// Flip the capturing flag on/off. Returns the **new** state.
-    ///
-    /// `Ordering::SeqCst` (sequentially consistent) is the strongest memory
-    /// ordering — fine for a toggle that happens infrequently.
-    pub fn toggle(&self) -> bool {
-        let was = self.is_capturing.fetch_xor(true, Ordering::SeqCst);
-        // fetch_xor returns the *previous* value; XOR with true flips the bit.
-        !was
-    }
-
-    /// Check whether the capture loop is active.
-    #[allow(dead_code)]
+    /// Check whether the capture loop is running.
     pub fn is_capturing(&self) -> bool {
-        self.is_capturing.load(Ordering::SeqCst)
+        self.task.is_running()
     }
 
-    /// Start the capture loop on a background tokio task.
+    /// Start the capture loop on a background tokio task. Returns `false` if
+    /// it was already running (only one loop ever runs at a time).
     ///
-    /// The loop keeps running while `is_capturing` is true.
     /// Each iteration:
     ///   1. Grabs a screenshot via `xcap`
     ///   2. Computes a perceptual hash and skips if similar to the last frame
     ///   3. Downscales, JPEG-encodes, base64-encodes
-    ///   4. Emits a `capture:frame` event on the Tauri app handle
+    ///   4. Emits a `capture:frame` event on the event sink
     ///
     /// ## Why `&self` + clones?
     /// Tauri commands receive shared references, and `tokio::spawn` requires
     /// `'static` data.  We clone the `Arc`s / values we need so the spawned
     /// future owns them independently of `self`.
-    pub async fn start_loop(
+    pub async fn start(
         &self,
-        app_handle: AppHandle,
+        events: Arc<dyn EventSink>,
         stream_manager: Option<Arc<crate::stream_manager::StreamManager>>,
-    ) {
+    ) -> bool {
         // Clone the pieces we need so the spawned task owns them.
-        let flag = Arc::clone(&self.is_capturing);
         let interval = self.interval_ms;
         let max_w = self.max_width;
         let quality = self.jpeg_quality;
         let monitor_id = self.selected_monitor_id();
+        let last_hash = Arc::clone(&self.last_hash);
+        if let Some(ref sm) = stream_manager {
+            sm.set_monitor_name(self.monitor_name());
+        }
 
-        // We need the Mutex to travel into the spawned task. Because
-        // `Mutex<u64>` isn't Clone, we wrap access through a shared Arc.
-        // However, `self.last_hash` is already inside `ScreenCapture` which
-        // is stored in an Arc in Tauri state, so we use a separate Arc<Mutex>
-        // here that we initialise from the current value.
-        let prev_hash = {
-            let h = self.last_hash.lock().unwrap();
-            *h
-        };
-        let last_hash = Arc::new(Mutex::new(prev_hash));
-        let last_hash_self = Arc::clone(&last_hash);
-
-        tokio::spawn(async move {
-            log::info!("Screen capture loop started (interval={}ms)", interval);
-
-            while flag.load(Ordering::SeqCst) {
-                match capture_frame(max_w, quality, &last_hash, monitor_id) {
-                    Ok(Some(payload)) => {
-                        log::debug!(
-                            "Emitting capture:frame ({}x{}, diff={:.1}%)",
-                            payload.width,
-                            payload.height,
-                            payload.diff_pct
-                        );
-                        // Send frame to AI pipeline if configured
-                        if let Some(ref sm) = stream_manager {
-                            sm.analyze_frame(payload.data.clone(), app_handle.clone());
+        self.task
+            .start(move |cancel, guard| {
+                tokio::spawn(async move {
+                    let _guard = guard;
+                    log::info!("Screen capture loop started (interval={}ms)", interval);
+
+                    while !cancel.is_cancelled() {
+                        let size = FrameSize {
+                            max_width: max_w,
+                            jpeg_quality: quality,
+                        };
+                        let planner = stream_manager.as_deref().map(|sm| {
+                            move |source| {
+                                sm.plan_frame(source, size, Duration::from_millis(interval))
+                            }
+                        });
+                        match capture_frame(size, planner, &last_hash, monitor_id) {
+                            Ok(Some((payload, plan))) => {
+                                log::debug!(
+                                    "Emitting capture:frame ({}x{}, diff={:.1}%)",
+                                    payload.width,
+                                    payload.height,
+                                    payload.diff_pct
+                                );
+                                // Send frame to AI pipeline if configured
+                                if let Some(ref sm) = stream_manager {
+                                    sm.analyze_frame(payload.data.clone(), plan);
+                                }
+
+                                events.send("capture:frame", &payload);
+                            }
+                            Ok(None) => {
+                                log::debug!("Frame skipped (similar to previous)");
+                            }
+                            Err(e) => {
+                                log::error!("Capture error: {}", e);
+                            }
                         }
 
-                        if let Err(e) = app_handle.emit("capture:frame", &payload) {
-                            log::error!("Failed to emit capture:frame: {}", e);
+                        // Wake early on stop instead of finishing the interval.
+                        tokio::select! {
+                            _ = cancel.cancelled() => {}
+                            _ = tokio::time::sleep(Duration::from_millis(interval)) => {}
                         }
                     }
-                    Ok(None) => {
-                        log::debug!("Frame skipped (similar to previous)");
-                    }
-                    Err(e) => {
-                        log::error!("Capture error: {}", e);
-                    }
-                }
 
-                tokio::time::sleep(std::time::Duration::from_millis(interval)).await;
-            }
+                    log::info!("Screen capture loop stopped");
+                });
+            })
+            .await
+    }
+
+    /// Stop the capture loop and wait for it to exit. Returns `false` if it
+    /// wasn't running.
+    pub async fn stop(&self) -> bool {
+        self.task.stop().await
+    }
 
-            log::info!("Screen capture loop stopped");
-        });
-
-        // Sync back the hash so that if the loop is restarted, we keep the
-        // last known hash for diffing continuity.
-        // Note: This write happens after spawn returns (immediately), not
-        // after the loop finishes, so it seeds the spawned task with the
-        // current value — the spawned task maintains its own copy via
-        // `last_hash` Arc.
-        let final_h = last_hash_self.lock().unwrap();
-        let mut self_h = self.last_hash.lock().unwrap();
-        *self_h = *final_h;
+    /// Run `hook` when the capture loop exits without being stopped.
+    pub fn on_loop_exit(&self, hook: ExitHook) {
+        self.task.set_on_exit(hook);
+    }
+
+    /// Grab the selected monitor right now, whether or not it changed. The
+    /// diff baseline is updated so the loop doesn't send the same frame
+    /// again. With a stream manager the frame is sized for its token budget,
+    /// and the plan it was encoded from returned. Blocking — call it off the
+    /// async runtime.
+    pub fn snapshot(
+        &self,
+        stream_manager: Option<&crate::stream_manager::StreamManager>,
+    ) -> Result<(FramePayload, Option<FramePlan>), BemeError> {
+        let img = grab_monitor(self.selected_monitor_id()).map_err(BemeError::Capture)?;
+        let distance = update_hash(&img, &self.last_hash);
+        let diff_pct = (distance as f64 / 64.0) * 100.0;
+        let size = FrameSize {
+            max_width: self.max_width,
+            jpeg_quality: self.jpeg_quality,
+        };
+        let interval = Duration::from_millis(self.interval_ms);
+        let plan = stream_manager.map(|sm| sm.plan_frame(img.dimensions(), size, interval));
+        let size = plan.map_or(size, |p| p.size);
+        let payload = encode_frame(img, size.max_width, size.jpeg_quality, diff_pct)
+            .map_err(BemeError::Capture)?;
+        Ok((payload, plan))
     }
 }
 
 // ── Internal helpers ────────────────────────────────────────────────────
 
+/// Minimum number of differing hash bits for a frame to count as changed
+/// (5 of 64 bits ≈ 7.8 %).
+const MIN_CHANGED_BITS: u32 = 5;
+
 /// Grab a screenshot, diff it, and return the encoded payload (or None if
-/// the frame is too similar to the previous one).
+/// the frame is too similar to the previous one). `planner`, given the
+/// screen's size, decides how large to encode a changed frame; the plan is
+/// returned with it.
 fn capture_frame(
-    max_width: u32,
-    jpeg_quality: u8,
+    size: FrameSize,
+    planner: Option<impl FnOnce((u32, u32)) -> FramePlan>,
     last_hash: &Arc<Mutex<u64>>,
     monitor_id: Option<u32>,
-) -> Result<Option<FramePayload>, String> {
-    // 1. Capture the selected monitor (or primary / first available)
+) -> Result<Option<(FramePayload, Option<FramePlan>)>, String> {
+    let img = grab_monitor(monitor_id)?;
+    let Some(diff_pct) = frame_diff(&img, last_hash) else {
+        return Ok(None);
+    };
+    let plan = planner.map(|plan| plan(img.dimensions()));
+    let size = plan.map_or(size, |p| p.size);
+    encode_frame(img, size.max_width, size.jpeg_quality, diff_pct).map(|f| Some((f, plan)))
+}
+
+/// The selected monitor (or primary / first available).
+fn find_monitor(monitor_id: Option<u32>) -> Result<xcap::Monitor, String> {
     let monitors = xcap::Monitor::all().map_err(|e| format!("enumerate monitors: {e}"))?;
-    let monitor = if let Some(id) = monitor_id {
+    if let Some(id) = monitor_id {
         monitors.into_iter().find(|m| m.id() == id)
     } else {
         monitors
@@ -228,39 +302,72 @@ fn capture_frame(
             .find(|m| m.is_primary())
             .or_else(|| xcap::Monitor::all().ok()?.into_iter().next())
     }
-    .ok_or_else(|| "no monitors found".to_string())?;
+    .ok_or_else(|| "no monitors found".to_string())
+}
 
-    let raw = monitor
+/// Capture the selected monitor (or primary / first available).
+fn grab_monitor(monitor_id: Option<u32>) -> Result<DynamicImage, String> {
+    let raw = find_monitor(monitor_id)?
         .capture_image()
         .map_err(|e| format!("capture_image: {e}"))?;
 
     // `xcap` returns an `image::RgbaImage`. Wrap it in DynamicImage for
     // convenient manipulation.
-    let img = DynamicImage::ImageRgba8(raw);
+    Ok(DynamicImage::ImageRgba8(raw))
+}
+
+/// Diff a frame against the previous one and, if it changed enough,
+/// downscale and encode it. Shared by the live loop and offline replay so
+/// both see exactly the same frames.
+pub fn process_frame(
+    img: DynamicImage,
+    max_width: u32,
+    jpeg_quality: u8,
+    last_hash: &Arc<Mutex<u64>>,
+) -> Result<Option<FramePayload>, String> {
+    match frame_diff(&img, last_hash) {
+        Some(diff_pct) => encode_frame(img, max_width, jpeg_quality, diff_pct).map(Some),
+        None => Ok(None),
+    }
+}
 
+/// How much `img` differs from the previous frame, in percent, or `None`
+/// if it's too similar to send.
+fn frame_diff(img: &DynamicImage, last_hash: &Arc<Mutex<u64>>) -> Option<f64> {
     // 2. Compute perceptual hash and diff
-    let current_hash = compute_average_hash(&img);
-    let distance = {
-        let mut prev = last_hash.lock().unwrap();
-        let d = hamming_distance(current_hash, *prev);
-        *prev = current_hash;
-        d
-    };
+    let distance = update_hash(img, last_hash);
+
+    // Skip if fewer than 5 bits differ (< ~7.8 % change)
+    if distance < MIN_CHANGED_BITS {
+        return None;
+    }
 
     // Each bit of the 64-bit hash represents one 8×8 cell.
     // distance / 64.0 * 100.0 gives a percentage.
-    let diff_pct = (distance as f64 / 64.0) * 100.0;
+    Some((distance as f64 / 64.0) * 100.0)
+}
 
-    // Skip if fewer than 5 bits differ (< ~7.8 % change)
-    if distance < 5 {
-        return Ok(None);
-    }
+/// Store `img`'s hash as the new baseline and return how many bits differ
+/// from the previous one.
+fn update_hash(img: &DynamicImage, last_hash: &Arc<Mutex<u64>>) -> u32 {
+    let current_hash = compute_average_hash(img);
+    let mut prev = last_hash.lock().unwrap();
+    let d = hamming_distance(current_hash, *prev);
+    *prev = current_hash;
+    d
+}
 
-    // 3. Downscale if wider than max_width
+/// Downscale, JPEG-encode and base64-encode a frame.
+fn encode_frame(
+    img: DynamicImage,
+    max_width: u32,
+    jpeg_quality: u8,
+    diff_pct: f64,
+) -> Result<FramePayload, String> {
+    // 3. Downscale if wider than max_width (to the size the budget expects)
     let img = if img.width() > max_width {
-        let ratio = max_width as f64 / img.width() as f64;
-        let new_h = (img.height() as f64 * ratio).round() as u32;
-        img.resize_exact(max_width, new_h, imageops::FilterType::Triangle)
+        let (new_w, new_h) = scaled_size(img.width(), img.height(), max_width);
+        img.resize_exact(new_w, new_h, imageops::FilterType::Triangle)
     } else {
         img
     };
@@ -283,13 +390,13 @@ fn capture_frame(
     // 6. Build timestamp
     let ts = chrono_now_iso();
 
-    Ok(Some(FramePayload {
+    Ok(FramePayload {
         data: b64,
         timestamp: ts,
         width: w,
         height: h,
         diff_pct,
-    }))
+    })
 }
 
 /// Compute a 64-bit average hash (aHash) for perceptual image comparison.
@@ -0,0 +1,225 @@
+// task.rs — Single-instance bookkeeping for the capture loops.
+//
+// Each capture source owns a `TaskSlot`. Starting spawns the loop with a
+// `CancellationToken` and a `LoopGuard`; the loop exits when the token is
+// cancelled and drops the guard on the way out, which is how `stop` knows the
+// loop is really gone. A start while the previous loop is still shutting down
+// waits for it, so two loops never overlap. A loop that exits on its own
+// (device lost, error) runs the slot's exit hook, so the status can be
+// republished.
+
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+/// Called when a loop exits without having been cancelled.
+pub type ExitHook = Arc<dyn Fn() + Send + Sync>;
+
+/// Held by a running capture loop; dropping it marks the loop as exited.
+pub struct LoopGuard {
+    exited: Option<watch::Sender<()>>,
+    cancel: CancellationToken,
+    on_exit: Option<ExitHook>,
+}
+
+impl Drop for LoopGuard {
+    fn drop(&mut self) {
+        // Mark the loop exited first, so the hook sees it as stopped.
+        self.exited.take();
+        if !self.cancel.is_cancelled() {
+            if let Some(hook) = &self.on_exit {
+                hook();
+            }
+        }
+    }
+}
+
+struct Running {
+    cancel: CancellationToken,
+    /// Closes when the loop's `LoopGuard` is dropped.
+    exited: watch::Receiver<()>,
+}
+
+impl Running {
+    fn has_exited(&self) -> bool {
+        self.exited.has_changed().is_err()
+    }
+
+    fn is_active(&self) -> bool {
+        !self.cancel.is_cancelled() && !self.has_exited()
+    }
+}
+
+/// Holds at most one running capture loop.
+#[derive(Default)]
+pub struct TaskSlot {
+    current: Mutex<Option<Running>>,
+    on_exit: Mutex<Option<ExitHook>>,
+}
+
+impl TaskSlot {
+    /// Run `hook` whenever a loop from this slot exits on its own.
+    pub fn set_on_exit(&self, hook: ExitHook) {
+        *self.on_exit.lock().unwrap() = Some(hook);
+    }
+
+    /// Whether a loop is running and hasn't been asked to stop.
+    pub fn is_running(&self) -> bool {
+        self.current
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(Running::is_active)
+    }
+
+    /// Start a loop via `spawn` unless one is already running. Returns
+    /// `false` (without calling `spawn`) if a loop was already active.
+    pub async fn start<F>(&self, spawn: F) -> bool
+    where
+        F: FnOnce(CancellationToken, LoopGuard),
+    {
+        loop {
+            let mut stopping = {
+                let mut current = self.current.lock().unwrap();
+                match current.as_ref() {
+                    Some(r) if r.is_active() => return false,
+                    Some(r) if !r.has_exited() => r.exited.clone(),
+                    _ => {
+                        let cancel = CancellationToken::new();
+                        let (tx, rx) = watch::channel(());
+                        *current = Some(Running {
+                            cancel: cancel.clone(),
+                            exited: rx,
+                        });
+                        let guard = LoopGuard {
+                            exited: Some(tx),
+                            cancel: cancel.clone(),
+                            on_exit: self.on_exit.lock().unwrap().clone(),
+                        };
+                        // Outside the lock: the exit hook may ask for the status.
+                        drop(current);
+                        spawn(cancel, guard);
+                        return true;
+                    }
+                }
+            };
+            // The previous loop was cancelled but is still winding down.
+            let _ = stopping.changed().await;
+        }
+    }
+
+    /// Cancel the running loop and wait until it has exited. Returns whether
+    /// a loop was active.
+    pub async fn stop(&self) -> bool {
+        let (was_active, mut exited) = {
+            let current = self.current.lock().unwrap();
+            match current.as_ref() {
+                Some(r) => {
+                    let was_active = r.is_active();
+                    r.cancel.cancel();
+                    (was_active, r.exited.clone())
+                }
+                None => return false,
+            }
+        };
+        let _ = exited.changed().await;
+        was_active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Spawn a loop that counts how many instances are alive at once.
+    fn counting_loop(
+        alive: Arc<AtomicUsize>,
+        max_alive: Arc<AtomicUsize>,
+    ) -> impl FnOnce(CancellationToken, LoopGuard) {
+        move |cancel, guard| {
+            tokio::spawn(async move {
+                let n = alive.fetch_add(1, Ordering::SeqCst) + 1;
+                max_alive.fetch_max(n, Ordering::SeqCst);
+                cancel.cancelled().await;
+                // Simulate a loop that takes a moment to wind down.
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                alive.fetch_sub(1, Ordering::SeqCst);
+                drop(guard);
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn second_start_is_a_no_op() {
+        let slot = TaskSlot::default();
+        let alive = Arc::new(AtomicUsize::new(0));
+        let max = Arc::new(AtomicUsize::new(0));
+
+        assert!(slot.start(counting_loop(alive.clone(), max.clone())).await);
+        assert!(!slot.start(counting_loop(alive.clone(), max.clone())).await);
+        assert!(slot.is_running());
+
+        assert!(slot.stop().await);
+        assert!(!slot.is_running());
+        assert_eq!(alive.load(Ordering::SeqCst), 0);
+        assert!(!slot.stop().await);
+    }
+
+    #[tokio::test]
+    async fn rapid_restarts_never_overlap() {
+        let slot = Arc::new(TaskSlot::default());
+        let alive = Arc::new(AtomicUsize::new(0));
+        let max = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..5 {
+            slot.start(counting_loop(alive.clone(), max.clone())).await;
+            // Cancel without waiting, then immediately start again.
+            let stopper = Arc::clone(&slot);
+            let stop = tokio::spawn(async move { stopper.stop().await });
+            tokio::task::yield_now().await;
+            slot.start(counting_loop(alive.clone(), max.clone())).await;
+            stop.await.unwrap();
+            slot.stop().await;
+        }
+        assert_eq!(max.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn a_loop_that_exits_on_its_own_is_not_running() {
+        let slot = TaskSlot::default();
+        slot.start(|_cancel, guard| drop(guard)).await;
+        assert!(!slot.is_running());
+        assert!(!slot.stop().await);
+    }
+
+    #[tokio::test]
+    async fn exit_hook_runs_only_when_a_loop_exits_on_its_own() {
+        let slot = Arc::new(TaskSlot::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen_running = Arc::new(AtomicUsize::new(0));
+        let hook: ExitHook = {
+            let (slot, calls, seen_running) =
+                (Arc::downgrade(&slot), calls.clone(), seen_running.clone());
+            Arc::new(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if slot.upgrade().is_some_and(|s| s.is_running()) {
+                    seen_running.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        };
+        slot.set_on_exit(hook);
+
+        slot.start(|_cancel, guard| drop(guard)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(seen_running.load(Ordering::SeqCst), 0);
+
+        let alive = Arc::new(AtomicUsize::new(0));
+        slot.start(counting_loop(alive.clone(), alive.clone()))
+            .await;
+        slot.stop().await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,217 @@
+// controller.rs — The one place capture is started and stopped.
+//
+// The dashboard, tray menu and global shortcut all go through
+// `CaptureController`, so they can't disagree about whether capture is
+// running. Every change is published as a `capture:status` event and on a
+// watch channel the tray uses to keep its icon and label in sync, including
+// a capture loop stopping on its own.
+
+use crate::ai::ImageDetail;
+use crate::capture::audio::AudioCapture;
+use crate::capture::screen::ScreenCapture;
+use crate::capture::task::ExitHook;
+use crate::capture::CaptureStatus;
+use crate::error::BemeError;
+use crate::stream_manager::StreamManager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use tokio::sync::{watch, Mutex as TokioMutex};
+
+pub struct CaptureController {
+    screen: Arc<ScreenCapture>,
+    audio: Arc<AudioCapture>,
+    stream_manager: Arc<StreamManager>,
+    /// Whether starting capture includes audio (the dashboard's audio switch).
+    audio_enabled: AtomicBool,
+    /// Serializes start/stop so toggles from different places can't interleave.
+    op: TokioMutex<()>,
+    status_tx: watch::Sender<CaptureStatus>,
+}
+
+impl CaptureController {
+    pub fn new(
+        screen: Arc<ScreenCapture>,
+        audio: Arc<AudioCapture>,
+        stream_manager: Arc<StreamManager>,
+    ) -> Arc<Self> {
+        let (status_tx, _) = watch::channel(CaptureStatus {
+            screen: false,
+            audio: false,
+            audio_enabled: true,
+        });
+        Arc::new_cyclic(|me: &Weak<Self>| {
+            // A loop that dies (device lost, error) changes the status too
+            let me = me.clone();
+            let hook: ExitHook = Arc::new(move || {
+                if let Some(controller) = me.upgrade() {
+                    log::warn!("A capture loop stopped on its own");
+                    controller.publish();
+                }
+            });
+            screen.on_loop_exit(Arc::clone(&hook));
+            audio.on_loop_exit(hook);
+            Self {
+                screen,
+                audio,
+                stream_manager,
+                audio_enabled: AtomicBool::new(true),
+                op: TokioMutex::new(()),
+                status_tx,
+            }
+        })
+    }
+
+    /// Which capture loops are running right now.
+    pub fn status(&self) -> CaptureStatus {
+        CaptureStatus {
+            screen: self.screen.is_capturing(),
+            audio: self.audio.is_capturing(),
+            audio_enabled: self.audio_enabled(),
+        }
+    }
+
+    /// Receive every status change (the tray uses this for its label).
+    pub fn subscribe(&self) -> watch::Receiver<CaptureStatus> {
+        self.status_tx.subscribe()
+    }
+
+    pub fn audio_enabled(&self) -> bool {
+        self.audio_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Start screen capture, plus audio capture and the audio AI session
+    /// if audio is enabled.
+    pub async fn start(&self) -> CaptureStatus {
+        let _op = self.op.lock().await;
+        self.start_all().await;
+        self.publish()
+    }
+
+    /// Stop everything that's running.
+    pub async fn stop(&self) -> CaptureStatus {
+        let _op = self.op.lock().await;
+        self.stop_all().await;
+        self.publish()
+    }
+
+    /// Stop if screen capture is running, otherwise start.
+    pub async fn toggle(&self) -> CaptureStatus {
+        let _op = self.op.lock().await;
+        if self.screen.is_capturing() {
+            self.stop_all().await;
+        } else {
+            self.start_all().await;
+        }
+        self.publish()
+    }
+
+    /// Include or exclude audio. While capturing, audio starts or stops
+    /// immediately; otherwise it applies to the next start.
+    pub async fn set_audio_enabled(&self, enabled: bool) -> CaptureStatus {
+        let _op = self.op.lock().await;
+        self.audio_enabled.store(enabled, Ordering::SeqCst);
+        if self.screen.is_capturing() {
+            if enabled {
+                self.start_audio().await;
+            } else {
+                self.stop_audio().await;
+            }
+        }
+        self.publish()
+    }
+
+    /// Capture the selected monitor right now, regardless of the frame diff
+    /// or whether capture is running, and stream the answer as a normal
+    /// suggestion. `question` replaces the default prompt text and `detail`
+    /// the configured image detail. The frame is sized for the token budget
+    /// like any other. Returns the suggestion id.
+    pub async fn analyze_now(
+        &self,
+        question: Option<String>,
+        detail: Option<ImageDetail>,
+    ) -> Result<u64, BemeError> {
+        if !self.stream_manager.is_configured() {
+            return Err(BemeError::NotConfigured("AI provider".into()));
+        }
+        let screen = Arc::clone(&self.screen);
+        let sm = Arc::clone(&self.stream_manager);
+        let ((frame, plan), monitor_name) = tokio::task::spawn_blocking(move || {
+            screen
+                .snapshot(Some(&sm))
+                .map(|f| (f, screen.monitor_name()))
+        })
+        .await
+        .map_err(|e| BemeError::Capture(e.to_string()))??;
+        self.stream_manager.set_monitor_name(monitor_name);
+        let plan = match detail {
+            Some(detail) => plan.map(|p| p.with_detail(detail)),
+            None => plan,
+        };
+        let id = self
+            .stream_manager
+            .analyze_now(frame.data.clone(), question, plan)?;
+        self.stream_manager
+            .event_sink()
+            .send("capture:frame", &frame);
+        Ok(id)
+    }
+
+    async fn start_all(&self) {
+        let sm = Arc::clone(&self.stream_manager);
+        // Each capture session is its own thread and usage session; reset
+        // before the first frame can go out.
+        if !self.screen.is_capturing() {
+            sm.reset_thread("capture");
+            sm.start_usage_session();
+        }
+        if self.screen.start(sm.event_sink(), Some(sm)).await {
+            log::info!("Screen capture started");
+        }
+        if self.audio_enabled() {
+            self.start_audio().await;
+        }
+    }
+
+    async fn stop_all(&self) {
+        self.stop_audio().await;
+        if self.screen.stop().await {
+            log::info!("Screen capture stopped");
+        }
+    }
+
+    async fn start_audio(&self) {
+        let sm = Arc::clone(&self.stream_manager);
+        if !self
+            .audio
+            .start(sm.event_sink(), Some(Arc::clone(&sm)))
+            .await
+        {
+            return;
+        }
+        // Without a session the loop still reports levels; AI is optional.
+        match sm.start_audio_session().await {
+            Ok(()) => {}
+            Err(BemeError::NotConfigured(what)) => {
+                log::info!("Audio AI not started: {} not configured", what)
+            }
+            Err(e) => log::error!("Failed to start audio AI: {}", e),
+        }
+    }
+
+    async fn stop_audio(&self) {
+        if self.audio.stop().await {
+            if let Err(e) = self.stream_manager.stop_audio_session().await {
+                log::error!("Failed to stop audio AI: {}", e);
+            }
+        }
+    }
+
+    fn publish(&self) -> CaptureStatus {
+        let status = self.status();
+        self.status_tx.send_replace(status);
+        self.stream_manager
+            .event_sink()
+            .send("capture:status", &status);
+        status
+    }
+}
@@ -0,0 +1,188 @@
+// error.rs — The error type returned by Tauri commands and carried on
+// `ai:error` events.
+//
+// Serializes as `{ kind, message, retryable, retry_after_ms?, status?,
+// provider_code? }` so the frontend can tell "not configured" from "auth
+// failed" from "device missing" without parsing message strings.
+
+use crate::ai::AiError;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BemeError {
+    /// A provider or session that the operation needs hasn't been set up.
+    #[error("{0} not configured")]
+    NotConfigured(String),
+    #[error("No active audio session")]
+    NoAudioSession,
+    #[error(transparent)]
+    Ai(#[from] AiError),
+    /// Monitor enumeration or screen grab failed.
+    #[error("Screen capture failed: {0}")]
+    Capture(String),
+    /// Audio device enumeration failed or the device is missing.
+    #[error("Audio device error: {0}")]
+    AudioDevice(String),
+    /// Settings couldn't be located, read or parsed.
+    #[error("Settings error: {0}")]
+    Settings(String),
+    #[error("Replay failed: {0}")]
+    Replay(String),
+    /// A command argument was malformed.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl BemeError {
+    /// Stable machine-readable kind, e.g. `"auth"` or `"notConfigured"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BemeError::NotConfigured(_) => "notConfigured",
+            BemeError::NoAudioSession => "noAudioSession",
+            BemeError::Ai(AiError::ConnectionError(_)) => "connection",
+            BemeError::Ai(AiError::AuthError(_)) => "auth",
+            BemeError::Ai(AiError::RateLimited { .. }) => "rateLimited",
+            BemeError::Ai(AiError::ModelError(_)) => "model",
+            BemeError::Ai(AiError::InvalidResponse(_)) => "invalidResponse",
+            BemeError::Ai(AiError::ApiError { .. }) => "api",
+            BemeError::Capture(_) => "capture",
+            BemeError::AudioDevice(_) => "audioDevice",
+            BemeError::Settings(_) => "settings",
+            BemeError::Replay(_) => "replay",
+            BemeError::InvalidInput(_) => "invalidInput",
+            BemeError::Io(_) => "io",
+        }
+    }
+
+    /// Whether trying the same thing again later may succeed.
+    pub fn retryable(&self) -> bool {
+        match self {
+            BemeError::Ai(AiError::ApiError { status, code, .. }) => {
+                status.is_some_and(|s| s >= 500)
+                    || matches!(
+                        code.as_deref(),
+                        Some("server_error") | Some("rate_limit_exceeded")
+                    )
+            }
+            BemeError::Ai(AiError::ConnectionError(_))
+            | BemeError::Ai(AiError::RateLimited { .. })
+            | BemeError::Ai(AiError::ModelError(_))
+            | BemeError::Capture(_) => true,
+            _ => false,
+        }
+    }
+
+    /// How long the server asked us to wait, for rate limits.
+    pub fn retry_after_ms(&self) -> Option<u64> {
+        match self {
+            BemeError::Ai(AiError::RateLimited { retry_after_ms }) => Some(*retry_after_ms),
+            _ => None,
+        }
+    }
+
+    /// HTTP status from the AI service, when known.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            BemeError::Ai(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// The AI service's own error code, when it sent one.
+    pub fn provider_code(&self) -> Option<&str> {
+        match self {
+            BemeError::Ai(e) => e.provider_code(),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for BemeError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let retry_after_ms = self.retry_after_ms();
+        let status = self.status();
+        let provider_code = self.provider_code();
+        let len = 3
+            + retry_after_ms.is_some() as usize
+            + status.is_some() as usize
+            + provider_code.is_some() as usize;
+        let mut s = serializer.serialize_struct("BemeError", len)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("retryable", &self.retryable())?;
+        if let Some(ms) = retry_after_ms {
+            s.serialize_field("retry_after_ms", &ms)?;
+        }
+        if let Some(status) = status {
+            s.serialize_field("status", &status)?;
+        }
+        if let Some(code) = provider_code {
+            s.serialize_field("provider_code", code)?;
+        }
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serializes_code_message_and_retryability() {
+        let err = BemeError::Ai(AiError::AuthError("bad key".into()));
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({
+                "kind": "auth",
+                "message": "Authentication failed: bad key",
+                "retryable": false,
+            })
+        );
+    }
+
+    #[test]
+    fn rate_limits_carry_retry_after() {
+        let err = BemeError::from(AiError::RateLimited {
+            retry_after_ms: 1500,
+        });
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["kind"], "rateLimited");
+        assert_eq!(value["retryable"], true);
+        assert_eq!(value["retry_after_ms"], 1500);
+        assert_eq!(value["status"], 429);
+    }
+
+    #[test]
+    fn api_errors_carry_status_and_provider_code() {
+        let err = BemeError::from(AiError::ApiError {
+            status: None,
+            code: Some("rate_limit_exceeded".into()),
+            message: "slow down".into(),
+        });
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["kind"], "api");
+        assert_eq!(value["provider_code"], "rate_limit_exceeded");
+        assert_eq!(value["retryable"], true);
+        assert!(value.get("status").is_none());
+
+        let err = BemeError::from(AiError::ApiError {
+            status: Some(400),
+            code: Some("invalid_request_error".into()),
+            message: "bad".into(),
+        });
+        assert!(!err.retryable());
+        assert_eq!(err.status(), Some(400));
+    }
+
+    #[test]
+    fn not_configured_names_the_missing_piece() {
+        let err = BemeError::NotConfigured("Audio provider".into());
+        assert_eq!(err.to_string(), "Audio provider not configured");
+        assert_eq!(err.kind(), "notConfigured");
+        assert!(!err.retryable());
+    }
+}
@@ -0,0 +1,217 @@
+// events.rs — Where pipeline events go.
+//
+// `StreamManager` reports suggestions, errors and session status through an
+// `EventSink` instead of calling `AppHandle::emit` directly. The app wires in
+// `TauriSink`; tests use `ChannelSink` to assert on the exact event sequence,
+// and `JsonlSink` writes events to a file (the `BEME_TEST_LOG` E2E hook).
+
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Receives named events with JSON payloads.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: Value);
+}
+
+impl dyn EventSink + '_ {
+    /// Serialize `payload` and emit it.
+    pub fn send<T: Serialize + ?Sized>(&self, event: &str, payload: &T) {
+        match serde_json::to_value(payload) {
+            Ok(value) => self.emit(event, value),
+            Err(e) => log::error!("Failed to serialize {} payload: {}", event, e),
+        }
+    }
+}
+
+/// Discards everything. The default until a real sink is attached.
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    fn emit(&self, _event: &str, _payload: Value) {}
+}
+
+/// Forwards events to the frontend via `AppHandle::emit`.
+pub struct TauriSink {
+    app_handle: tauri::AppHandle,
+}
+
+impl TauriSink {
+    pub fn new(app_handle: tauri::AppHandle) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl EventSink for TauriSink {
+    fn emit(&self, event: &str, payload: Value) {
+        use tauri::Emitter;
+        if let Err(e) = self.app_handle.emit(event, payload) {
+            log::debug!("Failed to emit {}: {}", event, e);
+        }
+    }
+}
+
+/// An event captured by [`ChannelSink`].
+#[derive(Debug, Clone)]
+pub struct EmittedEvent {
+    pub name: String,
+    pub payload: Value,
+}
+
+/// Sends events down an unbounded channel, for tests and embedders.
+pub struct ChannelSink {
+    tx: mpsc::UnboundedSender<EmittedEvent>,
+}
+
+impl ChannelSink {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<EmittedEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+}
+
+impl EventSink for ChannelSink {
+    fn emit(&self, event: &str, payload: Value) {
+        let _ = self.tx.send(EmittedEvent {
+            name: event.to_string(),
+            payload,
+        });
+    }
+}
+
+/// Appends one JSON line per event to a file:
+/// `{"event":"ai:suggestion","timestamp":"…","payload":{…}}`.
+pub struct JsonlSink {
+    path: PathBuf,
+    /// Only these events are written; `None` writes everything.
+    only: Option<Vec<String>>,
+    lock: Mutex<()>,
+}
+
+impl JsonlSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            only: None,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Restrict the log to the named events.
+    pub fn only(mut self, events: &[&str]) -> Self {
+        self.only = Some(events.iter().map(|e| e.to_string()).collect());
+        self
+    }
+
+    /// The `BEME_TEST_LOG` sink (suggestions only), if the env var is set.
+    pub fn from_test_env() -> Option<Self> {
+        let path = std::env::var("BEME_TEST_LOG").ok()?;
+        Some(Self::new(path).only(&["ai:suggestion"]))
+    }
+}
+
+impl EventSink for JsonlSink {
+    fn emit(&self, event: &str, payload: Value) {
+        if let Some(only) = &self.only {
+            if !only.iter().any(|e| e == event) {
+                return;
+            }
+        }
+        let timestamp = payload
+            .get("timestamp")
+            .and_then(|t| t.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(crate::stream_manager::now_iso);
+        let line = serde_json::json!({
+            "event": event,
+            "timestamp": timestamp,
+            "payload": payload,
+        });
+
+        let _guard = self.lock.lock().unwrap();
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = result {
+            log::warn!("Failed to write {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Emits every event to each of several sinks.
+pub struct FanoutSink(pub Vec<Arc<dyn EventSink>>);
+
+impl EventSink for FanoutSink {
+    fn emit(&self, event: &str, payload: Value) {
+        for sink in &self.0 {
+            sink.emit(event, payload.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn channel_sink_delivers_in_order() {
+        let (sink, mut rx) = ChannelSink::new();
+        let sink: Arc<dyn EventSink> = Arc::new(sink);
+        sink.send("a", &json!({ "n": 1 }));
+        sink.send("b", &json!({ "n": 2 }));
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.name, "a");
+        assert_eq!(first.payload["n"], 1);
+        assert_eq!(rx.try_recv().unwrap().name, "b");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn jsonl_sink_filters_and_writes_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "beme-events-{}-{}.jsonl",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let sink = JsonlSink::new(&path).only(&["ai:suggestion"]);
+        sink.emit(
+            "ai:suggestion",
+            json!({ "text": "hi", "timestamp": "2025-01-01T00:00:00Z" }),
+        );
+        sink.emit("capture:audio-level", json!({ "level": 0.5 }));
+        sink.emit("ai:suggestion", json!({ "text": "there" }));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "ai:suggestion");
+        assert_eq!(lines[0]["timestamp"], "2025-01-01T00:00:00Z");
+        assert_eq!(lines[0]["payload"]["text"], "hi");
+        assert!(lines[1]["timestamp"].as_str().unwrap().ends_with('Z'));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn fanout_reaches_every_sink() {
+        let (a, mut rx_a) = ChannelSink::new();
+        let (b, mut rx_b) = ChannelSink::new();
+        let fanout = FanoutSink(vec![Arc::new(a), Arc::new(b)]);
+        fanout.emit("x", json!(null));
+        assert_eq!(rx_a.try_recv().unwrap().name, "x");
+        assert_eq!(rx_b.try_recv().unwrap().name, "x");
+    }
+}
@@ -1,9 +1,22 @@
 pub mod ai;
-mod capture;
-mod settings;
+pub mod budget;
+pub mod capture;
+pub mod controller;
+pub mod error;
+pub mod events;
+pub mod limits;
+pub mod metering;
+pub mod novelty;
+pub mod profiles;
+pub mod prompt_template;
+pub mod replay;
+pub mod settings;
+pub mod shortcuts;
 pub mod stream_manager;
 mod tray;
 
+use error::BemeError;
+
 #[allow(unused_imports)]
 use tauri::Manager;
 
@@ -15,38 +28,40 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-/// Start or stop screen capture. Returns the new capturing state.
+/// Start or stop capture (screen, plus audio when enabled). Returns the new
+/// capturing state.
 #[tauri::command]
 async fn toggle_capture(
-    state: tauri::State<'_, Arc<capture::screen::ScreenCapture>>,
-    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
-    app_handle: tauri::AppHandle,
-) -> Result<bool, String> {
-    let now_capturing = state.toggle();
-    if now_capturing {
+    controller: tauri::State<'_, Arc<controller::CaptureController>>,
+) -> Result<bool, BemeError> {
+    let status = controller.toggle().await;
+    if status.screen {
         log::info!("Screen capture toggled ON");
-        let sm = Arc::clone(&*sm_state);
-        state.start_loop(app_handle, Some(sm)).await;
     } else {
         log::info!("Screen capture toggled OFF");
     }
-    Ok(now_capturing)
+    Ok(status.screen)
 }
 
 /// List available monitors.
 #[tauri::command]
-fn list_monitors() -> Result<Vec<capture::screen::MonitorInfo>, String> {
+fn list_monitors() -> Result<Vec<capture::screen::MonitorInfo>, BemeError> {
     capture::screen::list_monitors()
 }
 
-/// Select which monitor to capture.
+/// Select which monitor to capture. Starts a new conversation thread, since
+/// earlier answers were about a different screen.
 #[tauri::command]
 fn select_monitor(
     state: tauri::State<'_, Arc<capture::screen::ScreenCapture>>,
+    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
     monitor_id: Option<u32>,
-) -> Result<(), String> {
-    state.set_monitor(monitor_id);
-    log::info!("Monitor selection changed to {:?}", monitor_id);
+) -> Result<(), BemeError> {
+    if state.selected_monitor_id() != monitor_id {
+        state.set_monitor(monitor_id);
+        log::info!("Monitor selection changed to {:?}", monitor_id);
+        sm_state.reset_thread("monitor");
+    }
     Ok(())
 }
 
@@ -59,7 +74,7 @@ async fn configure_ai(
     deployment: String,
     system_prompt: String,
     use_bearer: Option<bool>,
-) -> Result<(), String> {
+) -> Result<(), BemeError> {
     state.configure_azure(
         &endpoint,
         &api_key,
@@ -74,60 +89,191 @@ async fn configure_ai(
 #[tauri::command]
 async fn is_ai_configured(
     state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
-) -> Result<bool, String> {
+) -> Result<bool, BemeError> {
     Ok(state.is_configured())
 }
 
 /// List available audio output devices.
 #[tauri::command]
-fn list_audio_devices() -> Result<Vec<capture::audio::AudioDeviceInfo>, String> {
+fn list_audio_devices() -> Result<Vec<capture::audio::AudioDeviceInfo>, BemeError> {
     capture::audio::list_audio_devices()
 }
 
-/// Select which audio device to capture from.
+/// Select which audio device to capture from. Persisted to settings.
 #[tauri::command]
 fn select_audio_device(
     state: tauri::State<'_, Arc<capture::audio::AudioCapture>>,
+    app_handle: tauri::AppHandle,
     device_name: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), BemeError> {
+    let mut s = settings::Settings::load_from_app(&app_handle)?;
+    s.audio_device = device_name.clone();
     state.set_device(device_name);
-    Ok(())
+    s.save_to_app(&app_handle)
 }
 
-/// Start or stop audio capture. Returns the new capturing state.
+/// Choose the sample rate / channel count to capture from a device.
+/// Pass `None` to go back to the device's default config. Persisted to
+/// settings.
 #[tauri::command]
-async fn toggle_audio_capture(
+fn select_audio_config(
+    state: tauri::State<'_, Arc<capture::audio::AudioCapture>>,
+    app_handle: tauri::AppHandle,
+    device_name: String,
+    config: Option<capture::audio::AudioStreamConfig>,
+) -> Result<(), BemeError> {
+    let mut s = settings::Settings::load_from_app(&app_handle)?;
+    match &config {
+        Some(c) => s.audio_configs.insert(device_name.clone(), c.clone()),
+        None => s.audio_configs.remove(&device_name),
+    };
+    state.set_device_config(device_name, config);
+    s.save_to_app(&app_handle)
+}
+
+/// Build recorder options from settings, rooted at `<app data>/recordings`.
+pub(crate) fn recording_options(
+    app_handle: &tauri::AppHandle,
+    rec: &settings::RecordingSettings,
+) -> Result<capture::recorder::RecordingOptions, BemeError> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| BemeError::Settings(e.to_string()))?
+        .join("recordings");
+    Ok(capture::recorder::RecordingOptions {
+        dir,
+        max_segment_secs: rec.max_segment_secs,
+        max_segment_bytes: rec.max_segment_mb as u64 * 1024 * 1024,
+        max_segments: rec.max_segments,
+    })
+}
+
+/// Turn recording of captured audio to WAV files on or off.
+/// Applies from the next capture session and is persisted to settings.
+#[tauri::command]
+fn set_audio_recording(
     state: tauri::State<'_, Arc<capture::audio::AudioCapture>>,
-    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
     app_handle: tauri::AppHandle,
-) -> Result<bool, String> {
-    let now_capturing = state.toggle();
-    if now_capturing {
-        log::info!("Audio capture toggled ON");
-        let sm = Arc::clone(&*sm_state);
-        state.start_loop(app_handle, Some(sm));
+    enabled: bool,
+) -> Result<(), BemeError> {
+    let mut s = settings::Settings::load_from_app(&app_handle)?;
+    s.recording.enabled = enabled;
+    let options = if enabled {
+        Some(recording_options(&app_handle, &s.recording)?)
     } else {
-        log::info!("Audio capture toggled OFF");
+        None
+    };
+    state.set_recording(options);
+    s.save_to_app(&app_handle)
+}
+
+/// Include or exclude audio (capture + audio AI session). While capturing
+/// it starts or stops right away; otherwise it applies to the next start.
+#[tauri::command]
+async fn set_audio_enabled(
+    controller: tauri::State<'_, Arc<controller::CaptureController>>,
+    enabled: bool,
+) -> Result<capture::CaptureStatus, BemeError> {
+    Ok(controller.set_audio_enabled(enabled).await)
+}
+
+/// Capture the selected monitor right now, regardless of the frame diff, and
+/// stream the answer as a normal suggestion. `question` replaces the
+/// configured text sent with frames, and `detail` the configured image
+/// detail. Returns the suggestion id.
+#[tauri::command]
+async fn analyze_now(
+    controller: tauri::State<'_, Arc<controller::CaptureController>>,
+    question: Option<String>,
+    detail: Option<ai::ImageDetail>,
+) -> Result<u64, BemeError> {
+    controller.analyze_now(question, detail).await
+}
+
+/// Ask a follow-up ("why?", "how exactly?") about a screen suggestion. The
+/// reply streams as a new suggestion with `parent_id` set; returns its id.
+#[tauri::command]
+fn ask_followup(
+    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
+    suggestion_id: u64,
+    text: String,
+) -> Result<u64, BemeError> {
+    sm_state.ask_followup(suggestion_id, text)
+}
+
+/// Start a fresh conversation thread: the next frame is analyzed without
+/// earlier context. Returns the new thread id.
+#[tauri::command]
+fn reset_thread(sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>) -> u64 {
+    sm_state.reset_thread("reset")
+}
+
+/// Continue capture from an earlier screen suggestion instead of the latest
+/// one. Returns the new thread id.
+#[tauri::command]
+fn fork_thread(
+    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
+    suggestion_id: u64,
+) -> Result<u64, BemeError> {
+    sm_state.fork_thread(suggestion_id)
+}
+
+/// Token usage and what it cost: this session (since capture last started),
+/// today and each earlier day on record.
+#[tauri::command]
+fn get_usage(
+    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
+) -> metering::UsageReport {
+    sm_state.usage_report()
+}
+
+/// The spending limits, which one (if any) is reached, and any override.
+#[tauri::command]
+fn limit_status(
+    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
+) -> stream_manager::LimitStatus {
+    sm_state.limit_status()
+}
+
+/// Keep capturing past the spending limits for `minutes`; they apply again
+/// afterwards.
+#[tauri::command]
+fn override_limits(
+    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
+    minutes: u32,
+) -> Result<(), BemeError> {
+    if minutes == 0 {
+        return Err(BemeError::InvalidInput(
+            "override must last at least a minute".into(),
+        ));
     }
-    Ok(now_capturing)
+    sm_state.override_limits(std::time::Duration::from_secs(minutes as u64 * 60));
+    Ok(())
+}
+
+/// Report which capture loops are actually running.
+#[tauri::command]
+fn capture_status(
+    controller: tauri::State<'_, Arc<controller::CaptureController>>,
+) -> capture::CaptureStatus {
+    controller.status()
 }
 
 /// Start audio AI session — opens WebSocket to Azure Realtime API.
 #[tauri::command]
 async fn start_audio_ai(
     sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
-    app_handle: tauri::AppHandle,
-) -> Result<(), String> {
-    sm_state.start_audio_session(app_handle).await
+) -> Result<(), BemeError> {
+    sm_state.start_audio_session().await
 }
 
 /// Stop audio AI session — closes the WebSocket.
 #[tauri::command]
 async fn stop_audio_ai(
     sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
-    app_handle: tauri::AppHandle,
-) -> Result<(), String> {
-    sm_state.stop_audio_session(&app_handle).await
+) -> Result<(), BemeError> {
+    sm_state.stop_audio_session().await
 }
 
 /// Send an audio chunk to the AI session.
@@ -135,10 +281,10 @@ async fn stop_audio_ai(
 async fn send_audio_chunk(
     sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
     data: String,
-) -> Result<(), String> {
+) -> Result<(), BemeError> {
     let bytes = base64::engine::general_purpose::STANDARD
         .decode(&data)
-        .map_err(|e| format!("Invalid base64: {}", e))?;
+        .map_err(|e| BemeError::InvalidInput(format!("audio chunk is not base64: {}", e)))?;
     sm_state.process_audio_chunk(&bytes).await
 }
 
@@ -146,7 +292,7 @@ async fn send_audio_chunk(
 #[tauri::command]
 async fn get_prompts(
     state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
-) -> Result<std::collections::HashMap<String, String>, String> {
+) -> Result<std::collections::HashMap<String, String>, BemeError> {
     let (vision, audio) = state.get_prompts();
     let mut map = std::collections::HashMap::new();
     map.insert("vision".to_string(), vision);
@@ -154,37 +300,110 @@ async fn get_prompts(
     Ok(map)
 }
 
-/// Update a prompt (vision or audio) and persist to settings.
+/// Update a prompt (vision or audio) and persist to settings. Prompts
+/// using unknown `{{variables}}` are rejected.
 #[tauri::command]
 async fn update_prompt(
     state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
     app_handle: tauri::AppHandle,
     source: String,
     text: String,
-) -> Result<(), String> {
+) -> Result<(), BemeError> {
+    prompt_template::validate(&text)?;
     state.update_prompt(&source, &text);
 
-    // Persist to settings.toml
+    // Persist to settings.toml, into the live profile if there is one
     if let Ok(mut s) = settings::Settings::load_from_app(&app_handle) {
+        let active = app_handle.state::<profiles::ProfileState>().active();
+        let (vision, audio) = match active
+            .as_deref()
+            .and_then(|n| s.profiles.iter_mut().find(|p| p.name == n))
+        {
+            Some(p) => (&mut p.vision_prompt, &mut p.audio_prompt),
+            None => (&mut s.vision_prompt, &mut s.audio_prompt),
+        };
         match source.as_str() {
-            "vision" => s.vision_prompt = text,
-            "audio" => s.audio_prompt = text,
+            "vision" => *vision = text,
+            "audio" => *audio = text,
             _ => {}
         }
-        let dir = app_handle
-            .path()
-            .app_config_dir()
-            .map_err(|e| e.to_string())?;
-        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-        let path = dir.join("settings.toml");
-        let content = toml::to_string_pretty(&s).map_err(|e| e.to_string())?;
-        std::fs::write(&path, content).map_err(|e| e.to_string())?;
+        s.save_to_app(&app_handle)?;
+        profiles::sync(&app_handle, &s);
         log::info!("Prompt '{}' persisted to settings", source);
     }
 
     Ok(())
 }
 
+/// Replay a recording directory (JPEG frames + WAV) through the AI pipeline.
+/// `speed` scales playback (1.0 = real time, 0 = as fast as possible).
+/// Runs in the background and emits `replay:finished` with the totals, or
+/// `replay:error` if it fails part way.
+#[tauri::command]
+async fn start_replay(
+    sm_state: tauri::State<'_, Arc<stream_manager::StreamManager>>,
+    dir: String,
+    speed: Option<f64>,
+) -> Result<(), BemeError> {
+    let recording =
+        replay::Recording::load(std::path::Path::new(&dir)).map_err(BemeError::Replay)?;
+    let options = replay::ReplayOptions {
+        speed: speed.unwrap_or(1.0),
+        ..Default::default()
+    };
+    let replay = replay::Replay::new(recording, options).map_err(BemeError::Replay)?;
+    let sm = Arc::clone(&*sm_state);
+
+    tauri::async_runtime::spawn(async move {
+        let events = sm.event_sink();
+        match replay::run(replay, sm).await {
+            Ok(stats) => events.send("replay:finished", &stats),
+            Err(e) => {
+                log::error!("Replay failed: {}", e);
+                events.send("replay:error", &BemeError::Replay(e));
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Configure the AI provider(s) from saved settings: the scripted provider
+/// when selected, otherwise Azure if credentials are present. Shared by the
+/// app's startup and `beme-cli`.
+pub fn configure_ai_from_settings(
+    sm: &stream_manager::StreamManager,
+    s: &settings::Settings,
+) -> Result<(), BemeError> {
+    sm.set_suggestion_format(s.suggestion_format);
+    sm.set_generation_config(s.generation.clone());
+    sm.set_budget_settings(s.budget.clone());
+    sm.set_pricing(s.pricing.clone());
+    sm.set_limits(s.limits.clone());
+    sm.set_novelty_settings(s.novelty.clone());
+    sm.set_user_notes(&s.user_notes);
+    let (vision_prompt, audio_prompt) = s.prompts_for(s.active_profile.as_deref());
+    if s.provider == ai::scripted::PROVIDER_ID {
+        let path = std::path::Path::new(&s.scripted_fixture);
+        let provider =
+            ai::scripted::ScriptedProvider::from_file(path).map_err(BemeError::Settings)?;
+        sm.configure_provider(Arc::new(provider), vision_prompt, audio_prompt);
+        log::info!("Scripted provider loaded from {}", path.display());
+    } else if !s.endpoint.is_empty() && !s.api_key.is_empty() {
+        sm.configure_azure(
+            &s.endpoint,
+            &s.api_key,
+            &s.vision_deployment,
+            vision_prompt,
+            s.use_bearer,
+        );
+        if !s.audio_deployment.is_empty() {
+            sm.configure_audio(&s.endpoint, &s.api_key, &s.audio_deployment, audio_prompt);
+        }
+        log::info!("AI provider auto-configured from saved settings");
+    }
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     env_logger::init();
@@ -192,15 +411,24 @@ pub fn run() {
     let screen_capture = Arc::new(capture::screen::ScreenCapture::new(2000, 1024, 75));
     let audio_capture = Arc::new(capture::audio::AudioCapture::new(24000, 250));
     let stream_mgr = Arc::new(stream_manager::StreamManager::new());
+    let capture_controller = controller::CaptureController::new(
+        Arc::clone(&screen_capture),
+        Arc::clone(&audio_capture),
+        Arc::clone(&stream_mgr),
+    );
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(screen_capture)
         .manage(audio_capture)
         .manage(stream_mgr)
+        .manage(capture_controller)
+        .manage(shortcuts::ShortcutRegistry::default())
+        .manage(profiles::ProfileState::default())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_handler(tray::on_shortcut_event)
+                .with_handler(shortcuts::on_shortcut_event)
                 .build(),
         )
         .invoke_handler(tauri::generate_handler![
@@ -208,9 +436,19 @@ pub fn run() {
             toggle_capture,
             list_monitors,
             select_monitor,
-            toggle_audio_capture,
+            set_audio_enabled,
+            capture_status,
+            get_usage,
+            limit_status,
+            override_limits,
+            analyze_now,
+            ask_followup,
+            reset_thread,
+            fork_thread,
             list_audio_devices,
             select_audio_device,
+            select_audio_config,
+            set_audio_recording,
             configure_ai,
             is_ai_configured,
             start_audio_ai,
@@ -218,39 +456,89 @@ pub fn run() {
             send_audio_chunk,
             get_prompts,
             update_prompt,
+            start_replay,
             settings::save_settings,
-            settings::load_settings
+            settings::load_settings,
+            profiles::list_profiles,
+            profiles::save_profile,
+            profiles::delete_profile,
+            profiles::activate_profile,
+            profiles::set_profile_auto_switch,
+            profiles::current_window,
+            shortcuts::validate_shortcuts
         ])
         .setup(|app| {
             tray::setup_tray(app)?;
 
-            use tauri_plugin_global_shortcut::GlobalShortcutExt;
-            app.global_shortcut().register("ctrl+shift+b")?;
-            log::info!("Global shortcut Ctrl+Shift+B registered");
-
-            // Auto-configure AI provider from saved settings
+            // Route pipeline events to the frontend (and the E2E log if requested)
             let sm = app.state::<Arc<stream_manager::StreamManager>>();
-            if let Ok(s) = settings::Settings::load_from_app(app.handle()) {
-                if !s.endpoint.is_empty() && !s.api_key.is_empty() {
-                    sm.configure_azure(
-                        &s.endpoint,
-                        &s.api_key,
-                        &s.vision_deployment,
-                        &s.vision_prompt,
-                        s.use_bearer,
-                    );
-                    if !s.audio_deployment.is_empty() {
-                        sm.configure_audio(
-                            &s.endpoint,
-                            &s.api_key,
-                            &s.audio_deployment,
-                            &s.audio_prompt,
-                        );
+            let tauri_sink: Arc<dyn events::EventSink> =
+                Arc::new(events::TauriSink::new(app.handle().clone()));
+            match events::JsonlSink::from_test_env() {
+                Some(log) => sm.set_event_sink(Arc::new(events::FanoutSink(vec![
+                    tauri_sink,
+                    Arc::new(log),
+                ]))),
+                None => sm.set_event_sink(tauri_sink),
+            }
+
+            // `{{active_window}}` in prompts: the frontmost window that isn't ours
+            let own_pid = std::process::id();
+            sm.set_window_probe(Arc::new(move || {
+                let window = capture::screen::focused_window(own_pid).ok()??;
+                Some(if window.title.is_empty() {
+                    window.app_name
+                } else {
+                    format!("{} — {}", window.app_name, window.title)
+                })
+            }));
+
+            // Daily usage totals carry over between runs
+            let usage_log = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| BemeError::Settings(e.to_string()))
+                .and_then(|dir| {
+                    std::fs::create_dir_all(&dir)?;
+                    sm.open_usage_log(&dir.join("usage.json"))
+                });
+            if let Err(e) = usage_log {
+                log::warn!("Usage totals won't be kept: {}", e);
+            }
+
+            // Auto-configure AI provider and shortcuts from saved settings
+            let saved = settings::Settings::load_from_app(app.handle());
+            let shortcut_settings = match &saved {
+                Ok(s) => s.shortcuts.clone(),
+                Err(_) => settings::ShortcutSettings::default(),
+            };
+            match shortcuts::apply(app.handle(), &shortcut_settings) {
+                Ok(issues) => {
+                    for issue in issues {
+                        log::warn!("Shortcut {}: {}", issue.shortcut, issue.message);
                     }
-                    log::info!("AI provider auto-configured from saved settings");
+                }
+                // Saved shortcuts are broken; fall back to the defaults
+                Err(e) => {
+                    log::error!("{}", e);
+                    shortcuts::apply(app.handle(), &settings::ShortcutSettings::default())?;
                 }
             }
 
+            if let Ok(s) = saved {
+                if let Err(e) = configure_ai_from_settings(&sm, &s) {
+                    log::error!("AI provider not configured: {}", e);
+                }
+                profiles::init(app.handle(), &s);
+                let audio = app.state::<Arc<capture::audio::AudioCapture>>();
+                s.apply_audio_device(&audio);
+                if s.recording.enabled {
+                    audio.set_recording(Some(recording_options(app.handle(), &s.recording)?));
+                }
+            }
+
+            profiles::watch_focus(app.handle().clone());
+
             Ok(())
         })
         .run(tauri::generate_context!())
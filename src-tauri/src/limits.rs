@@ -0,0 +1,300 @@
+// limits.rs — Hard caps on what the AI may spend.
+//
+// A capture nobody remembers to stop keeps sending frames and audio all
+// weekend. `Limiter` caps the request rate, tokens per hour and cost per
+// UTC day; `StreamManager` checks it before each capture frame and audio
+// chunk and, while a limit is reached, drops frames and suspends the audio
+// session. Limits lift by themselves once their window moves on (the next
+// day, for cost), or can be overridden for a while.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::error::BemeError;
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Zero turns a limit off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LimitSettings {
+    /// Vision requests and audio turns per minute.
+    pub requests_per_minute: u32,
+    /// Input and output tokens per hour, both pipelines together.
+    pub tokens_per_hour: u64,
+    /// Cost per UTC day, in the pricing currency.
+    pub cost_per_day: f64,
+}
+
+impl LimitSettings {
+    pub fn validate(&self) -> Result<(), BemeError> {
+        if !self.cost_per_day.is_finite() || self.cost_per_day < 0.0 {
+            return Err(BemeError::InvalidInput(
+                "daily cost limit must be zero or more".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    RequestsPerMinute,
+    TokensPerHour,
+    CostPerDay,
+}
+
+/// A limit that has been reached.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitHit {
+    pub kind: LimitKind,
+    pub limit: f64,
+    /// What has been used in the limit's window.
+    pub used: f64,
+    /// Seconds until the limit lifts by itself.
+    pub resets_in_secs: u64,
+}
+
+impl LimitHit {
+    /// E.g. "tokens per hour limit reached (52000 of 50000)".
+    pub fn describe(&self) -> String {
+        match self.kind {
+            LimitKind::RequestsPerMinute => format!(
+                "requests per minute limit reached ({} of {})",
+                self.used, self.limit
+            ),
+            LimitKind::TokensPerHour => format!(
+                "tokens per hour limit reached ({} of {})",
+                self.used, self.limit
+            ),
+            LimitKind::CostPerDay => format!(
+                "daily cost limit reached ({:.2} of {:.2})",
+                self.used, self.limit
+            ),
+        }
+    }
+}
+
+/// Requests and tokens in their windows, and any override in force.
+#[derive(Debug, Default)]
+pub struct Limiter {
+    settings: LimitSettings,
+    /// When each request in the last minute was made.
+    requests: VecDeque<Instant>,
+    /// Tokens used in the last hour, by when they were reported.
+    tokens: VecDeque<(Instant, u64)>,
+    override_until: Option<Instant>,
+}
+
+impl Limiter {
+    pub fn new(settings: LimitSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub fn settings(&self) -> &LimitSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: LimitSettings) {
+        self.settings = settings;
+    }
+
+    /// Count a request made at `now`.
+    pub fn record_request(&mut self, now: Instant) {
+        self.expire(now);
+        self.requests.push_back(now);
+    }
+
+    /// Count tokens a response reported at `now`.
+    pub fn record_tokens(&mut self, now: Instant, tokens: u64) {
+        self.expire(now);
+        self.tokens.push_back((now, tokens));
+    }
+
+    /// Set limits aside until `now + duration`.
+    pub fn override_for(&mut self, now: Instant, duration: Duration) {
+        self.override_until = Some(now + duration);
+    }
+
+    /// How much longer an override lasts, if one is in force.
+    pub fn override_left(&self, now: Instant) -> Option<Duration> {
+        self.override_until
+            .and_then(|until| until.checked_duration_since(now))
+            .filter(|left| !left.is_zero())
+    }
+
+    /// The limit standing in the way of the next request, if any: the one
+    /// that lasts longest when several are reached. `cost_today` is what
+    /// today has cost so far, and `day_left` how long today has to go.
+    pub fn check(&mut self, now: Instant, cost_today: f64, day_left: Duration) -> Option<LimitHit> {
+        self.expire(now);
+        if self.override_left(now).is_some() {
+            return None;
+        }
+        let s = &self.settings;
+        let mut hits = Vec::new();
+
+        if s.cost_per_day > 0.0 && cost_today >= s.cost_per_day {
+            hits.push(LimitHit {
+                kind: LimitKind::CostPerDay,
+                limit: s.cost_per_day,
+                used: cost_today,
+                resets_in_secs: day_left.as_secs(),
+            });
+        }
+
+        let used: u64 = self.tokens.iter().map(|(_, t)| t).sum();
+        if s.tokens_per_hour > 0 && used >= s.tokens_per_hour {
+            // Lifts once enough of the oldest tokens leave the window
+            let mut left = used;
+            let mut resets = now;
+            for &(at, tokens) in &self.tokens {
+                left -= tokens;
+                resets = at + HOUR;
+                if left < s.tokens_per_hour {
+                    break;
+                }
+            }
+            hits.push(LimitHit {
+                kind: LimitKind::TokensPerHour,
+                limit: s.tokens_per_hour as f64,
+                used: used as f64,
+                resets_in_secs: resets.duration_since(now).as_secs(),
+            });
+        }
+
+        let made = self.requests.len();
+        let per_minute = s.requests_per_minute as usize;
+        if per_minute > 0 && made >= per_minute {
+            let resets = self.requests[made - per_minute] + MINUTE;
+            hits.push(LimitHit {
+                kind: LimitKind::RequestsPerMinute,
+                limit: per_minute as f64,
+                used: made as f64,
+                resets_in_secs: resets.duration_since(now).as_secs(),
+            });
+        }
+
+        // On a tie the first one found wins
+        hits.into_iter().reduce(|longest, hit| {
+            if hit.resets_in_secs > longest.resets_in_secs {
+                hit
+            } else {
+                longest
+            }
+        })
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(&at) = self.requests.front() {
+            if now.duration_since(at) < MINUTE {
+                break;
+            }
+            self.requests.pop_front();
+        }
+        while let Some(&(at, _)) = self.tokens.front() {
+            if now.duration_since(at) < HOUR {
+                break;
+            }
+            self.tokens.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_LEFT: Duration = Duration::from_secs(5 * 3600);
+
+    #[test]
+    fn no_limits_never_stop_anything() {
+        let mut l = Limiter::new(LimitSettings::default());
+        let now = Instant::now();
+        for _ in 0..1000 {
+            l.record_request(now);
+            l.record_tokens(now, 10_000);
+        }
+        assert_eq!(l.check(now, 1e6, DAY_LEFT), None);
+    }
+
+    #[test]
+    fn request_rate_lifts_as_the_minute_moves_on() {
+        let mut l = Limiter::new(LimitSettings {
+            requests_per_minute: 3,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        for i in 0..3 {
+            assert_eq!(
+                l.check(start + Duration::from_secs(i * 10), 0.0, DAY_LEFT),
+                None
+            );
+            l.record_request(start + Duration::from_secs(i * 10));
+        }
+        let hit = l
+            .check(start + Duration::from_secs(25), 0.0, DAY_LEFT)
+            .unwrap();
+        assert_eq!(hit.kind, LimitKind::RequestsPerMinute);
+        assert_eq!(hit.used, 3.0);
+        assert_eq!(hit.resets_in_secs, 35);
+        assert_eq!(
+            l.check(start + Duration::from_secs(60), 0.0, DAY_LEFT),
+            None
+        );
+    }
+
+    #[test]
+    fn tokens_per_hour_and_cost_per_day() {
+        let mut l = Limiter::new(LimitSettings {
+            tokens_per_hour: 10_000,
+            cost_per_day: 5.0,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        l.record_tokens(start, 6000);
+        l.record_tokens(start + Duration::from_secs(600), 6000);
+        let now = start + Duration::from_secs(1200);
+        let hit = l.check(now, 1.0, DAY_LEFT).unwrap();
+        assert_eq!(hit.kind, LimitKind::TokensPerHour);
+        assert_eq!(hit.used, 12_000.0);
+        // Dropping the first 6000 is enough
+        assert_eq!(hit.resets_in_secs, 2400);
+        assert!(hit.describe().contains("12000 of 10000"));
+
+        // The daily limit outlasts the hourly one
+        let hit = l.check(now, 5.2, DAY_LEFT).unwrap();
+        assert_eq!(hit.kind, LimitKind::CostPerDay);
+        assert_eq!(hit.resets_in_secs, DAY_LEFT.as_secs());
+        assert!(hit.describe().contains("5.20 of 5.00"));
+
+        // ...but not late in the day
+        let hit = l.check(now, 5.2, Duration::from_secs(600)).unwrap();
+        assert_eq!(hit.kind, LimitKind::TokensPerHour);
+        assert_eq!(hit.resets_in_secs, 2400);
+
+        assert_eq!(l.check(start + HOUR, 1.0, DAY_LEFT), None);
+    }
+
+    #[test]
+    fn override_sets_limits_aside_for_a_while() {
+        let mut l = Limiter::new(LimitSettings {
+            cost_per_day: 1.0,
+            ..Default::default()
+        });
+        let now = Instant::now();
+        assert!(l.check(now, 2.0, DAY_LEFT).is_some());
+        l.override_for(now, Duration::from_secs(1800));
+        assert_eq!(l.check(now, 2.0, DAY_LEFT), None);
+        assert_eq!(l.override_left(now), Some(Duration::from_secs(1800)));
+        let later = now + Duration::from_secs(1800);
+        assert_eq!(l.override_left(later), None);
+        assert!(l.check(later, 2.0, DAY_LEFT).is_some());
+    }
+}
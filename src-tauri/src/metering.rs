@@ -0,0 +1,394 @@
+// metering.rs — What the AI costs: token usage and its price.
+//
+// Providers report the tokens each response used (`TokenUsage`). `Meter`
+// prices them with `PricingSettings` and adds them up for the current
+// session — from when capture last started — and for each UTC day. Day
+// totals are kept in `usage.json` under the app data dir so "today" means
+// today across restarts; the session lives in memory only.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::ai::TokenUsage;
+use crate::error::BemeError;
+
+/// Days of totals kept in the usage file.
+const DAYS_KEPT: usize = 90;
+
+/// A session shorter than this has no meaningful hourly rate yet.
+const MIN_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Which pipeline a response belongs to; each is priced by its own model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageSource {
+    /// Frames, "analyze now" and follow-ups (the vision deployment).
+    Screen,
+    /// The Realtime audio session.
+    Audio,
+}
+
+/// Prices per million tokens.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelPricing {
+    /// Text and image input.
+    pub input: f64,
+    /// Input served from the prompt cache.
+    pub cached_input: f64,
+    /// Text output, reasoning included.
+    pub output: f64,
+    pub audio_input: f64,
+    pub audio_output: f64,
+}
+
+impl ModelPricing {
+    /// What `usage` costs. Cached and audio tokens are part of the input
+    /// and output totals, so they're taken out before the text price applies.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage.cached_input_tokens.min(usage.input_tokens);
+        let audio_in = usage.audio_input_tokens.min(usage.input_tokens - cached);
+        let text_in = usage.input_tokens - cached - audio_in;
+        let audio_out = usage.audio_output_tokens.min(usage.output_tokens);
+        let text_out = usage.output_tokens - audio_out;
+        (text_in as f64 * self.input
+            + cached as f64 * self.cached_input
+            + audio_in as f64 * self.audio_input
+            + text_out as f64 * self.output
+            + audio_out as f64 * self.audio_output)
+            / 1_000_000.0
+    }
+
+    fn prices(&self) -> [f64; 5] {
+        [
+            self.input,
+            self.cached_input,
+            self.output,
+            self.audio_input,
+            self.audio_output,
+        ]
+    }
+}
+
+/// What the team pays for each deployment. Defaults are list prices for
+/// `gpt-4o` and `gpt-4o-realtime-preview` in US dollars.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PricingSettings {
+    /// Shown next to costs; prices are taken to be in it.
+    pub currency: String,
+    pub vision: ModelPricing,
+    pub audio: ModelPricing,
+}
+
+impl Default for PricingSettings {
+    fn default() -> Self {
+        Self {
+            currency: "USD".into(),
+            vision: ModelPricing {
+                input: 2.50,
+                cached_input: 1.25,
+                output: 10.0,
+                ..Default::default()
+            },
+            audio: ModelPricing {
+                input: 5.0,
+                cached_input: 2.50,
+                output: 20.0,
+                audio_input: 40.0,
+                audio_output: 80.0,
+            },
+        }
+    }
+}
+
+impl PricingSettings {
+    pub fn validate(&self) -> Result<(), BemeError> {
+        if self.currency.trim().is_empty() {
+            return Err(BemeError::InvalidInput("currency is empty".into()));
+        }
+        let prices = self.vision.prices().into_iter().chain(self.audio.prices());
+        if prices.into_iter().any(|p| !p.is_finite() || p < 0.0) {
+            return Err(BemeError::InvalidInput(
+                "token prices must be zero or more".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn for_source(&self, source: UsageSource) -> &ModelPricing {
+        match source {
+            UsageSource::Screen => &self.vision,
+            UsageSource::Audio => &self.audio,
+        }
+    }
+}
+
+/// Usage added up over a session or a day.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UsageTotals {
+    /// Responses that reported usage.
+    pub requests: u64,
+    #[serde(flatten)]
+    pub tokens: TokenUsage,
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &TokenUsage, cost: f64) {
+        self.requests += 1;
+        self.tokens.add(usage);
+        self.cost += cost;
+    }
+}
+
+/// Answer to `get_usage`.
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub currency: String,
+    /// When the session started (ISO 8601, UTC).
+    pub session_started: String,
+    pub session_secs: u64,
+    pub session: UsageTotals,
+    /// Session cost per hour so far; `None` for the first minute.
+    pub cost_per_hour: Option<f64>,
+    /// Today's date (UTC), and its totals.
+    pub day: String,
+    pub today: UsageTotals,
+    /// Every day on record, by date.
+    pub days: BTreeMap<String, UsageTotals>,
+}
+
+#[derive(Debug)]
+pub struct Meter {
+    pricing: PricingSettings,
+    session: UsageTotals,
+    session_started: Instant,
+    session_started_at: String,
+    /// Totals per UTC date (`YYYY-MM-DD`).
+    days: BTreeMap<String, UsageTotals>,
+    /// Where day totals are saved; `None` keeps them in memory.
+    store: Option<PathBuf>,
+    /// Bumped by every `record`, so a stale save never overwrites a newer one.
+    version: u64,
+    /// The version last written to `store`.
+    saved: Arc<Mutex<u64>>,
+}
+
+/// Day totals to write to the usage file, taken by `Meter::unsaved`.
+/// Writing blocks, so it's done off the async runtime.
+#[derive(Debug)]
+pub struct UsageSave {
+    path: PathBuf,
+    json: String,
+    version: u64,
+    saved: Arc<Mutex<u64>>,
+}
+
+impl UsageSave {
+    /// Write the file, unless a later save already has.
+    pub fn write(self) {
+        let mut saved = self.saved.lock().unwrap();
+        if *saved >= self.version {
+            return;
+        }
+        match fs::write(&self.path, self.json) {
+            Ok(()) => *saved = self.version,
+            Err(e) => log::warn!("Usage not saved to {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+impl Meter {
+    /// A meter whose session starts at `now` (`started_at` in ISO 8601).
+    pub fn new(now: Instant, started_at: String) -> Self {
+        Self {
+            pricing: PricingSettings::default(),
+            session: UsageTotals::default(),
+            session_started: now,
+            session_started_at: started_at,
+            days: BTreeMap::new(),
+            store: None,
+            version: 0,
+            saved: Arc::default(),
+        }
+    }
+
+    pub fn pricing(&self) -> &PricingSettings {
+        &self.pricing
+    }
+
+    /// Price responses from now on with `pricing`; what's recorded keeps
+    /// the cost it was recorded at.
+    pub fn set_pricing(&mut self, pricing: PricingSettings) {
+        self.pricing = pricing;
+    }
+
+    /// Load day totals from `path` and save them there from now on. Fails
+    /// if the file exists but can't be read or parsed; it's then left
+    /// alone and the totals are kept in memory only.
+    pub fn open(&mut self, path: &Path) -> Result<(), BemeError> {
+        match fs::read_to_string(path) {
+            Ok(json) => {
+                self.days = serde_json::from_str(&json)
+                    .map_err(|e| BemeError::Settings(format!("{}: {}", path.display(), e)))?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.store = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Start a new session at `now`.
+    pub fn start_session(&mut self, now: Instant, started_at: String) {
+        self.session = UsageTotals::default();
+        self.session_started = now;
+        self.session_started_at = started_at;
+    }
+
+    /// Count one response from `source` on `day`. Returns what it cost.
+    pub fn record(&mut self, day: &str, source: UsageSource, usage: &TokenUsage) -> f64 {
+        let cost = self.pricing.for_source(source).cost(usage);
+        self.session.add(usage, cost);
+        self.days
+            .entry(day.to_string())
+            .or_default()
+            .add(usage, cost);
+        while self.days.len() > DAYS_KEPT {
+            self.days.pop_first();
+        }
+        self.version += 1;
+        cost
+    }
+
+    pub fn session(&self) -> &UsageTotals {
+        &self.session
+    }
+
+    /// Totals for `day`.
+    pub fn day(&self, day: &str) -> UsageTotals {
+        self.days.get(day).cloned().unwrap_or_default()
+    }
+
+    pub fn report(&self, now: Instant, day: &str) -> UsageReport {
+        let elapsed = now.duration_since(self.session_started);
+        let cost_per_hour = (elapsed >= MIN_RATE_WINDOW)
+            .then(|| self.session.cost * 3600.0 / elapsed.as_secs_f64());
+        UsageReport {
+            currency: self.pricing.currency.clone(),
+            session_started: self.session_started_at.clone(),
+            session_secs: elapsed.as_secs(),
+            session: self.session.clone(),
+            cost_per_hour,
+            day: day.to_string(),
+            today: self.day(day),
+            days: self.days.clone(),
+        }
+    }
+
+    /// Day totals not yet written to the usage file, if there is one.
+    pub fn unsaved(&self) -> Option<UsageSave> {
+        let path = self.store.as_ref()?;
+        if *self.saved.lock().unwrap() >= self.version {
+            return None;
+        }
+        let json = match serde_json::to_string_pretty(&self.days) {
+            Ok(json) => json,
+            Err(e) => {
+                log::warn!("Usage not saved to {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        Some(UsageSave {
+            path: path.clone(),
+            json,
+            version: self.version,
+            saved: self.saved.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, output: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            output_tokens: output,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prices_each_kind_of_token() {
+        let pricing = PricingSettings::default();
+        // 1M text in at 2.50 + 100k out at 10
+        let cost = pricing.vision.cost(&usage(1_000_000, 100_000));
+        assert!((cost - 3.5).abs() < 1e-9, "{cost}");
+
+        let cached = TokenUsage {
+            cached_input_tokens: 1_000_000,
+            ..usage(1_000_000, 0)
+        };
+        assert!((pricing.vision.cost(&cached) - 1.25).abs() < 1e-9);
+
+        // 100k audio in at 40, 50k text in at 5, 10k audio out at 80
+        let audio = TokenUsage {
+            audio_input_tokens: 100_000,
+            audio_output_tokens: 10_000,
+            ..usage(150_000, 10_000)
+        };
+        assert!((pricing.audio.cost(&audio) - 5.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn adds_up_sessions_and_days() {
+        let start = Instant::now();
+        let mut meter = Meter::new(start, "2026-10-18T09:00:00Z".into());
+        meter.record("2026-10-18", UsageSource::Screen, &usage(1000, 100));
+        meter.record("2026-10-18", UsageSource::Screen, &usage(1000, 100));
+        assert_eq!(meter.session().requests, 2);
+        assert_eq!(meter.session().tokens.input_tokens, 2000);
+
+        meter.start_session(start, "2026-10-18T10:00:00Z".into());
+        meter.record("2026-10-19", UsageSource::Audio, &usage(500, 50));
+        assert_eq!(meter.session().requests, 1);
+        assert_eq!(meter.day("2026-10-18").requests, 2);
+        assert_eq!(meter.day("2026-10-19").tokens.output_tokens, 50);
+
+        let report = meter.report(start + Duration::from_secs(1800), "2026-10-19");
+        assert_eq!(report.days.len(), 2);
+        let hourly = report.cost_per_hour.unwrap();
+        assert!((hourly - 2.0 * meter.session().cost).abs() < 1e-12);
+        assert_eq!(meter.report(start, "2026-10-19").cost_per_hour, None);
+    }
+
+    #[test]
+    fn day_totals_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("beme-usage-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut meter = Meter::new(Instant::now(), String::new());
+        meter.open(&path).unwrap();
+        meter.record("2026-10-18", UsageSource::Screen, &usage(1000, 100));
+        let save = meter.unsaved().unwrap();
+        meter.record("2026-10-18", UsageSource::Screen, &usage(1000, 100));
+        meter.unsaved().unwrap().write();
+        // An older save that lands late doesn't undo the newer one.
+        save.write();
+        assert!(meter.unsaved().is_none());
+
+        let mut reopened = Meter::new(Instant::now(), String::new());
+        reopened.open(&path).unwrap();
+        assert_eq!(reopened.day("2026-10-18"), meter.day("2026-10-18"));
+        assert_eq!(reopened.session().requests, 0);
+        let _ = fs::remove_file(&path);
+    }
+}
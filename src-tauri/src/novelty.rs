@@ -0,0 +1,225 @@
+// novelty.rs — Hides screen suggestions that repeat recent advice.
+//
+// While the screen changes only slightly, the model tends to give the same
+// answer every capture. `NoveltyFilter` compares each completed suggestion
+// with the last few it let through, using the overlap of their normalized
+// word sets, and reports near-duplicates so `StreamManager` can suppress them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+use crate::error::BemeError;
+
+/// When suggestions count as repeats. Off unless turned on in settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NoveltySettings {
+    pub enabled: bool,
+    /// Word overlap (0–1) at or above which a suggestion is a repeat.
+    pub threshold: f64,
+    /// How many recent suggestions to compare against.
+    pub window: usize,
+}
+
+impl Default for NoveltySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0.75,
+            window: 5,
+        }
+    }
+}
+
+impl NoveltySettings {
+    pub fn validate(&self) -> Result<(), BemeError> {
+        if !(self.threshold > 0.0 && self.threshold <= 1.0) {
+            return Err(BemeError::InvalidInput(
+                "repeat threshold must be above 0 and at most 1".into(),
+            ));
+        }
+        if self.window == 0 {
+            return Err(BemeError::InvalidInput(
+                "repeats must be compared with at least one suggestion".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether a suggestion adds anything.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Novel,
+    /// Repeats suggestion `of`; `similarity` is 1.0 for the same words.
+    Duplicate {
+        of: u64,
+        similarity: f64,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct NoveltyFilter {
+    settings: NoveltySettings,
+    /// Recent novel suggestions, newest last.
+    recent: VecDeque<(u64, HashSet<String>)>,
+}
+
+impl NoveltyFilter {
+    pub fn new(settings: NoveltySettings) -> Self {
+        Self {
+            settings,
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn settings(&self) -> &NoveltySettings {
+        &self.settings
+    }
+
+    /// Replace the settings, keeping what's been seen.
+    pub fn set_settings(&mut self, settings: NoveltySettings) {
+        self.settings = settings;
+        self.trim();
+    }
+
+    /// Forget everything seen so far, e.g. when a new thread starts.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+    }
+
+    /// Judge suggestion `id`. Novel suggestions are remembered for later
+    /// comparisons; duplicates aren't, so a run of repeats is measured
+    /// against the original.
+    pub fn check(&mut self, id: u64, text: &str) -> Verdict {
+        if !self.settings.enabled {
+            return Verdict::Novel;
+        }
+        let words = words(text);
+        if words.is_empty() {
+            return Verdict::Novel;
+        }
+        let best = self
+            .recent
+            .iter()
+            .rev()
+            .map(|(seen, seen_words)| (*seen, similarity(&words, seen_words)))
+            .fold(None, |best: Option<(u64, f64)>, (seen, sim)| match best {
+                Some((_, b)) if b >= sim => best,
+                _ => Some((seen, sim)),
+            });
+        if let Some((of, similarity)) = best {
+            if similarity >= self.settings.threshold {
+                return Verdict::Duplicate { of, similarity };
+            }
+        }
+        self.recent.push_back((id, words));
+        self.trim();
+        Verdict::Novel
+    }
+
+    fn trim(&mut self) {
+        while self.recent.len() > self.settings.window {
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// Lowercased alphanumeric words, ignoring punctuation and markdown.
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Jaccard similarity of two word sets.
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> NoveltySettings {
+        NoveltySettings {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_words_are_a_duplicate_whatever_the_formatting() {
+        let mut filter = NoveltyFilter::new(enabled());
+        assert_eq!(
+            filter.check(1, "Click **Save** to keep your changes."),
+            Verdict::Novel
+        );
+        assert_eq!(
+            filter.check(2, "click save to keep your changes"),
+            Verdict::Duplicate {
+                of: 1,
+                similarity: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn near_duplicates_match_the_closest_recent_suggestion() {
+        let mut filter = NoveltyFilter::new(enabled());
+        filter.check(1, "Press Ctrl+S to save your changes.");
+        filter.check(2, "Reply to the unread message from Sam.");
+        match filter.check(3, "Press Ctrl+S to save the changes.") {
+            Verdict::Duplicate { of, similarity } => {
+                assert_eq!(of, 1);
+                assert!((0.75..1.0).contains(&similarity));
+            }
+            Verdict::Novel => panic!("expected a duplicate"),
+        }
+        assert_eq!(
+            filter.check(4, "Close the settings dialog."),
+            Verdict::Novel
+        );
+    }
+
+    #[test]
+    fn only_the_window_is_remembered() {
+        let mut filter = NoveltyFilter::new(NoveltySettings {
+            window: 1,
+            ..enabled()
+        });
+        filter.check(1, "Save the file.");
+        filter.check(2, "Open the terminal.");
+        assert_eq!(filter.check(3, "Save the file."), Verdict::Novel);
+
+        filter.clear();
+        filter.set_settings(NoveltySettings {
+            enabled: false,
+            ..Default::default()
+        });
+        filter.check(4, "Save the file.");
+        assert_eq!(filter.check(5, "Save the file."), Verdict::Novel);
+    }
+
+    #[test]
+    fn validate_rejects_settings_that_would_misbehave() {
+        assert!(NoveltySettings::default().validate().is_ok());
+        for threshold in [0.0, -0.5, 1.5, f64::NAN] {
+            let settings = NoveltySettings {
+                threshold,
+                ..enabled()
+            };
+            assert!(settings.validate().is_err(), "{threshold}");
+        }
+        let settings = NoveltySettings {
+            window: 0,
+            ..enabled()
+        };
+        assert!(settings.validate().is_err());
+    }
+}
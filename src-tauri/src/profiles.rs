@@ -0,0 +1,468 @@
+// profiles.rs — Named prompt presets with per-app auto-switching.
+//
+// Settings hold the default prompts plus any number of `PromptProfile`s
+// ("sales call", "coding", "support desk"). Activating a profile loads its
+// prompts into the `StreamManager`; activating `None` goes back to the
+// defaults. With auto-switching on, `watch_focus` polls the frontmost window
+// and activates the first profile with a matching rule, falling back to the
+// profile the user chose by hand.
+
+use crate::capture::screen::{self, FocusedWindow};
+use crate::error::BemeError;
+use crate::prompt_template;
+use crate::settings::Settings;
+use crate::stream_manager::StreamManager;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the frontmost window is checked while auto-switching.
+const FOCUS_POLL_MS: u64 = 1500;
+
+/// A named pair of prompts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptProfile {
+    pub name: String,
+    pub vision_prompt: String,
+    pub audio_prompt: String,
+    /// Windows that activate this profile when auto-switching.
+    #[serde(default)]
+    pub rules: Vec<ProfileRule>,
+}
+
+/// Matches a window when every non-empty field is a case-insensitive
+/// substring of the window's app name / title. A rule with no fields
+/// matches nothing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProfileRule {
+    pub app: String,
+    pub title: String,
+}
+
+impl ProfileRule {
+    pub fn matches(&self, window: &FocusedWindow) -> bool {
+        let contains = |haystack: &str, needle: &str| {
+            let needle = needle.trim();
+            needle.is_empty() || haystack.to_lowercase().contains(&needle.to_lowercase())
+        };
+        let any = !self.app.trim().is_empty() || !self.title.trim().is_empty();
+        any && contains(&window.app_name, &self.app) && contains(&window.title, &self.title)
+    }
+}
+
+/// The first profile with a rule matching `window`.
+pub fn matching_profile<'a>(
+    profiles: &'a [PromptProfile],
+    window: &FocusedWindow,
+) -> Option<&'a PromptProfile> {
+    profiles
+        .iter()
+        .find(|p| p.rules.iter().any(|r| r.matches(window)))
+}
+
+/// Check `profile` before it replaces the one named `original` (or is
+/// added, for `None`): it needs a name no other profile has, and prompts
+/// that only use known variables.
+pub fn validate(
+    profile: &PromptProfile,
+    profiles: &[PromptProfile],
+    original: Option<&str>,
+) -> Result<(), BemeError> {
+    let name = profile.name.trim();
+    if name.is_empty() {
+        return Err(BemeError::InvalidInput("profile name is empty".into()));
+    }
+    let taken = profiles
+        .iter()
+        .filter(|p| Some(p.name.as_str()) != original)
+        .any(|p| p.name.trim().eq_ignore_ascii_case(name));
+    if taken {
+        return Err(BemeError::InvalidInput(format!(
+            "a profile named \"{}\" already exists",
+            name
+        )));
+    }
+    prompt_template::validate(&profile.vision_prompt)?;
+    prompt_template::validate(&profile.audio_prompt)
+}
+
+/// Check a whole profile list, as saved with the settings: each profile
+/// valid with a name of its own, and `chosen` (if set) one of them.
+pub fn validate_all(profiles: &[PromptProfile], chosen: Option<&str>) -> Result<(), BemeError> {
+    for (i, profile) in profiles.iter().enumerate() {
+        validate(profile, &profiles[..i], None)?;
+    }
+    if let Some(name) = chosen {
+        if !profiles.iter().any(|p| p.name == name) {
+            return Err(BemeError::InvalidInput(format!(
+                "no profile named \"{}\"",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Which profile is live, which one the user picked, and what auto-switching
+/// matches against.
+#[derive(Debug, Default, Clone)]
+struct Selection {
+    active: Option<String>,
+    chosen: Option<String>,
+    auto_switch: bool,
+    profiles: Vec<PromptProfile>,
+}
+
+#[derive(Default)]
+pub struct ProfileState {
+    selection: Mutex<Selection>,
+}
+
+/// Returned by the profile commands: every profile plus what's active.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfilesView {
+    pub profiles: Vec<PromptProfile>,
+    /// The profile whose prompts are live; `None` = the default prompts.
+    pub active: Option<String>,
+    /// The profile picked by hand, used when no rule matches.
+    pub chosen: Option<String>,
+    pub auto_switch: bool,
+}
+
+/// Payload emitted on `profile:changed` events.
+#[derive(Clone, Serialize)]
+pub struct ProfileChangedPayload {
+    pub active: Option<String>,
+    pub reason: String, // "manual", "auto", "edited" or "deleted"
+    /// The window that triggered an automatic switch.
+    pub window: Option<FocusedWindow>,
+}
+
+impl ProfileState {
+    /// The profile whose prompts are live.
+    pub fn active(&self) -> Option<String> {
+        self.selection.lock().unwrap().active.clone()
+    }
+
+    fn view(&self) -> ProfilesView {
+        let s = self.selection.lock().unwrap();
+        ProfilesView {
+            profiles: s.profiles.clone(),
+            active: s.active.clone(),
+            chosen: s.chosen.clone(),
+            auto_switch: s.auto_switch,
+        }
+    }
+}
+
+/// Pick up saved profiles. `configure_ai_from_settings` has already loaded
+/// the chosen profile's prompts, so this only records what's live.
+pub fn init(app: &AppHandle, settings: &Settings) {
+    let state = app.state::<ProfileState>();
+    let mut s = state.selection.lock().unwrap();
+    s.active = settings.chosen_profile().map(|p| p.name.clone());
+    s.chosen = s.active.clone();
+    sync_selection(&mut s, settings);
+}
+
+/// Refresh the profiles and auto-switch flag after settings were saved.
+pub fn sync(app: &AppHandle, settings: &Settings) {
+    let state = app.state::<ProfileState>();
+    sync_selection(&mut state.selection.lock().unwrap(), settings);
+}
+
+fn sync_selection(s: &mut Selection, settings: &Settings) {
+    s.profiles = settings.profiles.clone();
+    s.auto_switch = settings.auto_switch_profiles;
+}
+
+/// Load `name`'s prompts (or the defaults) and report the switch.
+fn apply(
+    app: &AppHandle,
+    settings: &Settings,
+    name: Option<String>,
+    reason: &str,
+    window: Option<FocusedWindow>,
+) {
+    let (vision, audio) = settings.prompts_for(name.as_deref());
+    let sm = app.state::<Arc<StreamManager>>();
+    sm.update_prompt("vision", vision);
+    sm.update_prompt("audio", audio);
+    app.state::<ProfileState>().selection.lock().unwrap().active = name.clone();
+    log::info!(
+        "Prompt profile: {} ({})",
+        name.as_deref().unwrap_or("default"),
+        reason
+    );
+    sm.event_sink().send(
+        "profile:changed",
+        &ProfileChangedPayload {
+            active: name,
+            reason: reason.into(),
+            window,
+        },
+    );
+}
+
+/// Poll the frontmost window and switch profiles when auto-switching is on.
+/// beme's own windows are skipped, so using the dashboard keeps the profile
+/// of the app behind it. Runs for the life of the app.
+pub fn watch_focus(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(FOCUS_POLL_MS));
+        loop {
+            interval.tick().await;
+            let selection = app
+                .state::<ProfileState>()
+                .selection
+                .lock()
+                .unwrap()
+                .clone();
+            if !selection.auto_switch {
+                continue;
+            }
+            let own_pid = std::process::id();
+            let window =
+                match tokio::task::spawn_blocking(move || screen::focused_window(own_pid)).await {
+                    Ok(Ok(Some(window))) => window,
+                    Ok(Err(e)) => {
+                        log::debug!("Profile auto-switch: {}", e);
+                        continue;
+                    }
+                    _ => continue,
+                };
+            let target = matching_profile(&selection.profiles, &window)
+                .map(|p| p.name.clone())
+                .or(selection.chosen);
+            if target == selection.active {
+                continue;
+            }
+            match Settings::load_from_app(&app) {
+                Ok(settings) => apply(&app, &settings, target, "auto", Some(window)),
+                Err(e) => log::error!("Profile auto-switch: {}", e),
+            }
+        }
+    });
+}
+
+// ─── Commands ────────────────────────────────────────────────────────
+
+/// Every profile, plus which one is active.
+#[tauri::command]
+pub fn list_profiles(state: tauri::State<'_, ProfileState>) -> ProfilesView {
+    state.view()
+}
+
+/// Add a profile, or replace the one named `original_name` (which may
+/// rename it). An active profile's new prompts take effect immediately.
+#[tauri::command]
+pub fn save_profile(
+    app: AppHandle,
+    profile: PromptProfile,
+    original_name: Option<String>,
+) -> Result<ProfilesView, BemeError> {
+    let mut settings = Settings::load_from_app(&app)?;
+    validate(&profile, &settings.profiles, original_name.as_deref())?;
+    let profile = PromptProfile {
+        name: profile.name.trim().to_string(),
+        ..profile
+    };
+    let name = profile.name.clone();
+
+    match original_name
+        .as_deref()
+        .and_then(|o| settings.profiles.iter().position(|p| p.name == o))
+    {
+        Some(i) => settings.profiles[i] = profile,
+        None => settings.profiles.push(profile),
+    }
+    let renamed = |n: &mut Option<String>| {
+        if n.is_some() && *n == original_name {
+            *n = Some(name.clone());
+        }
+    };
+    renamed(&mut settings.active_profile);
+    settings.save_to_app(&app)?;
+
+    let state = app.state::<ProfileState>();
+    let was_active = {
+        let mut s = state.selection.lock().unwrap();
+        sync_selection(&mut s, &settings);
+        renamed(&mut s.chosen);
+        s.active.is_some()
+            && (s.active == original_name || s.active.as_deref() == Some(name.as_str()))
+    };
+    if was_active {
+        apply(&app, &settings, Some(name), "edited", None);
+    }
+    Ok(state.view())
+}
+
+/// Delete a profile. If it was active, the default prompts take over.
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, name: String) -> Result<ProfilesView, BemeError> {
+    let mut settings = Settings::load_from_app(&app)?;
+    let before = settings.profiles.len();
+    settings.profiles.retain(|p| p.name != name);
+    if settings.profiles.len() == before {
+        return Err(BemeError::InvalidInput(format!(
+            "no profile named \"{}\"",
+            name
+        )));
+    }
+    if settings.active_profile.as_deref() == Some(name.as_str()) {
+        settings.active_profile = None;
+    }
+    settings.save_to_app(&app)?;
+
+    let state = app.state::<ProfileState>();
+    let was_active = {
+        let mut s = state.selection.lock().unwrap();
+        sync_selection(&mut s, &settings);
+        if s.chosen.as_deref() == Some(name.as_str()) {
+            s.chosen = None;
+        }
+        s.active.as_deref() == Some(name.as_str())
+    };
+    if was_active {
+        apply(&app, &settings, None, "deleted", None);
+    }
+    Ok(state.view())
+}
+
+/// Switch to a profile by hand (`None` = the default prompts) and remember
+/// the choice. While auto-switching, a matching rule still takes precedence.
+#[tauri::command]
+pub fn activate_profile(app: AppHandle, name: Option<String>) -> Result<ProfilesView, BemeError> {
+    let mut settings = Settings::load_from_app(&app)?;
+    if let Some(n) = &name {
+        if settings.profile(n).is_none() {
+            return Err(BemeError::InvalidInput(format!(
+                "no profile named \"{}\"",
+                n
+            )));
+        }
+    }
+    settings.active_profile = name.clone();
+    settings.save_to_app(&app)?;
+
+    let state = app.state::<ProfileState>();
+    state.selection.lock().unwrap().chosen = name.clone();
+    apply(&app, &settings, name, "manual", None);
+    Ok(state.view())
+}
+
+/// Turn automatic profile switching on or off. Turning it off goes back to
+/// the profile chosen by hand.
+#[tauri::command]
+pub fn set_profile_auto_switch(app: AppHandle, enabled: bool) -> Result<ProfilesView, BemeError> {
+    let mut settings = Settings::load_from_app(&app)?;
+    settings.auto_switch_profiles = enabled;
+    settings.save_to_app(&app)?;
+
+    let state = app.state::<ProfileState>();
+    let (active, chosen) = {
+        let mut s = state.selection.lock().unwrap();
+        s.auto_switch = enabled;
+        (s.active.clone(), s.chosen.clone())
+    };
+    if !enabled && active != chosen {
+        apply(&app, &settings, chosen, "manual", None);
+    }
+    Ok(state.view())
+}
+
+/// The frontmost window other than beme's, for filling in a rule from
+/// what's on screen.
+#[tauri::command]
+pub async fn current_window() -> Result<Option<FocusedWindow>, BemeError> {
+    let own_pid = std::process::id();
+    tokio::task::spawn_blocking(move || screen::focused_window(own_pid))
+        .await
+        .map_err(|e| BemeError::Capture(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(app: &str, title: &str) -> FocusedWindow {
+        FocusedWindow {
+            app_name: app.into(),
+            title: title.into(),
+            process_id: 1,
+        }
+    }
+
+    fn profile(name: &str, rules: &[(&str, &str)]) -> PromptProfile {
+        PromptProfile {
+            name: name.into(),
+            vision_prompt: format!("{name} vision"),
+            audio_prompt: format!("{name} audio"),
+            rules: rules
+                .iter()
+                .map(|(app, title)| ProfileRule {
+                    app: app.to_string(),
+                    title: title.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn rules_match_case_insensitive_substrings() {
+        let zoom_sales = ProfileRule {
+            app: "zoom".into(),
+            title: "Sales".into(),
+        };
+        assert!(zoom_sales.matches(&window("Zoom.us", "Weekly sales sync")));
+        assert!(!zoom_sales.matches(&window("Zoom.us", "Standup")));
+        assert!(!ProfileRule::default().matches(&window("Code", "main.rs")));
+    }
+
+    #[test]
+    fn first_matching_profile_wins() {
+        let profiles = [
+            profile("coding", &[("code", ""), ("", "- Visual Studio")]),
+            profile("sales call", &[("zoom", "")]),
+            profile("everything", &[("", "e")]),
+        ];
+        let pick =
+            |app, title| matching_profile(&profiles, &window(app, title)).map(|p| p.name.as_str());
+        assert_eq!(pick("Code", "lib.rs"), Some("coding"));
+        assert_eq!(pick("zoom.us", "Meeting"), Some("sales call"));
+        assert_eq!(pick("Slack", "general"), Some("everything"));
+        assert_eq!(pick("Slack", "dms"), None);
+    }
+
+    #[test]
+    fn names_must_be_unique_and_prompts_valid() {
+        let profiles = [profile("Coding", &[]), profile("Sales", &[])];
+        assert!(validate(&profile("  ", &[]), &profiles, None).is_err());
+        assert!(validate(&profile("coding ", &[]), &profiles, None).is_err());
+        assert!(validate(&profile("Support", &[]), &profiles, None).is_ok());
+        // Saving a profile over itself (or renaming it) is fine
+        assert!(validate(&profile("coding", &[]), &profiles, Some("Coding")).is_ok());
+        assert!(validate(&profile("Sales", &[]), &profiles, Some("Coding")).is_err());
+
+        let mut unknown_variable = profile("Support", &[]);
+        unknown_variable.audio_prompt = "Reply as {{agent_name}}".into();
+        assert!(validate(&unknown_variable, &profiles, None).is_err());
+    }
+
+    #[test]
+    fn saved_lists_need_unique_names_and_a_real_choice() {
+        let profiles = [profile("Coding", &[]), profile("Sales", &[])];
+        assert!(validate_all(&profiles, None).is_ok());
+        assert!(validate_all(&profiles, Some("Sales")).is_ok());
+        assert!(validate_all(&profiles, Some("Support")).is_err());
+        assert!(validate_all(&[profile("Coding", &[]), profile("coding", &[])], None).is_err());
+
+        let mut unknown_variable = profile("Support", &[]);
+        unknown_variable.vision_prompt = "{{screen_owner}}".into();
+        assert!(validate_all(&[unknown_variable], None).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+// prompt_template.rs — Live context variables in system prompts.
+//
+// A prompt may mention `{{active_window}}`, `{{time}}`, `{{monitor_name}}`,
+// `{{recent_transcript}}`, `{{last_suggestion}}` or `{{user_notes}}`.
+// `StreamManager` renders them just before each request, so the model sees
+// the values as of that frame (or, for audio, as of the session start).
+// Anything in braces that isn't a plain identifier — JSON examples, say — is
+// left alone.
+
+use crate::error::BemeError;
+use std::ops::Range;
+
+/// Every variable a prompt can use.
+pub const VARIABLES: [&str; 6] = [
+    "active_window",
+    "time",
+    "monitor_name",
+    "recent_transcript",
+    "last_suggestion",
+    "user_notes",
+];
+
+/// Values to render a prompt with. Missing values render as empty text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PromptContext {
+    /// "App — window title" of the frontmost window.
+    pub active_window: Option<String>,
+    /// UTC, ISO 8601.
+    pub time: String,
+    pub monitor_name: Option<String>,
+    /// The last few things heard on the audio stream, one per line.
+    pub recent_transcript: String,
+    pub last_suggestion: Option<String>,
+    pub user_notes: String,
+}
+
+impl PromptContext {
+    fn value(&self, name: &str) -> Option<&str> {
+        Some(match name {
+            "active_window" => self.active_window.as_deref().unwrap_or_default(),
+            "time" => &self.time,
+            "monitor_name" => self.monitor_name.as_deref().unwrap_or_default(),
+            "recent_transcript" => &self.recent_transcript,
+            "last_suggestion" => self.last_suggestion.as_deref().unwrap_or_default(),
+            "user_notes" => &self.user_notes,
+            _ => return None,
+        })
+    }
+}
+
+/// Each `{{name}}` in `template`: where it is, and the trimmed name.
+fn placeholders(template: &str) -> Vec<(Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(open) = template[from..].find("{{").map(|i| from + i) {
+        let Some(close) = template[open + 2..].find("}}").map(|i| open + 2 + i) else {
+            break;
+        };
+        let name = template[open + 2..close].trim();
+        let is_identifier =
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_identifier {
+            found.push((open..close + 2, name));
+            from = close + 2;
+        } else {
+            from = open + 1;
+        }
+    }
+    found
+}
+
+/// Whether `template` mentions variable `name`.
+pub fn uses(template: &str, name: &str) -> bool {
+    placeholders(template).iter().any(|(_, n)| *n == name)
+}
+
+/// Variables in `template` that aren't in [`VARIABLES`], in order of first
+/// appearance.
+pub fn unknown_variables(template: &str) -> Vec<String> {
+    let mut unknown: Vec<String> = Vec::new();
+    for (_, name) in placeholders(template) {
+        if !VARIABLES.contains(&name) && !unknown.iter().any(|u| u == name) {
+            unknown.push(name.to_string());
+        }
+    }
+    unknown
+}
+
+/// Reject a prompt that uses variables beme doesn't know, naming them.
+pub fn validate(template: &str) -> Result<(), BemeError> {
+    let unknown = unknown_variables(template);
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let list = |names: &mut dyn Iterator<Item = &str>| {
+        names
+            .map(|n| format!("{{{{{}}}}}", n))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Err(BemeError::InvalidInput(format!(
+        "unknown prompt variable{} {} (available: {})",
+        if unknown.len() == 1 { "" } else { "s" },
+        list(&mut unknown.iter().map(String::as_str)),
+        list(&mut VARIABLES.into_iter()),
+    )))
+}
+
+/// Substitute every known variable in `template`; unknown ones stay as
+/// written.
+pub fn render(template: &str, context: &PromptContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut last = 0;
+    for (range, name) in placeholders(template) {
+        if let Some(value) = context.value(name) {
+            out.push_str(&template[last..range.start]);
+            out.push_str(value);
+            last = range.end;
+        }
+    }
+    out.push_str(&template[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_known_variables() {
+        let context = PromptContext {
+            active_window: Some("Code — main.rs".into()),
+            time: "2026-01-02T03:04:05Z".into(),
+            user_notes: "I'm on the billing team.".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(
+                "At {{time}} I'm in {{ active_window }}. {{user_notes}}{{last_suggestion}}",
+                &context
+            ),
+            "At 2026-01-02T03:04:05Z I'm in Code — main.rs. I'm on the billing team."
+        );
+    }
+
+    #[test]
+    fn leaves_other_braces_alone() {
+        let template = r#"Answer like {{"action": "…"}} about {{monitor_name}} {{ nope }} {{"#;
+        assert_eq!(
+            render(template, &PromptContext::default()),
+            r#"Answer like {{"action": "…"}} about  {{ nope }} {{"#
+        );
+        assert!(uses(template, "monitor_name"));
+        assert!(!uses(template, "time"));
+    }
+
+    #[test]
+    fn reports_unknown_variables() {
+        assert!(validate("Plain prompt, {{time}} and {{ user_notes }}.").is_ok());
+        assert_eq!(
+            unknown_variables("{{app}} {{time}} {{app}} {{window_title}}"),
+            ["app", "window_title"]
+        );
+        let err = validate("{{app}}").unwrap_err().to_string();
+        assert!(err.contains("{{app}}"), "{err}");
+        assert!(err.contains("{{active_window}}"), "{err}");
+    }
+}
@@ -0,0 +1,506 @@
+// replay.rs — Feeds recorded frames and audio through the live pipeline.
+//
+// A recording directory holds JPEG frames whose file stem ends in the frame's
+// offset in milliseconds (e.g. `000000.jpg`, `frame-002000.jpg`) plus an
+// optional WAV file (`audio.wav`, or the only `.wav` in the directory).
+// Frames go through the same perceptual-hash diffing and JPEG encoding as
+// live capture, and audio is down-mixed, resampled to 24kHz PCM16 and cut
+// into the same chunk size, so prompts and providers can be A/B-tested on
+// identical input without `xcap` or `cpal`.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::Engine as _;
+use serde::Serialize;
+
+use crate::capture::audio::{compute_rms, AudioChunkPayload, AudioLevelPayload};
+use crate::capture::resample::resample;
+use crate::capture::screen::{process_frame, FramePayload};
+use crate::stream_manager::StreamManager;
+
+/// One JPEG frame on disk and when it was captured.
+#[derive(Debug, Clone)]
+pub struct RecordedFrame {
+    pub offset_ms: u64,
+    pub path: PathBuf,
+}
+
+/// The contents of a recording directory.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    /// Frames sorted by offset.
+    pub frames: Vec<RecordedFrame>,
+    /// WAV file to replay alongside the frames, if any.
+    pub audio: Option<PathBuf>,
+}
+
+impl Recording {
+    /// Scan a recording directory.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let entries = std::fs::read_dir(dir).map_err(|e| format!("read {}: {e}", dir.display()))?;
+
+        let mut frames = Vec::new();
+        let mut wavs = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_ascii_lowercase());
+            match ext.as_deref() {
+                Some("jpg") | Some("jpeg") => {
+                    let offset_ms = offset_from_stem(&path).ok_or_else(|| {
+                        format!(
+                            "frame {} has no millisecond offset in its name",
+                            path.display()
+                        )
+                    })?;
+                    frames.push(RecordedFrame { offset_ms, path });
+                }
+                Some("wav") => wavs.push(path),
+                _ => {}
+            }
+        }
+        frames.sort_by_key(|f| f.offset_ms);
+
+        let audio = match wavs.len() {
+            0 => None,
+            1 => wavs.pop(),
+            _ => Some(
+                wavs.into_iter()
+                    .find(|p| p.file_name().is_some_and(|n| n == "audio.wav"))
+                    .ok_or("several WAV files found; name the one to replay audio.wav")?,
+            ),
+        };
+
+        Ok(Self { frames, audio })
+    }
+}
+
+/// Parse the trailing digits of a file stem as a millisecond offset.
+fn offset_from_stem(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    let digits_at = stem
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |i| i + 1);
+    stem[digits_at..].parse().ok()
+}
+
+/// How to replay a recording.
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Playback speed: 1.0 = real time, 4.0 = four times faster,
+    /// 0.0 = as fast as possible.
+    pub speed: f64,
+    /// Same meaning as for live screen capture.
+    pub max_width: u32,
+    pub jpeg_quality: u8,
+    /// Target PCM sample rate and chunk size, as for live audio capture.
+    pub sample_rate: u32,
+    pub chunk_ms: u32,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            max_width: 1024,
+            jpeg_quality: 75,
+            sample_rate: 24000,
+            chunk_ms: 250,
+        }
+    }
+}
+
+/// A single step of the replay, in timeline order.
+pub enum ReplayEvent {
+    /// A frame that differed enough from the previous one to be analysed.
+    Frame {
+        offset_ms: u64,
+        payload: FramePayload,
+    },
+    /// A frame dropped by the diffing stage.
+    FrameSkipped { offset_ms: u64 },
+    /// A chunk of PCM16 little-endian audio at the target rate.
+    Audio { offset_ms: u64, pcm: Vec<u8> },
+}
+
+impl ReplayEvent {
+    pub fn offset_ms(&self) -> u64 {
+        match self {
+            ReplayEvent::Frame { offset_ms, .. }
+            | ReplayEvent::FrameSkipped { offset_ms }
+            | ReplayEvent::Audio { offset_ms, .. } => *offset_ms,
+        }
+    }
+}
+
+/// Walks a recording in timeline order, merging frames and audio chunks.
+/// Frames are decoded lazily, one at a time.
+pub struct Replay {
+    frames: VecDeque<RecordedFrame>,
+    pcm: Vec<i16>,
+    audio_pos: usize,
+    chunk_samples: usize,
+    options: ReplayOptions,
+    last_hash: Arc<Mutex<u64>>,
+}
+
+impl Replay {
+    /// Prepare a replay, decoding and resampling the audio track up front.
+    pub fn new(recording: Recording, options: ReplayOptions) -> Result<Self, String> {
+        let pcm = match &recording.audio {
+            Some(path) => load_wav_pcm(path, options.sample_rate)?,
+            None => Vec::new(),
+        };
+        let chunk_samples =
+            (options.sample_rate as usize * options.chunk_ms as usize / 1000).max(1);
+
+        Ok(Self {
+            frames: recording.frames.into(),
+            pcm,
+            audio_pos: 0,
+            chunk_samples,
+            options,
+            last_hash: Arc::new(Mutex::new(0)),
+        })
+    }
+
+    /// Whether the recording has any audio to replay.
+    pub fn has_audio(&self) -> bool {
+        !self.pcm.is_empty()
+    }
+
+    fn next_audio_offset_ms(&self) -> Option<u64> {
+        (self.audio_pos < self.pcm.len())
+            .then(|| self.audio_pos as u64 * 1000 / self.options.sample_rate as u64)
+    }
+}
+
+impl Iterator for Replay {
+    type Item = Result<ReplayEvent, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame_at = self.frames.front().map(|f| f.offset_ms);
+        let audio_at = self.next_audio_offset_ms();
+
+        // Audio wins ties so a frame sees the sound that preceded it.
+        let take_frame = match (frame_at, audio_at) {
+            (None, None) => return None,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some(f), Some(a)) => f < a,
+        };
+
+        if take_frame {
+            let frame = self.frames.pop_front()?;
+            let offset_ms = frame.offset_ms;
+            let result = image::open(&frame.path)
+                .map_err(|e| format!("decode {}: {e}", frame.path.display()))
+                .and_then(|img| {
+                    process_frame(
+                        img,
+                        self.options.max_width,
+                        self.options.jpeg_quality,
+                        &self.last_hash,
+                    )
+                })
+                .map(|payload| match payload {
+                    Some(payload) => ReplayEvent::Frame { offset_ms, payload },
+                    None => ReplayEvent::FrameSkipped { offset_ms },
+                });
+            Some(result)
+        } else {
+            let offset_ms = audio_at?;
+            let end = (self.audio_pos + self.chunk_samples).min(self.pcm.len());
+            let pcm = self.pcm[self.audio_pos..end]
+                .iter()
+                .flat_map(|s| s.to_le_bytes())
+                .collect();
+            self.audio_pos = end;
+            Some(Ok(ReplayEvent::Audio { offset_ms, pcm }))
+        }
+    }
+}
+
+/// Decode a WAV file to mono PCM16 at `target_rate`.
+fn load_wav_pcm(path: &Path, target_rate: u32) -> Result<Vec<i16>, String> {
+    let mut reader =
+        hound::WavReader::open(path).map_err(|e| format!("open {}: {e}", path.display()))?;
+    let spec = reader.spec();
+    let channels = spec.channels.max(1) as usize;
+
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("read {}: {e}", path.display()))?,
+        hound::SampleFormat::Int => {
+            let scale = (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|v| v as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("read {}: {e}", path.display()))?
+        }
+    };
+
+    let mono: Vec<f32> = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    let resampled = resample(&mono, spec.sample_rate, target_rate);
+
+    Ok(resampled
+        .iter()
+        .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect())
+}
+
+/// Totals reported when a replay finishes (`replay:finished` event).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayStats {
+    pub frames_analyzed: u32,
+    pub frames_skipped: u32,
+    pub audio_chunks: u32,
+    pub duration_ms: u64,
+}
+
+/// Replay a recording through the live pipeline: analysed frames go to
+/// `StreamManager::analyze_frame`, audio chunks to `process_audio_chunk`,
+/// and the same `capture:*` events are emitted (through the manager's event
+/// sink) as during live capture. A recording with audio gets its own audio
+/// session, closed when the replay ends, unless one is already open.
+pub async fn run(
+    replay: Replay,
+    stream_manager: Arc<StreamManager>,
+) -> Result<ReplayStats, String> {
+    let events = stream_manager.event_sink();
+    let speed = replay.options.speed;
+    let sample_rate = replay.options.sample_rate;
+    let chunk_ms = replay.options.chunk_ms;
+    let start = tokio::time::Instant::now();
+    let mut stats = ReplayStats::default();
+
+    let own_session = replay.has_audio() && !stream_manager.has_audio_session().await;
+    if own_session {
+        stream_manager
+            .start_audio_session()
+            .await
+            .map_err(|e| format!("audio session: {e}"))?;
+    }
+
+    log::info!("Replay started (speed={speed})");
+    let mut failed = None;
+    for event in replay {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                failed = Some(e);
+                break;
+            }
+        };
+        let offset_ms = event.offset_ms();
+
+        if speed > 0.0 {
+            let due = start + Duration::from_secs_f64(offset_ms as f64 / 1000.0 / speed);
+            tokio::time::sleep_until(due).await;
+        }
+
+        match event {
+            ReplayEvent::Frame { payload, .. } => {
+                stats.frames_analyzed += 1;
+                // Sized by the recording options, not the token budget, so
+                // a replay sends the same frames every time.
+                stream_manager.analyze_frame(payload.data.clone(), None);
+                events.send("capture:frame", &payload);
+            }
+            ReplayEvent::FrameSkipped { .. } => {
+                stats.frames_skipped += 1;
+            }
+            ReplayEvent::Audio { pcm, .. } => {
+                stats.audio_chunks += 1;
+                let samples: Vec<i16> = pcm
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                    .collect();
+                let timestamp = crate::stream_manager::now_iso();
+                events.send(
+                    "capture:audio-level",
+                    &AudioLevelPayload {
+                        level: compute_rms(&samples),
+                        timestamp: timestamp.clone(),
+                    },
+                );
+                events.send(
+                    "capture:audio-chunk",
+                    &AudioChunkPayload {
+                        data: base64::engine::general_purpose::STANDARD.encode(&pcm),
+                        timestamp,
+                        sample_rate,
+                        duration_ms: chunk_ms,
+                    },
+                );
+                if let Err(e) = stream_manager.process_audio_chunk(&pcm).await {
+                    log::debug!("Replay audio chunk not forwarded: {}", e);
+                }
+            }
+        }
+        stats.duration_ms = offset_ms;
+    }
+
+    if own_session {
+        if let Err(e) = stream_manager.stop_audio_session().await {
+            log::warn!("Closing the replay's audio session: {}", e);
+        }
+    }
+    if let Some(e) = failed {
+        return Err(e);
+    }
+
+    log::info!(
+        "Replay finished: {} frames analysed, {} skipped, {} audio chunks",
+        stats.frames_analyzed,
+        stats.frames_skipped,
+        stats.audio_chunks
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, RgbaImage};
+
+    /// Left half dark, right half light — `split` moves the boundary.
+    fn write_frame(dir: &Path, name: &str, split: u32) {
+        let mut img = RgbaImage::new(64, 64);
+        for (x, _y, p) in img.enumerate_pixels_mut() {
+            let v = if x < split { 0 } else { 255 };
+            *p = image::Rgba([v, v, v, 255]);
+        }
+        DynamicImage::ImageRgba8(img)
+            .to_rgb8()
+            .save(dir.join(name))
+            .unwrap();
+    }
+
+    fn write_wav(path: &Path, rate: u32, channels: u16, secs: f32) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut w = hound::WavWriter::create(path, spec).unwrap();
+        let frames = (rate as f32 * secs) as u32;
+        for i in 0..frames {
+            let v = ((i as f32 * 0.05).sin() * 8000.0) as i16;
+            for _ in 0..channels {
+                w.write_sample(v).unwrap();
+            }
+        }
+        w.finalize().unwrap();
+    }
+
+    #[test]
+    fn offset_parsed_from_trailing_digits() {
+        assert_eq!(offset_from_stem(Path::new("002000.jpg")), Some(2000));
+        assert_eq!(offset_from_stem(Path::new("frame-1500.jpeg")), Some(1500));
+        assert_eq!(offset_from_stem(Path::new("frame.jpg")), None);
+    }
+
+    #[test]
+    fn load_sorts_frames_and_finds_audio() {
+        let dir = tempfile::tempdir().unwrap();
+        write_frame(dir.path(), "frame-4000.jpg", 48);
+        write_frame(dir.path(), "frame-0.jpg", 16);
+        write_frame(dir.path(), "frame-2000.jpg", 32);
+        write_wav(&dir.path().join("session.wav"), 24000, 1, 0.1);
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let rec = Recording::load(dir.path()).unwrap();
+        let offsets: Vec<u64> = rec.frames.iter().map(|f| f.offset_ms).collect();
+        assert_eq!(offsets, vec![0, 2000, 4000]);
+        assert!(rec.audio.unwrap().ends_with("session.wav"));
+    }
+
+    #[test]
+    fn unchanged_frames_are_skipped_by_diffing() {
+        let dir = tempfile::tempdir().unwrap();
+        write_frame(dir.path(), "0000.jpg", 16);
+        write_frame(dir.path(), "1000.jpg", 16); // identical → skipped
+        write_frame(dir.path(), "2000.jpg", 48);
+
+        let replay = Replay::new(
+            Recording::load(dir.path()).unwrap(),
+            ReplayOptions::default(),
+        )
+        .unwrap();
+        let kinds: Vec<&str> = replay
+            .map(|e| match e.unwrap() {
+                ReplayEvent::Frame { .. } => "frame",
+                ReplayEvent::FrameSkipped { .. } => "skipped",
+                ReplayEvent::Audio { .. } => "audio",
+            })
+            .collect();
+        assert_eq!(kinds, vec!["frame", "skipped", "frame"]);
+    }
+
+    #[test]
+    fn audio_is_resampled_and_chunked() {
+        let dir = tempfile::tempdir().unwrap();
+        // One second of 48kHz stereo → 24kHz mono in 250ms chunks.
+        write_wav(&dir.path().join("audio.wav"), 48000, 2, 1.0);
+
+        let replay = Replay::new(
+            Recording::load(dir.path()).unwrap(),
+            ReplayOptions::default(),
+        )
+        .unwrap();
+        let chunks: Vec<(u64, usize)> = replay
+            .map(|e| match e.unwrap() {
+                ReplayEvent::Audio { offset_ms, pcm } => (offset_ms, pcm.len()),
+                _ => panic!("expected audio only"),
+            })
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![(0, 12000), (250, 12000), (500, 12000), (750, 12000)]
+        );
+    }
+
+    #[test]
+    fn frames_and_audio_interleave_by_time() {
+        let dir = tempfile::tempdir().unwrap();
+        write_frame(dir.path(), "0.jpg", 16);
+        write_frame(dir.path(), "600.jpg", 48);
+        write_wav(&dir.path().join("audio.wav"), 24000, 1, 1.0);
+
+        let replay = Replay::new(
+            Recording::load(dir.path()).unwrap(),
+            ReplayOptions::default(),
+        )
+        .unwrap();
+        let timeline: Vec<(u64, bool)> = replay
+            .map(|e| {
+                let e = e.unwrap();
+                (e.offset_ms(), matches!(e, ReplayEvent::Audio { .. }))
+            })
+            .collect();
+        assert_eq!(
+            timeline,
+            vec![
+                (0, true),
+                (0, false),
+                (250, true),
+                (500, true),
+                (600, false),
+                (750, true),
+            ]
+        );
+    }
+}
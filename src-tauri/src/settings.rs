@@ -1,11 +1,34 @@
+use crate::ai::{GenerationConfig, SuggestionFormat};
+use crate::budget::BudgetSettings;
+use crate::capture::audio::{AudioCapture, AudioStreamConfig};
+use crate::error::BemeError;
+use crate::limits::LimitSettings;
+use crate::metering::PricingSettings;
+use crate::novelty::NoveltySettings;
+use crate::profiles::{self, PromptProfile};
+use crate::prompt_template;
+use crate::shortcuts::{self, ShortcutAction, ShortcutIssue};
+use crate::stream_manager::StreamManager;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tauri::Manager;
 
+/// Bundle identifier from `tauri.conf.json`; Tauri nests its per-app
+/// config and data directories under it.
+const APP_IDENTIFIER: &str = "com.erichansen.beme";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Settings {
+    /// AI provider id: "azure" or "scripted".
+    #[serde(default = "default_provider")]
+    pub provider: String,
+    /// JSON script replayed by the "scripted" provider.
+    #[serde(default)]
+    pub scripted_fixture: String,
     pub endpoint: String,
     #[serde(default)]
     pub api_key: String,
@@ -16,13 +39,130 @@ pub struct Settings {
     pub capture_interval: f64,
     pub screenshot_max_width: u32,
     pub frame_diff_threshold: u32,
+    /// Default prompts, used when no profile is active.
     pub vision_prompt: String,
     pub audio_prompt: String,
+    /// Free-form notes prompts can include as `{{user_notes}}`.
+    #[serde(default)]
+    pub user_notes: String,
+    /// Named prompt presets.
+    #[serde(default)]
+    pub profiles: Vec<PromptProfile>,
+    /// The profile chosen by hand; `None` uses the default prompts.
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    /// Switch profiles automatically by the frontmost window.
+    #[serde(default)]
+    pub auto_switch_profiles: bool,
+    /// Ask for free-text or structured (JSON schema) screen suggestions.
+    #[serde(default)]
+    pub suggestion_format: SuggestionFormat,
+    /// Output length, sampling, image detail and transcription model.
+    #[serde(default)]
+    pub generation: GenerationConfig,
+    /// Per-minute image token budget frames are shrunk to fit.
+    #[serde(default)]
+    pub budget: BudgetSettings,
+    /// What tokens cost, for usage reports.
+    #[serde(default)]
+    pub pricing: PricingSettings,
+    /// Caps on requests, tokens and cost that pause the AI pipeline.
+    #[serde(default)]
+    pub limits: LimitSettings,
+    /// Suppressing capture suggestions that repeat recent ones.
+    #[serde(default)]
+    pub novelty: NoveltySettings,
+    /// Audio device to capture from; `None` uses the system default.
+    #[serde(default)]
+    pub audio_device: Option<String>,
+    /// Stream config chosen per device name. Devices without an entry use
+    /// their default config.
+    #[serde(default)]
+    pub audio_configs: BTreeMap<String, AudioStreamConfig>,
+    #[serde(default)]
+    pub recording: RecordingSettings,
+    #[serde(default)]
+    pub shortcuts: ShortcutSettings,
+}
+
+/// Audio recording to rolling WAV segments under the app data dir.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordingSettings {
+    pub enabled: bool,
+    /// Start a new segment after this many seconds (0 = no limit).
+    pub max_segment_secs: u32,
+    /// Start a new segment before the file exceeds this many MB (0 = no limit).
+    pub max_segment_mb: u32,
+    /// Keep only the newest N segments per session (0 = keep all).
+    pub max_segments: u32,
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_segment_secs: 600,
+            max_segment_mb: 100,
+            max_segments: 0,
+        }
+    }
+}
+
+/// Global shortcut for each action, e.g. `"ctrl+shift+b"`. An empty string
+/// leaves the action unbound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShortcutSettings {
+    pub toggle_screen: String,
+    pub toggle_audio: String,
+    pub analyze_now: String,
+    pub pause_ai: String,
+    pub copy_last_suggestion: String,
+    pub toggle_dashboard: String,
+}
+
+impl ShortcutSettings {
+    /// Each action with the shortcut configured for it.
+    pub fn bindings(&self) -> [(ShortcutAction, &str); 6] {
+        [
+            (ShortcutAction::ToggleScreen, &self.toggle_screen),
+            (ShortcutAction::ToggleAudio, &self.toggle_audio),
+            (ShortcutAction::AnalyzeNow, &self.analyze_now),
+            (ShortcutAction::PauseAi, &self.pause_ai),
+            (
+                ShortcutAction::CopyLastSuggestion,
+                &self.copy_last_suggestion,
+            ),
+            (ShortcutAction::ToggleDashboard, &self.toggle_dashboard),
+        ]
+    }
+}
+
+impl Default for ShortcutSettings {
+    /// Only capture is bound out of the box, so beme doesn't grab keys other
+    /// apps may be using.
+    fn default() -> Self {
+        Self {
+            toggle_screen: "ctrl+shift+b".into(),
+            toggle_audio: String::new(),
+            analyze_now: String::new(),
+            pause_ai: String::new(),
+            copy_last_suggestion: String::new(),
+            toggle_dashboard: String::new(),
+        }
+    }
+}
+
+fn default_provider() -> String {
+    "azure".into()
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            provider: default_provider(),
+            scripted_fixture: String::new(),
             endpoint: String::new(),
             api_key: String::new(),
             vision_deployment: "gpt-4o".into(),
@@ -33,44 +173,148 @@ impl Default for Settings {
             frame_diff_threshold: 5,
             vision_prompt: "You are an AI assistant observing my screen. Analyze what you see and suggest the single best next action I should take. Be specific and actionable.".into(),
             audio_prompt: "You are listening to a conversation. Suggest the best response or follow-up question.".into(),
+            user_notes: String::new(),
+            profiles: Vec::new(),
+            active_profile: None,
+            auto_switch_profiles: false,
+            suggestion_format: SuggestionFormat::Text,
+            generation: GenerationConfig::default(),
+            budget: BudgetSettings::default(),
+            pricing: PricingSettings::default(),
+            limits: LimitSettings::default(),
+            novelty: NoveltySettings::default(),
+            audio_device: None,
+            audio_configs: BTreeMap::new(),
+            recording: RecordingSettings::default(),
+            shortcuts: ShortcutSettings::default(),
         }
     }
 }
 
-fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, BemeError> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| BemeError::Settings(e.to_string()))?;
+    fs::create_dir_all(&dir)?;
     Ok(dir.join("settings.toml"))
 }
 
+/// The settings file the app uses, resolved without a running app —
+/// for tools like `beme-cli` that share the dashboard's configuration.
+pub fn default_settings_path() -> Option<PathBuf> {
+    Some(
+        dirs::config_dir()?
+            .join(APP_IDENTIFIER)
+            .join("settings.toml"),
+    )
+}
+
+/// The app's data directory (where `usage.json` lives), resolved without
+/// a running app.
+pub fn default_data_dir() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join(APP_IDENTIFIER))
+}
+
 impl Settings {
     /// Load settings from the app config directory (non-command helper).
-    pub fn load_from_app(app: &tauri::AppHandle) -> Result<Self, String> {
-        let path = config_path(app)?;
+    pub fn load_from_app(app: &tauri::AppHandle) -> Result<Self, BemeError> {
+        Self::load_from_path(&config_path(app)?)
+    }
+
+    /// Load settings from a file, falling back to defaults if it doesn't exist.
+    pub fn load_from_path(path: &Path) -> Result<Self, BemeError> {
         if !path.exists() {
             return Ok(Self::default());
         }
-        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        toml::from_str(&content).map_err(|e| e.to_string())
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content)
+            .map_err(|e| BemeError::Settings(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Persist settings to the app config directory (non-command helper).
+    pub fn save_to_app(&self, app: &tauri::AppHandle) -> Result<(), BemeError> {
+        let path = config_path(app)?;
+        let content =
+            toml::to_string_pretty(self).map_err(|e| BemeError::Settings(e.to_string()))?;
+        fs::write(&path, content)?;
+        log::info!("Settings saved to {}", path.display());
+        Ok(())
+    }
+
+    /// The profile called `name`, if there is one.
+    pub fn profile(&self, name: &str) -> Option<&PromptProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// The profile chosen by hand, if it still exists.
+    pub fn chosen_profile(&self) -> Option<&PromptProfile> {
+        self.active_profile.as_deref().and_then(|n| self.profile(n))
+    }
+
+    /// Point `audio` at the saved device and stream configs.
+    pub fn apply_audio_device(&self, audio: &AudioCapture) {
+        audio.set_device(self.audio_device.clone());
+        audio.set_device_configs(
+            self.audio_configs
+                .iter()
+                .map(|(name, config)| (name.clone(), config.clone()))
+                .collect(),
+        );
+    }
+
+    /// Vision and audio prompts for profile `name`, or the defaults for
+    /// `None` or a profile that no longer exists.
+    pub fn prompts_for(&self, name: Option<&str>) -> (&str, &str) {
+        match name.and_then(|n| self.profile(n)) {
+            Some(p) => (&p.vision_prompt, &p.audio_prompt),
+            None => (&self.vision_prompt, &self.audio_prompt),
+        }
     }
 }
 
+/// Save settings and re-register global shortcuts from them. Invalid or
+/// conflicting shortcuts, prompts with unknown variables, duplicate profile
+/// names, an active profile that doesn't exist, or out-of-range generation,
+/// budget, pricing, limit or repeat-suppression parameters reject the save;
+/// shortcuts the OS refuses (usually held by another app) are returned so
+/// the dashboard can show them.
 #[tauri::command]
-pub async fn save_settings(app: tauri::AppHandle, settings: Settings) -> Result<(), String> {
-    let path = config_path(&app)?;
-    let content = toml::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-    fs::write(&path, content).map_err(|e| e.to_string())?;
-    log::info!("Settings saved to {}", path.display());
-    Ok(())
+pub async fn save_settings(
+    app: tauri::AppHandle,
+    settings: Settings,
+) -> Result<Vec<ShortcutIssue>, BemeError> {
+    shortcuts::validate(&settings.shortcuts).map_err(|issues| shortcuts::issues_error(&issues))?;
+    prompt_template::validate(&settings.vision_prompt)?;
+    prompt_template::validate(&settings.audio_prompt)?;
+    profiles::validate_all(&settings.profiles, settings.active_profile.as_deref())?;
+    settings.generation.validate()?;
+    settings.budget.validate()?;
+    settings.pricing.validate()?;
+    settings.limits.validate()?;
+    settings.novelty.validate()?;
+    settings.save_to_app(&app)?;
+    let sm = app.state::<Arc<StreamManager>>();
+    sm.set_suggestion_format(settings.suggestion_format);
+    sm.set_generation_config(settings.generation.clone());
+    sm.set_budget_settings(settings.budget.clone());
+    sm.set_pricing(settings.pricing.clone());
+    sm.set_limits(settings.limits.clone());
+    sm.set_novelty_settings(settings.novelty.clone());
+    sm.set_user_notes(&settings.user_notes);
+    let audio = app.state::<Arc<AudioCapture>>();
+    settings.apply_audio_device(&audio);
+    // New segment limits apply from the next capture session
+    audio.set_recording(if settings.recording.enabled {
+        Some(crate::recording_options(&app, &settings.recording)?)
+    } else {
+        None
+    });
+    profiles::sync(&app, &settings);
+    shortcuts::apply(&app, &settings.shortcuts)
 }
 
 #[tauri::command]
-pub async fn load_settings(app: tauri::AppHandle) -> Result<Settings, String> {
-    let path = config_path(&app)?;
-    if !path.exists() {
-        return Ok(Settings::default());
-    }
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let settings: Settings = toml::from_str(&content).map_err(|e| e.to_string())?;
-    Ok(settings)
+pub async fn load_settings(app: tauri::AppHandle) -> Result<Settings, BemeError> {
+    Settings::load_from_app(&app)
 }
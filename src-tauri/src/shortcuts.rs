@@ -0,0 +1,306 @@
+// shortcuts.rs — Configurable global shortcuts.
+//
+// Settings map each `ShortcutAction` to a shortcut string such as
+// "ctrl+shift+b". `validate` parses them and reports bad syntax and keys
+// bound twice; `apply` swaps the registered set for the new one at startup
+// and whenever settings are saved. The plugin calls `on_shortcut_event` for
+// every press, which looks up the action in the `ShortcutRegistry`.
+
+use crate::controller::CaptureController;
+use crate::error::BemeError;
+use crate::settings::ShortcutSettings;
+use crate::stream_manager::StreamManager;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{
+    Code, GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState,
+};
+
+/// Something a global shortcut can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutAction {
+    ToggleScreen,
+    ToggleAudio,
+    AnalyzeNow,
+    PauseAi,
+    CopyLastSuggestion,
+    ToggleDashboard,
+}
+
+impl ShortcutAction {
+    /// Human-readable name for logs and conflict messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleScreen => "Toggle capture",
+            ShortcutAction::ToggleAudio => "Toggle audio",
+            ShortcutAction::AnalyzeNow => "Analyze now",
+            ShortcutAction::PauseAi => "Pause AI",
+            ShortcutAction::CopyLastSuggestion => "Copy last suggestion",
+            ShortcutAction::ToggleDashboard => "Show/hide dashboard",
+        }
+    }
+}
+
+/// A shortcut that can't be used, and why.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShortcutIssue {
+    pub action: ShortcutAction,
+    pub shortcut: String,
+    pub message: String,
+}
+
+/// Parse every bound shortcut. Fails with one issue per shortcut that doesn't
+/// parse, has no modifier, or is already bound to an earlier action.
+pub fn validate(
+    settings: &ShortcutSettings,
+) -> Result<Vec<(Shortcut, ShortcutAction)>, Vec<ShortcutIssue>> {
+    let mut bound: Vec<(Shortcut, ShortcutAction)> = Vec::new();
+    let mut issues = Vec::new();
+    for (action, text) in settings.bindings() {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let issue = |message: String| ShortcutIssue {
+            action,
+            shortcut: text.to_string(),
+            message,
+        };
+        match text.parse::<Shortcut>() {
+            Err(e) => issues.push(issue(e.to_string())),
+            Ok(shortcut) if shortcut.mods.is_empty() && !is_function_key(&shortcut) => issues.push(
+                issue("needs a modifier (ctrl, alt, shift or super) unless it's F1–F24".into()),
+            ),
+            Ok(shortcut) => match bound.iter().find(|(s, _)| *s == shortcut) {
+                Some((_, other)) => {
+                    issues.push(issue(format!("already used by \"{}\"", other.label())))
+                }
+                None => bound.push((shortcut, action)),
+            },
+        }
+    }
+    if issues.is_empty() {
+        Ok(bound)
+    } else {
+        Err(issues)
+    }
+}
+
+/// F1–F24 are fine on their own; anything else would swallow normal typing.
+fn is_function_key(shortcut: &Shortcut) -> bool {
+    matches!(
+        shortcut.key,
+        Code::F1
+            | Code::F2
+            | Code::F3
+            | Code::F4
+            | Code::F5
+            | Code::F6
+            | Code::F7
+            | Code::F8
+            | Code::F9
+            | Code::F10
+            | Code::F11
+            | Code::F12
+            | Code::F13
+            | Code::F14
+            | Code::F15
+            | Code::F16
+            | Code::F17
+            | Code::F18
+            | Code::F19
+            | Code::F20
+            | Code::F21
+            | Code::F22
+            | Code::F23
+            | Code::F24
+    )
+}
+
+/// Fold validation issues into one error for commands that reject them.
+pub fn issues_error(issues: &[ShortcutIssue]) -> BemeError {
+    let details: Vec<String> = issues
+        .iter()
+        .map(|i| format!("{} ({}): {}", i.action.label(), i.shortcut, i.message))
+        .collect();
+    BemeError::InvalidInput(format!("shortcuts: {}", details.join("; ")))
+}
+
+/// The shortcuts currently registered with the OS and what each one does.
+#[derive(Default)]
+pub struct ShortcutRegistry {
+    bound: Mutex<Vec<(Shortcut, ShortcutAction)>>,
+}
+
+impl ShortcutRegistry {
+    pub fn action_for(&self, shortcut: &Shortcut) -> Option<ShortcutAction> {
+        self.bound
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(s, _)| s == shortcut)
+            .map(|(_, action)| *action)
+    }
+}
+
+/// Replace the registered shortcuts with `settings`. Invalid settings are
+/// rejected without touching what's registered. Shortcuts the OS refuses are
+/// returned as issues; the rest stay active.
+pub fn apply(
+    app: &AppHandle,
+    settings: &ShortcutSettings,
+) -> Result<Vec<ShortcutIssue>, BemeError> {
+    let wanted = validate(settings).map_err(|issues| issues_error(&issues))?;
+    let registry = app.state::<ShortcutRegistry>();
+    let global = app.global_shortcut();
+
+    // Registering goes through the main thread, so don't hold the lock the
+    // shortcut handler needs while doing it.
+    let old = std::mem::take(&mut *registry.bound.lock().unwrap());
+    for (shortcut, _) in old {
+        if let Err(e) = global.unregister(shortcut) {
+            log::warn!("Couldn't unregister shortcut {}: {}", shortcut, e);
+        }
+    }
+
+    let mut bound = Vec::new();
+    let mut issues = Vec::new();
+    for (shortcut, action) in wanted {
+        match global.register(shortcut) {
+            Ok(()) => bound.push((shortcut, action)),
+            Err(e) => {
+                log::warn!("Shortcut {} for {}: {}", shortcut, action.label(), e);
+                issues.push(ShortcutIssue {
+                    action,
+                    shortcut: shortcut.to_string(),
+                    message: format!("couldn't register (in use by another app?): {}", e),
+                });
+            }
+        }
+    }
+    log::info!("{} global shortcut(s) registered", bound.len());
+    *registry.bound.lock().unwrap() = bound;
+    Ok(issues)
+}
+
+/// Check shortcuts without saving or registering them, for live feedback in
+/// the settings form.
+#[tauri::command]
+pub fn validate_shortcuts(shortcuts: ShortcutSettings) -> Vec<ShortcutIssue> {
+    validate(&shortcuts).err().unwrap_or_default()
+}
+
+// ─── Handler ─────────────────────────────────────────────────────────
+
+/// Called by the global-shortcut plugin when *any* registered shortcut fires.
+/// Acts on key-down only.
+pub fn on_shortcut_event(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(action) = app.state::<ShortcutRegistry>().action_for(shortcut) else {
+        return;
+    };
+    log::info!("Shortcut {}: {}", shortcut, action.label());
+    run_action(app, action);
+}
+
+fn run_action(app: &AppHandle, action: ShortcutAction) {
+    match action {
+        ShortcutAction::ToggleScreen => crate::tray::toggle_capture(app, "Shortcut"),
+        ShortcutAction::ToggleAudio => {
+            let controller = Arc::clone(&*app.state::<Arc<CaptureController>>());
+            tauri::async_runtime::spawn(async move {
+                let enabled = !controller.audio_enabled();
+                controller.set_audio_enabled(enabled).await;
+            });
+        }
+        ShortcutAction::AnalyzeNow => {
+            let controller = Arc::clone(&*app.state::<Arc<CaptureController>>());
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = controller.analyze_now(None, None).await {
+                    log::error!("Analyze now: {}", e);
+                }
+            });
+        }
+        ShortcutAction::PauseAi => {
+            let sm = app.state::<Arc<StreamManager>>();
+            sm.set_paused(!sm.is_paused());
+        }
+        ShortcutAction::CopyLastSuggestion => {
+            match app.state::<Arc<StreamManager>>().last_suggestion() {
+                Some(text) => {
+                    if let Err(e) = app.clipboard().write_text(text) {
+                        log::error!("Copy last suggestion: {}", e);
+                    }
+                }
+                None => log::info!("Copy last suggestion: nothing to copy yet"),
+            }
+        }
+        ShortcutAction::ToggleDashboard => {
+            if let Some(win) = app.get_webview_window("dashboard") {
+                if win.is_visible().unwrap_or(false) {
+                    let _ = win.hide();
+                } else {
+                    let _ = win.show();
+                    let _ = win.set_focus();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unbound() -> ShortcutSettings {
+        ShortcutSettings {
+            toggle_screen: String::new(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn defaults_bind_only_capture() {
+        let bound = validate(&ShortcutSettings::default()).unwrap();
+        assert_eq!(bound.len(), 1);
+        assert_eq!(bound[0].1, ShortcutAction::ToggleScreen);
+        assert_eq!(bound[0].0, "ctrl+shift+b".parse::<Shortcut>().unwrap());
+    }
+
+    #[test]
+    fn reports_the_same_key_bound_twice() {
+        let settings = ShortcutSettings {
+            pause_ai: "Shift+Control+B".into(),
+            ..Default::default()
+        };
+        let issues = validate(&settings).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].action, ShortcutAction::PauseAi);
+        assert!(issues[0].message.contains("Toggle capture"));
+    }
+
+    #[test]
+    fn rejects_unparseable_and_unmodified_keys() {
+        let settings = ShortcutSettings {
+            toggle_audio: "ctrl+shift+nope".into(),
+            analyze_now: "f".into(),
+            pause_ai: "F9".into(),
+            toggle_dashboard: "alt+d".into(),
+            ..unbound()
+        };
+        let issues = validate(&settings).unwrap_err();
+        let actions: Vec<_> = issues.iter().map(|i| i.action).collect();
+        assert_eq!(
+            actions,
+            [ShortcutAction::ToggleAudio, ShortcutAction::AnalyzeNow]
+        );
+        assert!(issues_error(&issues)
+            .to_string()
+            .contains("Analyze now (f)"));
+    }
+}
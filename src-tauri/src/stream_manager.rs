@@ -1,14 +1,33 @@
 // stream_manager.rs — Orchestrates capture → AI → suggestion pipeline.
 //
 // When capture starts, frames are sent to the configured AI provider.
-// AI responses are streamed back as `ai:suggestion` Tauri events.
+// AI responses are streamed back as `ai:suggestion` events through the
+// attached `EventSink` (the Tauri frontend in the app, a channel in tests).
+// Prompts are stored as written and their `{{variables}}` filled in just
+// before each request (see `prompt_template`). The tokens each response
+// used are priced and added up by a `Meter` (see `metering`), and spending
+// limits (see `limits`) pause the capture pipeline once reached.
 
 use crate::ai::azure_audio::AzureAudioClient;
 use crate::ai::azure_vision::AzureVisionClient;
-use crate::ai::{AiProvider, AudioSession};
+use crate::ai::structured::StructuredSuggestion;
+use crate::ai::{
+    AiError, AiProvider, AudioResponse, AudioSession, GenerationConfig, SuggestionFormat,
+    TextStream, TokenUsage, VisionRequest,
+};
+use crate::budget::{BudgetSettings, FramePlan, FrameSize, TokenBudget};
+use crate::error::BemeError;
+use crate::events::{EventSink, NullSink};
+use crate::limits::{LimitHit, LimitSettings, Limiter};
+use crate::metering::{Meter, PricingSettings, UsageReport, UsageSource, UsageTotals};
+use crate::novelty::{NoveltyFilter, NoveltySettings, Verdict};
+use crate::prompt_template::{self, PromptContext};
 use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex as TokioMutex;
 
 /// Payload emitted on `ai:suggestion` events.
@@ -19,15 +38,95 @@ pub struct SuggestionPayload {
     pub done: bool,
     pub id: u64,
     pub source: String,
+    /// What the user asked, for "analyze now" requests and follow-ups.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub question: Option<String>,
+    /// The suggestion this one answers a follow-up about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u64>,
+    /// The conversation thread a screen suggestion belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<u64>,
 }
 
-/// Payload emitted on `ai:error` events.
+/// Payload emitted on `ai:structured-suggestion` events when a structured
+/// screen suggestion completes, just before its `ai:suggestion` events
+/// (which carry the action as text).
 #[derive(Clone, Serialize)]
+pub struct StructuredSuggestionPayload {
+    pub id: u64,
+    pub timestamp: String,
+    #[serde(flatten)]
+    pub suggestion: StructuredSuggestion,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub question: Option<String>,
+    pub thread_id: u64,
+}
+
+/// Payload emitted on `suggestion:suppressed` events when a screen
+/// suggestion repeats a recent one. Structured suggestions are withheld
+/// entirely; free-text ones have already streamed and should be collapsed.
+#[derive(Clone, Serialize)]
+pub struct SuggestionSuppressedPayload {
+    pub id: u64,
+    pub duplicate_of: u64,
+    /// Word overlap with `duplicate_of`, 0–1.
+    pub similarity: f64,
+    pub reason: String, // "identical" or "similar"
+    pub timestamp: String,
+}
+
+/// Payload emitted on `ai:error` events: the error's kind, message, retry
+/// hint and HTTP status / provider code, plus which pipeline and suggestion
+/// it belongs to.
+#[derive(Serialize)]
 pub struct AiErrorPayload {
-    pub message: String,
+    #[serde(flatten)]
+    pub error: BemeError,
+    pub source: String, // "screen" or "audio"
+    pub suggestion_id: Option<u64>,
     pub timestamp: String,
 }
 
+/// Payload emitted on `ai:usage` events when a response reports the tokens
+/// it used, with the running totals it was added to.
+#[derive(Clone, Serialize)]
+pub struct UsagePayload {
+    pub source: UsageSource,
+    /// The suggestion (or audio turn) the response belongs to.
+    pub suggestion_id: Option<u64>,
+    pub usage: TokenUsage,
+    pub cost: f64,
+    pub currency: String,
+    pub session: UsageTotals,
+    pub today: UsageTotals,
+    pub timestamp: String,
+}
+
+/// Payload emitted on `ai:limit` events when a spending limit is reached,
+/// lifts, or is overridden.
+#[derive(Clone, Serialize)]
+pub struct LimitPayload {
+    pub state: String, // "reached", "cleared" or "overridden"
+    /// The limit reached, or set aside by an override.
+    pub limit: Option<LimitHit>,
+    /// How long an override lasts.
+    pub override_secs: Option<u64>,
+    pub timestamp: String,
+}
+
+/// Answer to `limit_status`.
+#[derive(Clone, Serialize)]
+pub struct LimitStatus {
+    pub limits: LimitSettings,
+    /// The limit currently reached, if any.
+    pub reached: Option<LimitHit>,
+    /// Seconds left on an override, if one is in force.
+    pub override_secs: Option<u64>,
+    /// Whether the audio session was closed for a limit and will reopen.
+    pub audio_suspended: bool,
+}
+
 /// Payload emitted on `ai:audio-status` events.
 #[derive(Clone, Serialize)]
 pub struct AudioStatusPayload {
@@ -35,6 +134,87 @@ pub struct AudioStatusPayload {
     pub message: Option<String>,
 }
 
+/// Payload emitted on `ai:paused` events.
+#[derive(Clone, Serialize)]
+pub struct AiPausedPayload {
+    pub paused: bool,
+}
+
+/// Payload emitted on `ai:thread` events when screen suggestions move to a
+/// new conversation thread.
+#[derive(Clone, Serialize)]
+pub struct ThreadPayload {
+    pub thread_id: u64,
+    pub reason: String, // "capture", "monitor", "prompt", "provider", "reset" or "fork"
+    /// The suggestion a forked thread continues from.
+    pub forked_from: Option<u64>,
+}
+
+/// How many suggestions' response ids are kept for follow-ups and forks.
+const MAX_TRACKED_RESPONSES: usize = 100;
+
+/// How many transcribed utterances `{{recent_transcript}}` covers.
+const MAX_TRANSCRIPT_LINES: usize = 10;
+
+/// Finds the frontmost window for `{{active_window}}`. May block.
+pub type WindowProbe = Arc<dyn Fn() -> Option<String> + Send + Sync>;
+
+/// The conversation that captured frames are chained into. Each frame
+/// continues from `head`, the newest reply in the thread.
+struct Thread {
+    id: u64,
+    head: Option<String>,
+}
+
+/// The provider response behind a screen suggestion.
+#[derive(Clone)]
+struct TrackedResponse {
+    thread_id: u64,
+    response_id: String,
+}
+
+/// Where a screen suggestion's request comes from.
+struct Origin {
+    /// What the user asked, for "analyze now" requests and follow-ups.
+    question: Option<String>,
+    /// The suggestion a follow-up is about.
+    parent_id: Option<u64>,
+    thread_id: u64,
+    /// Response the request continued from.
+    previous_response_id: Option<String>,
+    /// Whether the reply becomes the thread's new head. Frames do (if the
+    /// head hasn't moved meanwhile); follow-ups are side conversations.
+    advances_thread: bool,
+    format: SuggestionFormat,
+    /// Whether repeats of recent suggestions are suppressed. Only for the
+    /// capture loop; anything the user asked for is always shown.
+    filter_duplicates: bool,
+    /// Estimated image tokens of the frame sent, for usage reports that
+    /// don't break them out.
+    image_tokens: Option<u32>,
+}
+
+/// A prompt and its variables' values, taken when a request is made.
+struct PendingPrompt {
+    template: String,
+    context: PromptContext,
+    /// Set when the template needs `{{active_window}}`.
+    window_probe: Option<WindowProbe>,
+}
+
+impl PendingPrompt {
+    /// Look up the active window if needed and fill in the variables.
+    async fn render(mut self) -> String {
+        if let Some(probe) = self.window_probe {
+            self.context.active_window = tokio::task::spawn_blocking(move || probe())
+                .await
+                .ok()
+                .flatten();
+        }
+        prompt_template::render(&self.template, &self.context)
+    }
+}
+
 /// Shared state for the AI pipeline.
 pub struct StreamManager {
     provider: Mutex<Option<Arc<dyn AiProvider>>>,
@@ -44,6 +224,37 @@ pub struct StreamManager {
     audio_provider: Mutex<Option<Arc<dyn AiProvider>>>,
     audio_session: Arc<TokioMutex<Option<Box<dyn AudioSession>>>>,
     audio_prompt: Mutex<String>,
+    events: Mutex<Arc<dyn EventSink>>,
+    /// While set, frames and audio are dropped instead of sent to the AI.
+    paused: AtomicBool,
+    /// Full text of the most recently completed suggestion (either source).
+    last_suggestion: Arc<Mutex<Option<String>>>,
+    /// Provider response behind each recent screen suggestion.
+    response_ids: Arc<Mutex<HashMap<u64, TrackedResponse>>>,
+    /// The thread new frames are chained into.
+    thread: Arc<Mutex<Thread>>,
+    /// How frames are asked to be answered.
+    format: Mutex<SuggestionFormat>,
+    /// Output length, sampling and image detail for every request.
+    generation: Mutex<GenerationConfig>,
+    /// Image tokens sent in the last minute, for sizing frames.
+    budget: Mutex<TokenBudget>,
+    /// Recent capture suggestions, for suppressing repeats.
+    novelty: Arc<Mutex<NoveltyFilter>>,
+    /// Token usage and cost, per session and per day.
+    meter: Arc<Mutex<Meter>>,
+    /// Requests and tokens counted against the spending limits.
+    limiter: Arc<Mutex<Limiter>>,
+    /// The limit last reported reached, to notice when that changes.
+    limit_hit: Mutex<Option<LimitHit>>,
+    /// Set while the audio session is closed for a limit.
+    audio_suspended: AtomicBool,
+    // Prompt variables that come from outside the pipeline
+    monitor_name: Mutex<Option<String>>,
+    user_notes: Mutex<String>,
+    window_probe: Mutex<Option<WindowProbe>>,
+    /// Recent audio transcriptions, oldest first.
+    transcript: Arc<Mutex<VecDeque<String>>>,
 }
 
 impl Default for StreamManager {
@@ -61,9 +272,336 @@ impl StreamManager {
             audio_provider: Mutex::new(None),
             audio_session: Arc::new(TokioMutex::new(None)),
             audio_prompt: Mutex::new(String::new()),
+            events: Mutex::new(Arc::new(NullSink)),
+            paused: AtomicBool::new(false),
+            last_suggestion: Arc::new(Mutex::new(None)),
+            response_ids: Arc::new(Mutex::new(HashMap::new())),
+            thread: Arc::new(Mutex::new(Thread { id: 1, head: None })),
+            format: Mutex::new(SuggestionFormat::Text),
+            generation: Mutex::new(GenerationConfig::default()),
+            budget: Mutex::new(TokenBudget::default()),
+            novelty: Arc::new(Mutex::new(NoveltyFilter::default())),
+            meter: Arc::new(Mutex::new(Meter::new(Instant::now(), now_iso()))),
+            limiter: Arc::new(Mutex::new(Limiter::default())),
+            limit_hit: Mutex::new(None),
+            audio_suspended: AtomicBool::new(false),
+            monitor_name: Mutex::new(None),
+            user_notes: Mutex::new(String::new()),
+            window_probe: Mutex::new(None),
+            transcript: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Create a manager that reports events to `sink`.
+    pub fn with_event_sink(sink: Arc<dyn EventSink>) -> Self {
+        let sm = Self::new();
+        sm.set_event_sink(sink);
+        sm
+    }
+
+    /// Replace the sink that suggestions, errors and status go to.
+    pub fn set_event_sink(&self, sink: Arc<dyn EventSink>) {
+        *self.events.lock().unwrap() = sink;
+    }
+
+    /// The sink currently receiving pipeline events.
+    pub fn event_sink(&self) -> Arc<dyn EventSink> {
+        Arc::clone(&self.events.lock().unwrap())
+    }
+
+    /// Pause or resume sending frames and audio to the AI. Capture keeps
+    /// running; emits `ai:paused` when the state changes.
+    pub fn set_paused(&self, paused: bool) {
+        if self.paused.swap(paused, Ordering::SeqCst) != paused {
+            log::info!("AI {}", if paused { "paused" } else { "resumed" });
+            self.event_sink()
+                .send("ai:paused", &AiPausedPayload { paused });
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Text of the most recently completed suggestion, if any.
+    pub fn last_suggestion(&self) -> Option<String> {
+        self.last_suggestion.lock().unwrap().clone()
+    }
+
+    /// Ask for free-text or structured answers to frames from now on.
+    /// Follow-ups are always answered as text.
+    pub fn set_suggestion_format(&self, format: SuggestionFormat) {
+        let old = std::mem::replace(&mut *self.format.lock().unwrap(), format);
+        if old != format {
+            log::info!("Suggestion format: {:?}", format);
+        }
+    }
+
+    pub fn suggestion_format(&self) -> SuggestionFormat {
+        *self.format.lock().unwrap()
+    }
+
+    /// Generation parameters for requests from now on. An audio session
+    /// already running keeps the ones it started with.
+    pub fn set_generation_config(&self, generation: GenerationConfig) {
+        let mut current = self.generation.lock().unwrap();
+        if *current != generation {
+            log::info!("Generation config: {:?}", generation);
+            *current = generation;
+        }
+    }
+
+    pub fn generation_config(&self) -> GenerationConfig {
+        self.generation.lock().unwrap().clone()
+    }
+
+    /// Change the image token budget frames are sized for.
+    pub fn set_budget_settings(&self, settings: BudgetSettings) {
+        self.budget.lock().unwrap().set_settings(settings);
+    }
+
+    pub fn budget_settings(&self) -> BudgetSettings {
+        self.budget.lock().unwrap().settings().clone()
+    }
+
+    /// Image tokens sent with frames in the last minute.
+    pub fn image_tokens_last_minute(&self) -> u32 {
+        self.budget.lock().unwrap().spent(Instant::now())
+    }
+
+    /// How to encode and send the next frame from a `source`-sized screen,
+    /// at most `base` and at the configured detail, to stay within the
+    /// image token budget. `interval` is how often frames are taken.
+    pub fn plan_frame(&self, source: (u32, u32), base: FrameSize, interval: Duration) -> FramePlan {
+        let detail = self.generation.lock().unwrap().image_detail;
+        let plan = self
+            .budget
+            .lock()
+            .unwrap()
+            .plan(Instant::now(), source, base, detail, interval);
+        if plan.size != base || plan.detail != detail {
+            log::debug!(
+                "Frame sized for the token budget: {}x{} at {:?} detail (~{} tokens)",
+                plan.width,
+                plan.height,
+                plan.detail,
+                plan.image_tokens
+            );
+        }
+        plan
+    }
+
+    /// Change what tokens cost.
+    pub fn set_pricing(&self, pricing: PricingSettings) {
+        self.meter.lock().unwrap().set_pricing(pricing);
+    }
+
+    pub fn pricing(&self) -> PricingSettings {
+        self.meter.lock().unwrap().pricing().clone()
+    }
+
+    /// Keep daily usage totals in `path` (loading any already there).
+    pub fn open_usage_log(&self, path: &std::path::Path) -> Result<(), BemeError> {
+        self.meter.lock().unwrap().open(path)
+    }
+
+    /// Start counting a new usage session; daily totals carry on.
+    pub fn start_usage_session(&self) {
+        self.meter
+            .lock()
+            .unwrap()
+            .start_session(Instant::now(), now_iso());
+    }
+
+    /// Usage and cost for this session, today and each day on record.
+    pub fn usage_report(&self) -> UsageReport {
+        self.meter.lock().unwrap().report(Instant::now(), &today())
+    }
+
+    /// Change the spending limits; they apply from the next frame or chunk.
+    pub fn set_limits(&self, limits: LimitSettings) {
+        self.limiter.lock().unwrap().set_settings(limits);
+    }
+
+    pub fn limits(&self) -> LimitSettings {
+        self.limiter.lock().unwrap().settings().clone()
+    }
+
+    /// The spending limit currently reached, if any. Reports `ai:limit`
+    /// when a limit is reached or lifts.
+    pub fn limit_reached(&self) -> Option<LimitHit> {
+        let cost_today = self.meter.lock().unwrap().day(&today()).cost;
+        let hit = self
+            .limiter
+            .lock()
+            .unwrap()
+            .check(Instant::now(), cost_today, day_left());
+        let mut last = self.limit_hit.lock().unwrap();
+        if last.as_ref().map(|h| h.kind) != hit.as_ref().map(|h| h.kind) {
+            let state = match &hit {
+                Some(h) => {
+                    log::warn!("Spending {}; AI pipeline paused", h.describe());
+                    "reached"
+                }
+                None => {
+                    log::info!("Spending limit lifted; AI pipeline resumed");
+                    "cleared"
+                }
+            };
+            self.event_sink().send(
+                "ai:limit",
+                &LimitPayload {
+                    state: state.into(),
+                    limit: hit.clone(),
+                    override_secs: None,
+                    timestamp: now_iso(),
+                },
+            );
+        }
+        *last = hit.clone();
+        hit
+    }
+
+    /// Set the spending limits aside for `duration`, resuming frames (and a
+    /// suspended audio session, with the next chunk) right away.
+    pub fn override_limits(&self, duration: Duration) {
+        self.limiter
+            .lock()
+            .unwrap()
+            .override_for(Instant::now(), duration);
+        let set_aside = self.limit_hit.lock().unwrap().take();
+        log::info!("Spending limits overridden for {:?}", duration);
+        self.event_sink().send(
+            "ai:limit",
+            &LimitPayload {
+                state: "overridden".into(),
+                limit: set_aside,
+                override_secs: Some(duration.as_secs()),
+                timestamp: now_iso(),
+            },
+        );
+    }
+
+    pub fn limit_status(&self) -> LimitStatus {
+        let reached = self.limit_reached();
+        let limiter = self.limiter.lock().unwrap();
+        LimitStatus {
+            limits: limiter.settings().clone(),
+            reached,
+            override_secs: limiter.override_left(Instant::now()).map(|d| d.as_secs()),
+            audio_suspended: self.audio_suspended.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Change how repeated capture suggestions are detected.
+    pub fn set_novelty_settings(&self, settings: NoveltySettings) {
+        self.novelty.lock().unwrap().set_settings(settings);
+    }
+
+    pub fn novelty_settings(&self) -> NoveltySettings {
+        self.novelty.lock().unwrap().settings().clone()
+    }
+
+    /// Name of the monitor being captured, for `{{monitor_name}}`.
+    pub fn set_monitor_name(&self, name: Option<String>) {
+        *self.monitor_name.lock().unwrap() = name;
+    }
+
+    /// The user's own notes, for `{{user_notes}}`.
+    pub fn set_user_notes(&self, notes: &str) {
+        *self.user_notes.lock().unwrap() = notes.to_string();
+    }
+
+    /// How to find the frontmost window, for `{{active_window}}`. Only
+    /// called for prompts that use it.
+    pub fn set_window_probe(&self, probe: WindowProbe) {
+        *self.window_probe.lock().unwrap() = Some(probe);
+    }
+
+    /// What was recently heard on the audio stream, oldest first.
+    pub fn recent_transcript(&self) -> Vec<String> {
+        self.transcript.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Capture the current values of `template`'s variables. The active
+    /// window is looked up when the prompt is rendered, off the runtime.
+    fn pending_prompt(&self, template: String) -> PendingPrompt {
+        let window_probe = if prompt_template::uses(&template, "active_window") {
+            self.window_probe.lock().unwrap().clone()
+        } else {
+            None
+        };
+        let context = PromptContext {
+            active_window: None,
+            time: now_iso(),
+            monitor_name: self.monitor_name.lock().unwrap().clone(),
+            recent_transcript: self.recent_transcript().join("\n"),
+            last_suggestion: self.last_suggestion(),
+            user_notes: self.user_notes.lock().unwrap().clone(),
+        };
+        PendingPrompt {
+            template,
+            context,
+            window_probe,
         }
     }
 
+    /// Id of the thread new frames are chained into.
+    pub fn thread_id(&self) -> u64 {
+        self.thread.lock().unwrap().id
+    }
+
+    /// Start an empty thread, so the next frame is sent without earlier
+    /// context. Requests still in flight finish in their old thread.
+    /// Returns the new thread's id.
+    pub fn reset_thread(&self, reason: &str) -> u64 {
+        self.start_thread(None, reason, None)
+    }
+
+    /// Start a thread that continues from an earlier screen suggestion, so
+    /// the next frames build on that answer instead of the latest one.
+    /// Returns the new thread's id.
+    pub fn fork_thread(&self, suggestion_id: u64) -> Result<u64, BemeError> {
+        let response = self.tracked_response(suggestion_id)?;
+        Ok(self.start_thread(Some(response.response_id), "fork", Some(suggestion_id)))
+    }
+
+    fn start_thread(&self, head: Option<String>, reason: &str, forked_from: Option<u64>) -> u64 {
+        let thread_id = {
+            let mut thread = self.thread.lock().unwrap();
+            thread.id += 1;
+            thread.head = head;
+            thread.id
+        };
+        // A new thread may well repeat earlier advice; let it.
+        self.novelty.lock().unwrap().clear();
+        log::info!("Thread {} started ({})", thread_id, reason);
+        self.event_sink().send(
+            "ai:thread",
+            &ThreadPayload {
+                thread_id,
+                reason: reason.into(),
+                forked_from,
+            },
+        );
+        thread_id
+    }
+
+    /// The response behind a recent screen suggestion.
+    fn tracked_response(&self, suggestion_id: u64) -> Result<TrackedResponse, BemeError> {
+        self.response_ids
+            .lock()
+            .unwrap()
+            .get(&suggestion_id)
+            .cloned()
+            .ok_or_else(|| {
+                BemeError::InvalidInput(format!(
+                    "suggestion {} has no response to continue",
+                    suggestion_id
+                ))
+            })
+    }
+
     /// Configure the AI provider with Azure OpenAI credentials.
     pub fn configure_azure(
         &self,
@@ -83,6 +621,23 @@ impl StreamManager {
             "StreamManager: Azure vision provider configured (bearer={})",
             use_bearer
         );
+        self.reset_thread("provider");
+    }
+
+    /// Use `provider` for both vision and audio, e.g. a
+    /// [`ScriptedProvider`](crate::ai::scripted::ScriptedProvider).
+    pub fn configure_provider(
+        &self,
+        provider: Arc<dyn AiProvider>,
+        vision_prompt: &str,
+        audio_prompt: &str,
+    ) {
+        log::info!("StreamManager: {} provider configured", provider.name());
+        *self.provider.lock().unwrap() = Some(Arc::clone(&provider));
+        *self.audio_provider.lock().unwrap() = Some(provider);
+        *self.system_prompt.lock().unwrap() = vision_prompt.to_string();
+        *self.audio_prompt.lock().unwrap() = audio_prompt.to_string();
+        self.reset_thread("provider");
     }
 
     /// Check if a provider is configured.
@@ -110,37 +665,41 @@ impl StreamManager {
     }
 
     /// Start the audio AI WebSocket session and spawn a reader task.
-    pub async fn start_audio_session(&self, app_handle: AppHandle) -> Result<(), String> {
+    pub async fn start_audio_session(&self) -> Result<(), BemeError> {
         log::info!("Starting audio AI session...");
         let provider = {
             let p = self.audio_provider.lock().unwrap();
             match p.as_ref() {
                 Some(p) => Arc::clone(p),
-                None => return Err("Audio provider not configured".into()),
+                None => return Err(BemeError::NotConfigured("Audio provider".into())),
             }
         };
-        let prompt = self.audio_prompt.lock().unwrap().clone();
+        let template = self.audio_prompt.lock().unwrap().clone();
+        let prompt = self.pending_prompt(template).render().await;
+        let generation = self.generation_config();
+        let events = self.event_sink();
 
-        emit_audio_status(&app_handle, "connecting", None);
+        emit_audio_status(&*events, "connecting", None);
 
-        let (session, audio_rx) = provider
-            .start_audio_stream(&prompt)
-            .await
-            .map_err(|e| format!("Failed to start audio session: {}", e))?;
+        let (session, audio_rx) = provider.start_audio_stream(&prompt, &generation).await?;
 
         {
             let mut sess = self.audio_session.lock().await;
             *sess = Some(session);
         }
 
-        emit_audio_status(&app_handle, "connected", None);
+        emit_audio_status(&*events, "connected", None);
 
         // Spawn reader task — owns audio_rx directly, no mutex needed
         let next_id = Arc::clone(&self.next_id);
-        let reader_app_handle = app_handle.clone();
+        let last_suggestion = Arc::clone(&self.last_suggestion);
+        let transcript = Arc::clone(&self.transcript);
+        let meter = Arc::clone(&self.meter);
+        let limiter = Arc::clone(&self.limiter);
 
         tokio::spawn(async move {
             let mut audio_rx = audio_rx;
+            let mut turn_text = String::new();
             // Allocate one suggestion ID per response turn
             let mut suggestion_id = {
                 let mut id = next_id.lock().unwrap();
@@ -151,17 +710,22 @@ impl StreamManager {
             let mut is_first_response = true;
             loop {
                 match audio_rx.recv().await {
-                    Some(Ok(text)) if text.is_empty() => {
-                        // Empty string = turn done signal
+                    Some(Ok(AudioResponse::Done)) => {
+                        limiter.lock().unwrap().record_request(Instant::now());
+                        if !turn_text.is_empty() {
+                            *last_suggestion.lock().unwrap() = Some(std::mem::take(&mut turn_text));
+                        }
                         let payload = SuggestionPayload {
                             text: String::new(),
                             timestamp: now_iso(),
                             done: true,
                             id: suggestion_id,
                             source: "audio".into(),
+                            question: None,
+                            parent_id: None,
+                            thread_id: None,
                         };
-                        log_event_for_testing("ai:suggestion", &payload);
-                        let _ = reader_app_handle.emit("ai:suggestion", payload);
+                        events.send("ai:suggestion", &payload);
                         // Allocate a new ID for the next turn
                         suggestion_id = {
                             let mut id = next_id.lock().unwrap();
@@ -170,37 +734,53 @@ impl StreamManager {
                             current
                         };
                     }
-                    Some(Ok(text)) => {
+                    Some(Ok(AudioResponse::Usage(usage))) => {
+                        record_usage(
+                            &meter,
+                            &limiter,
+                            &*events,
+                            UsageSource::Audio,
+                            Some(suggestion_id),
+                            &usage,
+                        );
+                    }
+                    Some(Ok(AudioResponse::Transcript(text))) => {
+                        log::debug!("Audio AI: heard {} chars", text.len());
+                        let mut lines = transcript.lock().unwrap();
+                        lines.push_back(text);
+                        while lines.len() > MAX_TRANSCRIPT_LINES {
+                            lines.pop_front();
+                        }
+                    }
+                    Some(Ok(AudioResponse::Delta(text))) => {
                         if is_first_response {
                             log::info!("Audio AI: first response delta received");
                             is_first_response = false;
                         }
+                        turn_text.push_str(&text);
                         let payload = SuggestionPayload {
                             text,
                             timestamp: now_iso(),
                             done: false,
                             id: suggestion_id,
                             source: "audio".into(),
+                            question: None,
+                            parent_id: None,
+                            thread_id: None,
                         };
-                        log_event_for_testing("ai:suggestion", &payload);
-                        let _ = reader_app_handle.emit("ai:suggestion", payload);
+                        events.send("ai:suggestion", &payload);
                     }
                     Some(Err(e)) => {
                         log::error!("Audio AI error: {}", e);
-                        let _ = reader_app_handle.emit(
-                            "ai:error",
-                            AiErrorPayload {
-                                message: e.to_string(),
-                                timestamp: now_iso(),
-                            },
-                        );
-                        emit_audio_status(&reader_app_handle, "error", Some(e.to_string()));
+                        let message = e.to_string();
+                        emit_ai_error(&*events, e.into(), "audio", Some(suggestion_id));
+                        emit_audio_status(&*events, "error", Some(message));
                         break;
                     }
                     None => break,
                 }
             }
-            emit_audio_status(&reader_app_handle, "disconnected", None);
+            emit_audio_status(&*events, "disconnected", None);
             log::info!("Audio AI reader task ended");
         });
 
@@ -209,11 +789,39 @@ impl StreamManager {
     }
 
     /// Send a chunk of audio PCM data to the active AI session.
-    pub async fn process_audio_chunk(&self, audio_data: &[u8]) -> Result<(), String> {
+    /// Chunks are dropped (not an error) while the AI is paused. Once a
+    /// spending limit is reached the session is closed, and reopened with
+    /// the first chunk after the limit lifts.
+    pub async fn process_audio_chunk(&self, audio_data: &[u8]) -> Result<(), BemeError> {
+        if let Some(hit) = self.limit_reached() {
+            self.suspend_audio_session(&hit).await;
+            return Ok(());
+        }
+        if self.audio_suspended.swap(false, Ordering::SeqCst) {
+            log::info!("Reopening the audio session suspended for a spending limit");
+            if let Err(e) = self.start_audio_session().await {
+                // Try again with the next chunk
+                self.audio_suspended.store(true, Ordering::SeqCst);
+                return Err(e);
+            }
+        }
         let mut sess = self.audio_session.lock().await;
         match sess.as_mut() {
-            Some(s) => s.send_audio(audio_data).await.map_err(|e| e.to_string()),
-            None => Err("No active audio session".into()),
+            Some(_) if self.is_paused() => Ok(()),
+            Some(s) => Ok(s.send_audio(audio_data).await?),
+            None => Err(BemeError::NoAudioSession),
+        }
+    }
+
+    /// Close the audio session for a spending limit, remembering to reopen it.
+    async fn suspend_audio_session(&self, hit: &LimitHit) {
+        let Some(mut session) = self.audio_session.lock().await.take() else {
+            return;
+        };
+        log::warn!("Audio session suspended: {}", hit.describe());
+        self.audio_suspended.store(true, Ordering::SeqCst);
+        if let Err(e) = session.close().await {
+            log::warn!("Closing the audio session: {}", e);
         }
     }
 
@@ -222,7 +830,7 @@ impl StreamManager {
         self.audio_session.lock().await.is_some()
     }
 
-    /// Inject a pre-built audio session (for testing without AppHandle).
+    /// Inject a pre-built audio session (for tests that build their own).
     pub async fn inject_audio_session(&self, session: Box<dyn AudioSession>) {
         *self.audio_session.lock().await = Some(session);
     }
@@ -232,19 +840,23 @@ impl StreamManager {
         *self.audio_session.lock().await = None;
     }
 
-    /// Get both prompts.
+    /// Get both prompts, as written (variables not filled in).
     pub fn get_prompts(&self) -> (String, String) {
         let vision = self.system_prompt.lock().unwrap().clone();
         let audio = self.audio_prompt.lock().unwrap().clone();
         (vision, audio)
     }
 
-    /// Update a prompt. Source is "vision" or "audio".
+    /// Update a prompt. Source is "vision" or "audio". A changed vision
+    /// prompt starts a new thread, since earlier answers followed the old one.
     pub fn update_prompt(&self, source: &str, text: &str) {
         match source {
             "vision" => {
-                *self.system_prompt.lock().unwrap() = text.to_string();
+                let old = std::mem::replace(&mut *self.system_prompt.lock().unwrap(), text.into());
                 log::info!("Vision prompt updated ({} chars)", text.len());
+                if old != text {
+                    self.reset_thread("prompt");
+                }
             }
             "audio" => {
                 *self.audio_prompt.lock().unwrap() = text.to_string();
@@ -255,29 +867,165 @@ impl StreamManager {
     }
 
     /// Close the audio AI WebSocket session.
-    pub async fn stop_audio_session(&self, app_handle: &AppHandle) -> Result<(), String> {
+    pub async fn stop_audio_session(&self) -> Result<(), BemeError> {
+        self.audio_suspended.store(false, Ordering::SeqCst);
         let mut sess = self.audio_session.lock().await;
         if let Some(ref mut s) = *sess {
-            s.close().await.map_err(|e| e.to_string())?;
+            s.close().await?;
         }
         *sess = None;
-        emit_audio_status(app_handle, "disconnected", None);
+        emit_audio_status(&*self.event_sink(), "disconnected", None);
         log::info!("Audio AI session stopped");
         Ok(())
     }
 
     /// Analyze a frame and emit streaming suggestions.
-    /// Called from the capture loop when a new frame is available.
-    pub fn analyze_frame(&self, frame_data: String, app_handle: AppHandle) {
-        let provider = {
-            let p = self.provider.lock().unwrap();
-            match p.as_ref() {
-                Some(p) => Arc::clone(p),
-                None => return,
-            }
+    /// Called from the capture loop when a new frame is available; ignored
+    /// while the AI is paused or a spending limit is reached. A frame
+    /// encoded from `plan_frame`'s `plan` is sent at its detail level and
+    /// counted against the token budget.
+    pub fn analyze_frame(&self, frame_data: String, plan: Option<FramePlan>) {
+        if self.is_paused() {
+            log::debug!("AI paused, frame not analyzed");
+            return;
+        }
+        if self.limit_reached().is_some() {
+            log::debug!("Spending limit reached, frame not analyzed");
+            return;
+        }
+        let provider = self.provider.lock().unwrap().clone();
+        if let Some(provider) = provider {
+            self.spawn_analysis(provider, frame_data, None, true, plan);
+        }
+    }
+
+    /// Analyze a frame the user explicitly asked about, optionally with
+    /// their own question. Runs even while the AI is paused or a spending
+    /// limit is reached (and counts towards it). Returns the id the answer
+    /// streams under.
+    pub fn analyze_now(
+        &self,
+        frame_data: String,
+        question: Option<String>,
+        plan: Option<FramePlan>,
+    ) -> Result<u64, BemeError> {
+        let provider = self
+            .provider
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| BemeError::NotConfigured("AI provider".into()))?;
+        let question = question
+            .map(|q| q.trim().to_string())
+            .filter(|q| !q.is_empty());
+        Ok(self.spawn_analysis(provider, frame_data, question, false, plan))
+    }
+
+    /// Stream `provider`'s answer for a frame as `screen` suggestions,
+    /// continuing the current thread.
+    fn spawn_analysis(
+        &self,
+        provider: Arc<dyn AiProvider>,
+        frame_data: String,
+        question: Option<String>,
+        filter_duplicates: bool,
+        plan: Option<FramePlan>,
+    ) -> u64 {
+        if let Some(plan) = plan {
+            self.budget
+                .lock()
+                .unwrap()
+                .record(Instant::now(), plan.image_tokens);
+        }
+        let detail = plan.map(|p| p.detail);
+        let template = self.system_prompt.lock().unwrap().clone();
+        let prompt = self.pending_prompt(template);
+        let format = self.suggestion_format();
+        let generation = self.generation_config();
+        let (thread_id, head) = {
+            let thread = self.thread.lock().unwrap();
+            (thread.id, thread.head.clone())
+        };
+        let origin = Origin {
+            question: question.clone(),
+            parent_id: None,
+            thread_id,
+            previous_response_id: head.clone(),
+            advances_thread: true,
+            format,
+            filter_duplicates,
+            image_tokens: plan.map(|p| p.image_tokens),
         };
+        self.spawn_suggestion(origin, async move {
+            let system_prompt = prompt.render().await;
+            let request = VisionRequest::new(&frame_data, &system_prompt)
+                .with_question(question.as_deref())
+                .continuing(head.as_deref())
+                .with_format(format)
+                .with_generation(&generation)
+                .with_detail(detail);
+            provider.analyze_frame(request).await
+        })
+    }
+
+    /// Ask a follow-up about an earlier screen suggestion ("why?", "how
+    /// exactly?"). Continues that suggestion's own response — not whatever
+    /// the capture loop sent last — and streams the reply as a new
+    /// suggestion linked to it by `parent_id`, in that suggestion's thread.
+    /// Returns the new id.
+    pub fn ask_followup(&self, suggestion_id: u64, text: String) -> Result<u64, BemeError> {
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return Err(BemeError::InvalidInput(
+                "follow-up question is empty".into(),
+            ));
+        }
+        let provider = self
+            .provider
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| BemeError::NotConfigured("AI provider".into()))?;
+        let parent = self.tracked_response(suggestion_id)?;
+        let template = self.system_prompt.lock().unwrap().clone();
+        let prompt = self.pending_prompt(template);
+        let generation = self.generation_config();
+        let origin = Origin {
+            question: Some(text.clone()),
+            parent_id: Some(suggestion_id),
+            thread_id: parent.thread_id,
+            previous_response_id: Some(parent.response_id.clone()),
+            advances_thread: false,
+            format: SuggestionFormat::Text,
+            filter_duplicates: false,
+            image_tokens: None,
+        };
+        Ok(self.spawn_suggestion(origin, async move {
+            let system_prompt = prompt.render().await;
+            provider
+                .follow_up(&parent.response_id, &text, &system_prompt, &generation)
+                .await
+        }))
+    }
 
-        let system_prompt = self.system_prompt.lock().unwrap().clone();
+    /// Allocate a suggestion id and stream `request`'s reply under it as
+    /// `screen` suggestions, remembering its response id for follow-ups and
+    /// forks. A completed frame reply becomes its thread's head unless the
+    /// thread moved on while it was in flight, so overlapping requests can't
+    /// chain onto each other out of order. Completed capture suggestions
+    /// that repeat recent ones are reported on `suggestion:suppressed`.
+    fn spawn_suggestion<F>(&self, origin: Origin, request: F) -> u64
+    where
+        F: Future<Output = Result<Box<dyn TextStream>, AiError>> + Send + 'static,
+    {
+        let events = self.event_sink();
+        let last_suggestion = Arc::clone(&self.last_suggestion);
+        let response_ids = Arc::clone(&self.response_ids);
+        let thread = Arc::clone(&self.thread);
+        let novelty = Arc::clone(&self.novelty);
+        let meter = Arc::clone(&self.meter);
+        let limiter = Arc::clone(&self.limiter);
+        limiter.lock().unwrap().record_request(Instant::now());
 
         let suggestion_id = {
             let mut id = self.next_id.lock().unwrap();
@@ -286,93 +1034,245 @@ impl StreamManager {
             current
         };
 
-        tokio::spawn(async move {
-            match provider.analyze_frame(&frame_data, &system_prompt).await {
+        // Not `tokio::spawn`: sync Tauri commands (`ask_followup`) call this
+        // from the main thread, outside the runtime.
+        tauri::async_runtime::spawn(async move {
+            let payload = |text: String, done: bool| SuggestionPayload {
+                text,
+                timestamp: now_iso(),
+                done,
+                id: suggestion_id,
+                source: "screen".into(),
+                question: origin.question.clone(),
+                parent_id: origin.parent_id,
+                thread_id: Some(origin.thread_id),
+            };
+            match request.await {
                 Ok(mut stream) => {
+                    let structured = origin.format == SuggestionFormat::Structured;
+                    let mut full_text = String::new();
+                    let mut failed = false;
                     while let Some(chunk_result) = stream.next_chunk().await {
                         match chunk_result {
                             Ok(chunk) => {
-                                let payload = SuggestionPayload {
-                                    text: chunk,
-                                    timestamp: now_iso(),
-                                    done: false,
-                                    id: suggestion_id,
-                                    source: "screen".into(),
-                                };
-                                log_event_for_testing("ai:suggestion", &payload);
-                                let _ = app_handle.emit("ai:suggestion", payload);
+                                full_text.push_str(&chunk);
+                                // Partial JSON isn't worth showing; structured
+                                // answers are reported once complete.
+                                if !structured {
+                                    events.send("ai:suggestion", &payload(chunk, false));
+                                }
                             }
                             Err(e) => {
                                 log::error!("AI stream error: {}", e);
-                                let _ = app_handle.emit(
-                                    "ai:error",
-                                    AiErrorPayload {
-                                        message: e.to_string(),
-                                        timestamp: now_iso(),
-                                    },
-                                );
+                                emit_ai_error(&*events, e.into(), "screen", Some(suggestion_id));
+                                failed = true;
                                 break;
                             }
                         }
                     }
+                    if let Some(mut usage) = stream.usage() {
+                        if usage.image_tokens == 0 {
+                            usage.image_tokens = origin.image_tokens.unwrap_or(0) as u64;
+                        }
+                        record_usage(
+                            &meter,
+                            &limiter,
+                            &*events,
+                            UsageSource::Screen,
+                            Some(suggestion_id),
+                            &usage,
+                        );
+                    }
 
-                    // Final done event
-                    let payload = SuggestionPayload {
-                        text: String::new(),
-                        timestamp: now_iso(),
-                        done: true,
-                        id: suggestion_id,
-                        source: "screen".into(),
+                    let complete = !failed && !full_text.is_empty();
+                    let mut parsed = None;
+                    if structured && complete {
+                        match StructuredSuggestion::parse(&full_text) {
+                            Ok(suggestion) => {
+                                full_text = suggestion.action.clone();
+                                parsed = Some(suggestion);
+                            }
+                            // Still show what the model said
+                            Err(e) => {
+                                log::warn!("AI structured suggestion: {}", e);
+                                emit_ai_error(&*events, e.into(), "screen", Some(suggestion_id));
+                            }
+                        }
+                    }
+
+                    let verdict = if origin.filter_duplicates && complete {
+                        novelty.lock().unwrap().check(suggestion_id, &full_text)
+                    } else {
+                        Verdict::Novel
                     };
-                    log_event_for_testing("ai:suggestion", &payload);
-                    let _ = app_handle.emit("ai:suggestion", payload);
+                    let suppressed = match verdict {
+                        Verdict::Duplicate { of, similarity } => {
+                            log::debug!("Suggestion {} repeats {}", suggestion_id, of);
+                            events.send(
+                                "suggestion:suppressed",
+                                &SuggestionSuppressedPayload {
+                                    id: suggestion_id,
+                                    duplicate_of: of,
+                                    similarity,
+                                    reason: if similarity >= 1.0 {
+                                        "identical"
+                                    } else {
+                                        "similar"
+                                    }
+                                    .into(),
+                                    timestamp: now_iso(),
+                                },
+                            );
+                            true
+                        }
+                        Verdict::Novel => false,
+                    };
+
+                    if structured && complete && !suppressed {
+                        if let Some(suggestion) = parsed {
+                            events.send(
+                                "ai:structured-suggestion",
+                                &StructuredSuggestionPayload {
+                                    id: suggestion_id,
+                                    timestamp: now_iso(),
+                                    suggestion,
+                                    question: origin.question.clone(),
+                                    thread_id: origin.thread_id,
+                                },
+                            );
+                        }
+                        events.send("ai:suggestion", &payload(full_text.clone(), false));
+                    }
+
+                    if let Some(response_id) = stream.response_id() {
+                        if origin.advances_thread && !failed {
+                            let mut thread = thread.lock().unwrap();
+                            if thread.id == origin.thread_id
+                                && thread.head == origin.previous_response_id
+                            {
+                                thread.head = Some(response_id.clone());
+                            }
+                        }
+                        let tracked = TrackedResponse {
+                            thread_id: origin.thread_id,
+                            response_id,
+                        };
+                        remember_response(&response_ids, suggestion_id, tracked);
+                    }
+                    if !full_text.is_empty() && !suppressed {
+                        *last_suggestion.lock().unwrap() = Some(full_text);
+                    }
+                    // Final done event; a withheld structured suggestion was
+                    // never shown, so there's nothing to finish.
+                    if !(structured && suppressed) {
+                        events.send("ai:suggestion", &payload(String::new(), true));
+                    }
                 }
                 Err(e) => {
-                    log::error!("AI analyze_frame error: {}", e);
-                    let _ = app_handle.emit(
-                        "ai:error",
-                        AiErrorPayload {
-                            message: e.to_string(),
-                            timestamp: now_iso(),
-                        },
-                    );
+                    log::error!("AI request error: {}", e);
+                    emit_ai_error(&*events, e.into(), "screen", Some(suggestion_id));
                 }
             }
         });
+        suggestion_id
     }
 }
 
-/// When `BEME_TEST_LOG` is set, append the event as a JSONL line to the specified file.
-/// No-op when the env var is absent — zero overhead in production.
-fn log_event_for_testing(event_name: &str, payload: &SuggestionPayload) {
-    if let Ok(path) = std::env::var("BEME_TEST_LOG") {
-        use std::io::Write;
-        if let Ok(mut file) = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&path)
-        {
-            let json = serde_json::to_string(payload).unwrap_or_default();
-            let _ = writeln!(
-                file,
-                r#"{{"event":"{}","timestamp":"{}","payload":{}}}"#,
-                event_name, payload.timestamp, json
-            );
+/// Record which provider response a suggestion came from, forgetting the
+/// oldest once more than `MAX_TRACKED_RESPONSES` are held.
+fn remember_response(
+    ids: &Mutex<HashMap<u64, TrackedResponse>>,
+    suggestion_id: u64,
+    response: TrackedResponse,
+) {
+    let mut ids = ids.lock().unwrap();
+    ids.insert(suggestion_id, response);
+    if ids.len() > MAX_TRACKED_RESPONSES {
+        if let Some(&oldest) = ids.keys().min() {
+            ids.remove(&oldest);
         }
     }
 }
 
-fn emit_audio_status(app_handle: &AppHandle, status: &str, message: Option<String>) {
-    let _ = app_handle.emit(
+fn emit_ai_error(
+    events: &dyn EventSink,
+    error: BemeError,
+    source: &str,
+    suggestion_id: Option<u64>,
+) {
+    events.send(
+        "ai:error",
+        &AiErrorPayload {
+            error,
+            source: source.into(),
+            suggestion_id,
+            timestamp: now_iso(),
+        },
+    );
+}
+
+/// Add a response's usage to the meter and the limits, and report it on
+/// `ai:usage`.
+fn record_usage(
+    meter: &Mutex<Meter>,
+    limiter: &Mutex<Limiter>,
+    events: &dyn EventSink,
+    source: UsageSource,
+    suggestion_id: Option<u64>,
+    usage: &TokenUsage,
+) {
+    let day = today();
+    limiter
+        .lock()
+        .unwrap()
+        .record_tokens(Instant::now(), usage.total_tokens());
+    let (payload, save) = {
+        let mut meter = meter.lock().unwrap();
+        let cost = meter.record(&day, source, usage);
+        let payload = UsagePayload {
+            source,
+            suggestion_id,
+            usage: *usage,
+            cost,
+            currency: meter.pricing().currency.clone(),
+            session: meter.session().clone(),
+            today: meter.day(&day),
+            timestamp: now_iso(),
+        };
+        (payload, meter.unsaved())
+    };
+    if let Some(save) = save {
+        tokio::task::spawn_blocking(move || save.write());
+    }
+    events.send("ai:usage", &payload);
+}
+
+fn emit_audio_status(events: &dyn EventSink, status: &str, message: Option<String>) {
+    events.send(
         "ai:audio-status",
-        AudioStatusPayload {
+        &AudioStatusPayload {
             status: status.to_string(),
             message,
         },
     );
 }
 
-fn now_iso() -> String {
+/// Today's UTC date, `YYYY-MM-DD`.
+fn today() -> String {
+    now_iso()[..10].to_string()
+}
+
+/// Time left until the next UTC midnight.
+fn day_left() -> Duration {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Duration::from_secs(86_400 - secs % 86_400)
+}
+
+pub(crate) fn now_iso() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let dur = SystemTime::now()
         .duration_since(UNIX_EPOCH)
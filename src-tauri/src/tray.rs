@@ -1,26 +1,49 @@
 // tray.rs — System tray icon + right-click menu for beme.
-// The global shortcut (Ctrl+Shift+B) is registered in lib.rs via the plugin builder.
-
-use std::sync::atomic::{AtomicBool, Ordering};
+// Global shortcuts live in shortcuts.rs.
+//
+// The tray and shortcuts start/stop capture through the shared
+// `CaptureController`, and the tray icon, label and tooltip follow its
+// status — whoever did the toggling, or a capture loop stopping by itself.
+
+use crate::controller::CaptureController;
+use std::sync::Arc;
 use tauri::{
+    image::Image,
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::TrayIconBuilder,
-    AppHandle, Emitter, Manager,
+    AppHandle, Manager,
 };
 
-/// Shared flag so the tray menu label can reflect capture state.
-static CAPTURING: AtomicBool = AtomicBool::new(false);
-
-/// Returns `true` if we are currently capturing.
-#[allow(dead_code)]
-pub fn is_capturing() -> bool {
-    CAPTURING.load(Ordering::SeqCst)
+/// Toggle capture through the controller without blocking the caller.
+pub(crate) fn toggle_capture(app: &AppHandle, source: &'static str) {
+    let controller = Arc::clone(&*app.state::<Arc<CaptureController>>());
+    tauri::async_runtime::spawn(async move {
+        let status = controller.toggle().await;
+        log::info!(
+            "{}: capture toggled → {}",
+            source,
+            if status.screen { "ON" } else { "OFF" }
+        );
+    });
 }
 
-/// Toggle the capture state and return the new value.
-pub fn toggle_capture() -> bool {
-    let prev = CAPTURING.fetch_xor(true, Ordering::SeqCst);
-    !prev // new state after XOR
+/// The app icon with a red dot in the bottom-right corner, shown while
+/// capturing.
+fn with_capture_dot(icon: &Image<'_>) -> Image<'static> {
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+    let radius = width.min(height) as f32 * 0.22;
+    let (cx, cy) = (width as f32 - radius, height as f32 - radius);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let i = ((y * width + x) * 4) as usize;
+                rgba[i..i + 4].copy_from_slice(&[220, 38, 38, 255]);
+            }
+        }
+    }
+    Image::new(&rgba, width, height).to_owned()
 }
 
 // ─── Tray setup ──────────────────────────────────────────────────────
@@ -52,35 +75,20 @@ pub fn setup_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         &[&toggle_item, &separator, &dashboard_item, &quit_item],
     )?;
 
-    // Build the tray icon (uses the default app icon).
-    TrayIconBuilder::new()
+    // Build the tray icon from the app icon; capturing adds a red dot.
+    let idle_icon = app.default_window_icon().cloned().map(Image::to_owned);
+    let capturing_icon = idle_icon.as_ref().map(with_capture_dot);
+    let mut builder = TrayIconBuilder::new();
+    if let Some(icon) = idle_icon.clone() {
+        builder = builder.icon(icon);
+    }
+    let tray = builder
+        .tooltip("beme — idle")
         .menu(&menu)
         .show_menu_on_left_click(false) // right-click opens menu
         .on_menu_event(move |app_handle, event| {
             match event.id().as_ref() {
-                "toggle_capture" => {
-                    let now_capturing = toggle_capture();
-                    log::info!(
-                        "Tray: capture toggled → {}",
-                        if now_capturing { "ON" } else { "OFF" }
-                    );
-
-                    // Update the menu item label to reflect new state.
-                    if let Some(item) = menu.get("toggle_capture") {
-                        if let Some(mi) = item.as_menuitem() {
-                            let label = if now_capturing {
-                                "Stop Capture"
-                            } else {
-                                "Start Capture"
-                            };
-                            let _ = mi.set_text(label);
-                        }
-                    }
-
-                    // Emit toggle event so frontend & capture modules can react.
-                    let _ =
-                        app_handle.emit("toggle:capture", serde_json::json!({ "source": "tray" }));
-                }
+                "toggle_capture" => toggle_capture(app_handle, "Tray"),
                 "open_dashboard" => {
                     log::info!("Tray: opening dashboard window");
                     // Show the dashboard window if it already exists, or ignore gracefully.
@@ -98,32 +106,24 @@ pub fn setup_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         })
         .build(app)?;
 
+    // Keep the icon, label and tooltip in step with the real capture state.
+    let mut status_rx = app.state::<Arc<CaptureController>>().subscribe();
+    tauri::async_runtime::spawn(async move {
+        while status_rx.changed().await.is_ok() {
+            let capturing = status_rx.borrow_and_update().screen;
+            let (label, tooltip, icon) = if capturing {
+                ("Stop Capture", "beme — capturing", &capturing_icon)
+            } else {
+                ("Start Capture", "beme — idle", &idle_icon)
+            };
+            let _ = toggle_item.set_text(label);
+            let _ = tray.set_tooltip(Some(tooltip));
+            if icon.is_some() {
+                let _ = tray.set_icon(icon.clone());
+            }
+        }
+    });
+
     log::info!("System tray created");
     Ok(())
 }
-
-// ─── Global shortcut handler ─────────────────────────────────────────
-
-/// Called by the global-shortcut plugin when *any* registered shortcut fires.
-/// We check for key-down and emit `toggle:capture`.
-pub fn on_shortcut_event(
-    app: &AppHandle,
-    _shortcut: &tauri_plugin_global_shortcut::Shortcut,
-    event: tauri_plugin_global_shortcut::ShortcutEvent,
-) {
-    // Only act on key-down (Pressed), not Released.
-    if event.state() != tauri_plugin_global_shortcut::ShortcutState::Pressed {
-        return;
-    }
-
-    let now_capturing = toggle_capture();
-    log::info!(
-        "Shortcut Ctrl+Shift+B: capture toggled → {}",
-        if now_capturing { "ON" } else { "OFF" }
-    );
-
-    let _ = app.emit(
-        "toggle:capture",
-        serde_json::json!({ "source": "shortcut" }),
-    );
-}
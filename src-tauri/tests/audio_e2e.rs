@@ -6,6 +6,9 @@
 //!   AZURE_OPENAI_AUDIO_DEPLOYMENT - e.g. "gpt-4o-realtime-preview"
 //!
 //! Run: cargo test --test audio_e2e -- --ignored
+//!
+//! `azure_stub_test.rs` covers the same client paths against a local stand-in
+//! server and runs by default.
 
 use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine as _;
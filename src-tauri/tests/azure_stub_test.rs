@@ -0,0 +1,382 @@
+//! End-to-end tests for the Azure clients against a local stand-in server.
+//! Real HTTP/SSE and WebSocket traffic, but no Azure resource needed.
+//!
+//! Run: cargo test --test azure_stub_test
+
+mod common;
+
+use beme_lib::ai::azure_audio::AzureAudioClient;
+use beme_lib::ai::azure_vision::AzureVisionClient;
+use beme_lib::ai::{
+    AiError, AiProvider, AudioResponse, GenerationConfig, TextStream, TokenUsage, VisionRequest,
+};
+use common::azure_stub::{
+    AudioReply, AzureStub, VisionReply, AUDIO_INPUT_TOKENS, VISION_INPUT_TOKENS,
+};
+use std::time::Duration;
+
+const KEY: &str = "stub-key";
+
+async fn collect(mut stream: Box<dyn TextStream>) -> Result<String, AiError> {
+    let mut text = String::new();
+    while let Some(chunk) = stream.next_chunk().await {
+        text.push_str(&chunk?);
+    }
+    Ok(text)
+}
+
+fn vision_client(stub: &AzureStub) -> AzureVisionClient {
+    AzureVisionClient::new(stub.endpoint(), KEY, "gpt-4o", "default prompt")
+}
+
+fn audio_client(stub: &AzureStub) -> AzureAudioClient {
+    AzureAudioClient {
+        endpoint: stub.endpoint(),
+        api_key: KEY.into(),
+        deployment: "gpt-4o-realtime-preview".into(),
+        system_prompt: "listen".into(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Responses API (vision)
+// ---------------------------------------------------------------------------
+
+/// Read a stream to the end, returning its text and response id.
+async fn collect_with_id(
+    mut stream: Box<dyn TextStream>,
+) -> Result<(String, Option<String>), AiError> {
+    let mut text = String::new();
+    while let Some(chunk) = stream.next_chunk().await {
+        text.push_str(&chunk?);
+    }
+    Ok((text, stream.response_id()))
+}
+
+/// Deltas stream through and each stream reports its response id. The
+/// client only chains when asked, and a user question replaces the default
+/// text sent with the frame.
+#[tokio::test]
+async fn vision_streams_and_continues_the_given_response() {
+    let stub = AzureStub::start(KEY).await;
+    stub.push_vision(VisionReply::Text(vec!["Click ", "Save", "."]));
+    let client = vision_client(&stub);
+
+    let first = VisionRequest::new("aGVsbG8=", "Be brief.");
+    let (text, id) = collect_with_id(client.analyze_frame(first).await.unwrap())
+        .await
+        .unwrap();
+    assert_eq!(text, "Click Save.");
+    assert_eq!(id.as_deref(), Some("resp_1"));
+
+    let second = first
+        .with_question(Some("Which button?"))
+        .continuing(id.as_deref());
+    let stream = client.analyze_frame(second).await.unwrap();
+    assert_eq!(collect(stream).await.unwrap(), "Hello world");
+
+    collect(client.analyze_frame(first).await.unwrap())
+        .await
+        .unwrap();
+
+    let requests = stub.vision_requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0]["instructions"], "Be brief.");
+    assert_eq!(requests[0]["stream"], true);
+    assert!(requests[0].get("previous_response_id").is_none());
+    assert_eq!(requests[1]["previous_response_id"], "resp_1");
+    assert!(requests[2].get("previous_response_id").is_none());
+    let question = |i: usize| requests[i]["input"][0]["content"][0]["text"].clone();
+    assert_eq!(question(0), "What do you see?");
+    assert_eq!(question(1), "Which button?");
+}
+
+/// Usage arrives with `response.completed`, after the text, and is
+/// available once the stream has ended.
+#[tokio::test]
+async fn vision_reports_usage_once_complete() {
+    let stub = AzureStub::start(KEY).await;
+    stub.push_vision(VisionReply::Text(vec!["Click ", "Save", "."]));
+
+    let mut stream = vision_client(&stub)
+        .analyze_frame(VisionRequest::new("aGVsbG8=", "p"))
+        .await
+        .unwrap();
+    assert_eq!(stream.usage(), None);
+    while let Some(chunk) = stream.next_chunk().await {
+        chunk.unwrap();
+    }
+    assert_eq!(
+        stream.usage(),
+        Some(TokenUsage {
+            input_tokens: VISION_INPUT_TOKENS,
+            output_tokens: 3,
+            ..Default::default()
+        })
+    );
+}
+
+/// A follow-up continues the given response as text only and reports its
+/// own id.
+#[tokio::test]
+async fn vision_follow_up_branches_off_a_response() {
+    let stub = AzureStub::start(KEY).await;
+    let client = vision_client(&stub);
+
+    collect(
+        client
+            .analyze_frame(VisionRequest::new("img", "p"))
+            .await
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+    let generation = GenerationConfig {
+        max_output_tokens: 64,
+        ..Default::default()
+    };
+    let stream = client
+        .follow_up("resp_1", "Why?", "p", &generation)
+        .await
+        .unwrap();
+    let (_, id) = collect_with_id(stream).await.unwrap();
+    assert_eq!(id.as_deref(), Some("resp_2"));
+
+    let requests = stub.vision_requests();
+    assert_eq!(requests[1]["previous_response_id"], "resp_1");
+    assert_eq!(requests[1]["input"][0]["content"][0]["text"], "Why?");
+    assert_eq!(requests[1]["max_output_tokens"], 64);
+    assert_eq!(
+        requests[1]["input"][0]["content"].as_array().unwrap().len(),
+        1
+    );
+}
+
+/// A stale `previous_response_id` is dropped and the request retried once;
+/// the new response starts a fresh chain.
+#[tokio::test]
+async fn vision_recovers_from_previous_response_not_found() {
+    let stub = AzureStub::start(KEY).await;
+    let client = vision_client(&stub);
+
+    collect(
+        client
+            .analyze_frame(VisionRequest::new("img", "p"))
+            .await
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+    stub.expire_responses();
+
+    let request = VisionRequest::new("img", "p").continuing(Some("resp_1"));
+    let (text, id) = collect_with_id(client.analyze_frame(request).await.unwrap())
+        .await
+        .unwrap();
+    assert_eq!(text, "Hello world");
+    assert_eq!(id.as_deref(), Some("resp_2"));
+
+    let requests = stub.vision_requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[1]["previous_response_id"], "resp_1");
+    assert!(requests[2].get("previous_response_id").is_none());
+}
+
+/// 429 maps to `RateLimited`, honouring the server's `Retry-After`.
+#[tokio::test]
+async fn vision_rate_limited() {
+    let stub = AzureStub::start(KEY).await;
+    stub.push_vision(VisionReply::Status {
+        code: 429,
+        body: r#"{"error":{"code":"429","message":"Too many requests"}}"#.into(),
+        retry_after_secs: Some(7),
+    });
+
+    let err = vision_client(&stub)
+        .analyze_frame(VisionRequest::new("img", "p"))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        AiError::RateLimited {
+            retry_after_ms: 7000
+        }
+    ));
+}
+
+/// Other HTTP failures keep their status and Azure's error code.
+#[tokio::test]
+async fn vision_server_error_keeps_status_and_code() {
+    let stub = AzureStub::start(KEY).await;
+    stub.push_vision(VisionReply::Status {
+        code: 500,
+        body: r#"{"error":{"code":"server_error","message":"The server had an error"}}"#.into(),
+        retry_after_secs: None,
+    });
+
+    let err = vision_client(&stub)
+        .analyze_frame(VisionRequest::new("img", "p"))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.status(), Some(500));
+    assert_eq!(err.provider_code(), Some("server_error"));
+    assert_eq!(
+        err.to_string(),
+        "API error [server_error]: The server had an error"
+    );
+}
+
+/// A wrong key is reported as an auth failure, not a connection error.
+#[tokio::test]
+async fn vision_rejects_bad_key() {
+    let stub = AzureStub::start(KEY).await;
+    let client = AzureVisionClient::new(stub.endpoint(), "wrong", "gpt-4o", "p");
+
+    let err = client
+        .analyze_frame(VisionRequest::new("img", "p"))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, AiError::AuthError(_)));
+}
+
+/// Bearer auth is accepted as an alternative to `api-key`.
+#[tokio::test]
+async fn vision_bearer_auth() {
+    let stub = AzureStub::start(KEY).await;
+    let client = vision_client(&stub).with_bearer();
+
+    let stream = client
+        .analyze_frame(VisionRequest::new("img", "p"))
+        .await
+        .unwrap();
+    assert_eq!(collect(stream).await.unwrap(), "Hello world");
+}
+
+// ---------------------------------------------------------------------------
+// Realtime API (audio)
+// ---------------------------------------------------------------------------
+
+/// Session config goes out first; after the commit interval the client
+/// commits, requests a response, and receives the transcript of what it
+/// sent, the deltas, its usage and turn-done.
+#[tokio::test]
+async fn audio_session_round_trip() {
+    let stub = AzureStub::start(KEY).await;
+    stub.push_audio(AudioReply::Text(vec!["Ask about ", "pricing."]));
+
+    let (mut session, mut rx) = audio_client(&stub)
+        .start_audio_stream("Suggest a reply.", &GenerationConfig::default())
+        .await
+        .unwrap();
+    for _ in 0..60 {
+        session.send_audio(&[0u8; 480]).await.unwrap();
+    }
+
+    let mut texts = Vec::new();
+    let mut transcripts = Vec::new();
+    let mut usage = None;
+    loop {
+        let response = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("timed out waiting for audio response")
+            .unwrap()
+            .unwrap();
+        match response {
+            AudioResponse::Delta(text) => texts.push(text),
+            AudioResponse::Transcript(text) => transcripts.push(text),
+            AudioResponse::Usage(u) => usage = Some(u),
+            AudioResponse::Done => break,
+        }
+    }
+    assert_eq!(texts.concat(), "Ask about pricing.");
+    assert_eq!(transcripts, ["What does it cost?"]);
+    assert_eq!(
+        usage,
+        Some(TokenUsage {
+            input_tokens: AUDIO_INPUT_TOKENS,
+            audio_input_tokens: 200,
+            output_tokens: 2,
+            ..Default::default()
+        })
+    );
+    session.close().await.unwrap();
+
+    let events = stub.audio_events();
+    let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+    assert_eq!(types[0], "session.update");
+    assert_eq!(events[0]["session"]["instructions"], "Suggest a reply.");
+    assert_eq!(
+        types
+            .iter()
+            .filter(|t| **t == "input_audio_buffer.append")
+            .count(),
+        60
+    );
+    assert_eq!(
+        &types[types.len() - 2..],
+        ["input_audio_buffer.commit", "response.create"]
+    );
+}
+
+/// An `error` frame from the server surfaces as an `ApiError` with its code.
+#[tokio::test]
+async fn audio_error_frame() {
+    let stub = AzureStub::start(KEY).await;
+    stub.push_audio(AudioReply::Error {
+        code: "rate_limit_exceeded",
+        message: "Rate limit reached",
+    });
+
+    let (mut session, mut rx) = audio_client(&stub)
+        .start_audio_stream("p", &GenerationConfig::default())
+        .await
+        .unwrap();
+    for _ in 0..60 {
+        session.send_audio(&[0u8; 480]).await.unwrap();
+    }
+
+    // The transcript of the committed audio comes first
+    let err = loop {
+        match tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap()
+        {
+            Ok(AudioResponse::Transcript(_)) => continue,
+            other => break other.unwrap_err(),
+        }
+    };
+    assert_eq!(err.provider_code(), Some("rate_limit_exceeded"));
+    assert!(err.to_string().contains("Rate limit reached"));
+}
+
+/// Handshake rejections map to auth and rate-limit errors.
+#[tokio::test]
+async fn audio_handshake_rejections() {
+    let stub = AzureStub::start(KEY).await;
+
+    let mut bad_key = audio_client(&stub);
+    bad_key.api_key = "wrong".into();
+    let err = bad_key
+        .start_audio_stream("p", &GenerationConfig::default())
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, AiError::AuthError(_)));
+
+    stub.reject_next_ws(429);
+    let err = audio_client(&stub)
+        .start_audio_stream("p", &GenerationConfig::default())
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        AiError::RateLimited {
+            retry_after_ms: 3000
+        }
+    ));
+}
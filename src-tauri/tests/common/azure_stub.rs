@@ -0,0 +1,468 @@
+//! Local stand-in for the Azure OpenAI endpoints used by beme.
+//!
+//! Speaks just enough of both protocols to drive the real clients over real
+//! sockets:
+//!   POST /openai/v1/responses  — Responses API, streamed as SSE
+//!   GET  /openai/realtime      — Realtime API over WebSocket
+//!
+//! Replies are scripted per test with [`AzureStub::push_vision`] and
+//! [`AzureStub::push_audio`]; everything the clients send is recorded so
+//! tests can assert on request bodies and WebSocket events. Completed
+//! responses report usage: [`VISION_INPUT_TOKENS`] / [`AUDIO_INPUT_TOKENS`]
+//! in, and one output token per delta.
+
+#![allow(dead_code)]
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Input tokens every Responses API reply reports.
+pub const VISION_INPUT_TOKENS: u64 = 1000;
+/// Input tokens every Realtime reply reports, of which 200 are audio.
+pub const AUDIO_INPUT_TOKENS: u64 = 250;
+
+/// One scripted reply to a Responses API request.
+#[derive(Debug, Clone)]
+pub enum VisionReply {
+    /// Stream these deltas as `response.output_text.delta` events.
+    Text(Vec<&'static str>),
+    /// Fail with an HTTP status, body and optional `Retry-After` (seconds).
+    Status {
+        code: u16,
+        body: String,
+        retry_after_secs: Option<u64>,
+    },
+}
+
+/// One scripted reply to a Realtime `response.create`.
+#[derive(Debug, Clone)]
+pub enum AudioReply {
+    /// Stream these deltas as `response.text.delta` events.
+    Text(Vec<&'static str>),
+    /// Send an `error` event with this code and message.
+    Error {
+        code: &'static str,
+        message: &'static str,
+    },
+}
+
+#[derive(Default)]
+struct StubState {
+    api_key: String,
+    vision_replies: VecDeque<VisionReply>,
+    audio_replies: VecDeque<AudioReply>,
+    /// Response ids the stub has issued and still "remembers".
+    known_responses: HashSet<String>,
+    next_response: u64,
+    vision_requests: Vec<Value>,
+    audio_events: Vec<Value>,
+    /// Reject the next WebSocket upgrade with this status.
+    reject_ws: Option<u16>,
+}
+
+/// A running stand-in server. Dropped servers keep listening until the
+/// test's runtime shuts down, which is fine for tests.
+#[derive(Clone)]
+pub struct AzureStub {
+    addr: SocketAddr,
+    state: Arc<Mutex<StubState>>,
+}
+
+impl AzureStub {
+    /// Start listening on an ephemeral localhost port. Requests must carry
+    /// `api-key: <api_key>` (or `Authorization: Bearer <api_key>`).
+    pub async fn start(api_key: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(StubState {
+            api_key: api_key.to_string(),
+            ..Default::default()
+        }));
+
+        let accept_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = Arc::clone(&accept_state);
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, state).await {
+                        eprintln!("azure stub: {e}");
+                    }
+                });
+            }
+        });
+
+        Self { addr, state }
+    }
+
+    /// Base URL to use as the client's endpoint.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Queue the reply to the next Responses API request. With nothing
+    /// queued the stub answers `"Hello world"` in two deltas.
+    pub fn push_vision(&self, reply: VisionReply) {
+        self.state.lock().unwrap().vision_replies.push_back(reply);
+    }
+
+    /// Queue the reply to the next Realtime `response.create`. With
+    /// nothing queued the stub answers `"I heard you"`.
+    pub fn push_audio(&self, reply: AudioReply) {
+        self.state.lock().unwrap().audio_replies.push_back(reply);
+    }
+
+    /// Forget every issued response id, so the next request chaining onto
+    /// one gets `previous_response_not_found` (as after server-side expiry).
+    pub fn expire_responses(&self) {
+        self.state.lock().unwrap().known_responses.clear();
+    }
+
+    /// Reject the next WebSocket upgrade with this HTTP status.
+    pub fn reject_next_ws(&self, status: u16) {
+        self.state.lock().unwrap().reject_ws = Some(status);
+    }
+
+    /// JSON bodies of every Responses API request received, in order.
+    pub fn vision_requests(&self) -> Vec<Value> {
+        self.state.lock().unwrap().vision_requests.clone()
+    }
+
+    /// Every Realtime client event received, in order.
+    pub fn audio_events(&self) -> Vec<Value> {
+        self.state.lock().unwrap().audio_events.clone()
+    }
+}
+
+// ── Connection handling ─────────────────────────────────────────────
+
+async fn handle_connection(stream: TcpStream, state: Arc<Mutex<StubState>>) -> Result<(), String> {
+    // Peek at the request line so the WebSocket handshake can still read
+    // the full request from the socket.
+    let mut buf = [0u8; 1024];
+    let n = stream.peek(&mut buf).await.map_err(|e| e.to_string())?;
+    let head = String::from_utf8_lossy(&buf[..n]);
+    let path = head.split_whitespace().nth(1).unwrap_or("").to_string();
+
+    if path.starts_with("/openai/realtime") {
+        handle_realtime(stream, state).await
+    } else if path.starts_with("/openai/v1/responses") {
+        handle_responses(stream, state).await
+    } else {
+        let mut stream = stream;
+        write_status(&mut stream, 404, "not found", None).await
+    }
+}
+
+/// Read an HTTP request head and body.
+async fn read_http_request(stream: &mut TcpStream) -> Result<(String, Vec<u8>), String> {
+    let mut data = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed mid-request".into());
+        }
+        data.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let content_length = header_value(&head, "content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    while data.len() < head_end + content_length {
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&chunk[..n]);
+    }
+    Ok((head, data[head_end..].to_vec()))
+}
+
+fn header_value<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        k.trim().eq_ignore_ascii_case(name).then(|| v.trim())
+    })
+}
+
+fn authorized(api_key: &str, header: impl Fn(&str) -> Option<String>) -> bool {
+    header("api-key").as_deref() == Some(api_key)
+        || header("authorization").as_deref() == Some(&format!("Bearer {api_key}"))
+}
+
+async fn write_status(
+    stream: &mut TcpStream,
+    code: u16,
+    body: &str,
+    retry_after_secs: Option<u64>,
+) -> Result<(), String> {
+    let retry = retry_after_secs
+        .map(|s| format!("Retry-After: {s}\r\n"))
+        .unwrap_or_default();
+    let response = format!(
+        "HTTP/1.1 {code} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{retry}Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ── Responses API (SSE) ─────────────────────────────────────────────
+
+async fn handle_responses(
+    mut stream: TcpStream,
+    state: Arc<Mutex<StubState>>,
+) -> Result<(), String> {
+    let (head, body) = read_http_request(&mut stream).await?;
+    let body: Value = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+
+    enum Outcome {
+        Stream(String, Vec<&'static str>),
+        Fail(u16, String, Option<u64>),
+    }
+
+    let outcome = {
+        let mut st = state.lock().unwrap();
+        st.vision_requests.push(body.clone());
+
+        let key = st.api_key.clone();
+        let stale = body
+            .get("previous_response_id")
+            .and_then(|v| v.as_str())
+            .filter(|id| !st.known_responses.contains(*id))
+            .map(str::to_string);
+
+        if !authorized(&key, |name| header_value(&head, name).map(str::to_string)) {
+            Outcome::Fail(
+                401,
+                error_json("401", "Access denied due to invalid key"),
+                None,
+            )
+        } else if let Some(id) = stale {
+            Outcome::Fail(
+                400,
+                error_json(
+                    "previous_response_not_found",
+                    &format!("Previous response with id '{id}' not found."),
+                ),
+                None,
+            )
+        } else {
+            match st.vision_replies.pop_front() {
+                Some(VisionReply::Status {
+                    code,
+                    body,
+                    retry_after_secs,
+                }) => Outcome::Fail(code, body, retry_after_secs),
+                reply => {
+                    let deltas = match reply {
+                        Some(VisionReply::Text(d)) => d,
+                        _ => vec!["Hello", " world"],
+                    };
+                    st.next_response += 1;
+                    let id = format!("resp_{}", st.next_response);
+                    st.known_responses.insert(id.clone());
+                    Outcome::Stream(id, deltas)
+                }
+            }
+        }
+    };
+
+    match outcome {
+        Outcome::Fail(code, body, retry) => write_status(&mut stream, code, &body, retry).await,
+        Outcome::Stream(id, deltas) => {
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n";
+            stream
+                .write_all(head.as_bytes())
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let mut events = vec![json!({ "type": "response.created", "response": { "id": id } })];
+            events.extend(
+                deltas
+                    .iter()
+                    .map(|d| json!({ "type": "response.output_text.delta", "delta": d })),
+            );
+            events.push(json!({ "type": "response.output_text.done", "text": deltas.concat() }));
+            events.push(json!({
+                "type": "response.completed",
+                "response": {
+                    "id": id,
+                    "usage": {
+                        "input_tokens": VISION_INPUT_TOKENS,
+                        "input_tokens_details": { "cached_tokens": 0 },
+                        "output_tokens": deltas.len(),
+                        "output_tokens_details": { "reasoning_tokens": 0 },
+                        "total_tokens": VISION_INPUT_TOKENS + deltas.len() as u64
+                    }
+                }
+            }));
+
+            // One write per event so the client sees a genuinely chunked stream.
+            for event in events {
+                let frame = format!(
+                    "event: {}\ndata: {}\n\n",
+                    event["type"].as_str().unwrap(),
+                    event
+                );
+                stream
+                    .write_all(frame.as_bytes())
+                    .await
+                    .map_err(|e| e.to_string())?;
+                stream.flush().await.map_err(|e| e.to_string())?;
+            }
+            stream.shutdown().await.map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn error_json(code: &str, message: &str) -> String {
+    json!({ "error": { "code": code, "message": message } }).to_string()
+}
+
+// ── Realtime API (WebSocket) ────────────────────────────────────────
+
+async fn handle_realtime(stream: TcpStream, state: Arc<Mutex<StubState>>) -> Result<(), String> {
+    let handshake_state = Arc::clone(&state);
+    // The handshake callback signature is fixed by tungstenite.
+    #[allow(clippy::result_large_err)]
+    let callback = move |req: &Request, resp: Response| -> Result<Response, ErrorResponse> {
+        let mut st = handshake_state.lock().unwrap();
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let status = match st.reject_ws.take() {
+            Some(code) => Some(code),
+            None if !authorized(&st.api_key, header) => Some(401),
+            None => None,
+        };
+        match status {
+            Some(code) => {
+                let mut err = ErrorResponse::new(Some(error_json(&code.to_string(), "rejected")));
+                *err.status_mut() = StatusCode::from_u16(code).unwrap();
+                if code == 429 {
+                    err.headers_mut()
+                        .insert("retry-after", "3".parse().unwrap());
+                }
+                Err(err)
+            }
+            None => Ok(resp),
+        }
+    };
+
+    let ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (mut sink, mut source) = ws.split();
+
+    let send = |v: Value| Message::Text(v.to_string().into());
+    sink.send(send(
+        json!({ "type": "session.created", "session": { "id": "sess_stub" } }),
+    ))
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut buffered_bytes = 0usize;
+    while let Some(msg) = source.next().await {
+        let text = match msg.map_err(|e| e.to_string())? {
+            Message::Text(t) => t.to_string(),
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let event: Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+        state.lock().unwrap().audio_events.push(event.clone());
+
+        let mut replies = Vec::new();
+        match event["type"].as_str().unwrap_or("") {
+            "session.update" => {
+                replies.push(json!({ "type": "session.updated", "session": event["session"] }));
+            }
+            "input_audio_buffer.append" => {
+                let audio = event["audio"].as_str().unwrap_or("");
+                buffered_bytes += audio.len() * 3 / 4;
+            }
+            "input_audio_buffer.commit" => {
+                if buffered_bytes == 0 {
+                    replies.push(json!({
+                        "type": "error",
+                        "error": {
+                            "code": "input_audio_buffer_commit_empty",
+                            "message": "Error committing input audio buffer: buffer is empty."
+                        }
+                    }));
+                } else {
+                    replies.push(
+                        json!({ "type": "input_audio_buffer.committed", "item_id": "item_stub" }),
+                    );
+                    replies.push(json!({
+                        "type": "conversation.item.input_audio_transcription.completed",
+                        "item_id": "item_stub",
+                        "content_index": 0,
+                        "transcript": "What does it cost?"
+                    }));
+                }
+                buffered_bytes = 0;
+            }
+            "response.create" => {
+                let reply = state.lock().unwrap().audio_replies.pop_front();
+                match reply.unwrap_or(AudioReply::Text(vec!["I heard", " you"])) {
+                    AudioReply::Text(deltas) => {
+                        replies.push(
+                            json!({ "type": "response.created", "response": { "id": "resp_rt" } }),
+                        );
+                        for d in &deltas {
+                            replies.push(json!({ "type": "response.text.delta", "delta": d }));
+                        }
+                        replies
+                            .push(json!({ "type": "response.text.done", "text": deltas.concat() }));
+                        replies.push(json!({
+                            "type": "response.done",
+                            "response": {
+                                "id": "resp_rt",
+                                "usage": {
+                                    "input_tokens": AUDIO_INPUT_TOKENS,
+                                    "output_tokens": deltas.len(),
+                                    "input_token_details": {
+                                        "text_tokens": AUDIO_INPUT_TOKENS - 200,
+                                        "audio_tokens": 200,
+                                        "cached_tokens": 0
+                                    },
+                                    "output_token_details": {
+                                        "text_tokens": deltas.len(),
+                                        "audio_tokens": 0
+                                    }
+                                }
+                            }
+                        }));
+                    }
+                    AudioReply::Error { code, message } => {
+                        replies.push(json!({ "type": "error", "error": { "code": code, "message": message } }));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        for reply in replies {
+            sink.send(send(reply)).await.map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
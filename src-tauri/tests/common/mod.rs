@@ -0,0 +1,3 @@
+//! Shared support code for integration tests.
+
+pub mod azure_stub;
@@ -0,0 +1,122 @@
+//! Integration test for replaying a recording through StreamManager.
+//! Fully deterministic — scripted provider, no Azure API, no capture devices.
+//!
+//! Run: cargo test --test replay_test
+
+use beme_lib::ai::scripted::ScriptedProvider;
+use beme_lib::events::{ChannelSink, EmittedEvent};
+use beme_lib::replay::{self, Recording, Replay, ReplayOptions};
+use beme_lib::stream_manager::StreamManager;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Left part dark, right part light — `split` moves the boundary.
+fn write_frame(dir: &Path, name: &str, split: u32) {
+    let img = image::RgbImage::from_fn(64, 64, |x, _| {
+        let v = if x < split { 0 } else { 255 };
+        image::Rgb([v, v, v])
+    });
+    img.save(dir.join(name)).unwrap();
+}
+
+/// One second of a 24kHz mono tone: four 250ms chunks.
+fn write_wav(path: &Path) {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 24000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut w = hound::WavWriter::create(path, spec).unwrap();
+    for i in 0..24000 {
+        w.write_sample(((i as f32 * 0.05).sin() * 8000.0) as i16)
+            .unwrap();
+    }
+    w.finalize().unwrap();
+}
+
+async fn next_event(rx: &mut mpsc::UnboundedReceiver<EmittedEvent>) -> EmittedEvent {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("timed out waiting for event")
+        .expect("sink closed")
+}
+
+/// Changed frames and every audio chunk reach the provider, and their
+/// answers come back as suggestions; the replay's audio session is closed
+/// once it ends.
+#[tokio::test]
+async fn replayed_recording_produces_suggestions() {
+    let dir = tempfile::tempdir().unwrap();
+    write_frame(dir.path(), "0000.jpg", 16);
+    write_frame(dir.path(), "0300.jpg", 16); // unchanged → skipped
+    write_frame(dir.path(), "0600.jpg", 48);
+    write_wav(&dir.path().join("audio.wav"));
+
+    let (sink, mut rx) = ChannelSink::new();
+    let sm = Arc::new(StreamManager::with_event_sink(Arc::new(sink)));
+    let provider = Arc::new(
+        ScriptedProvider::from_json(
+            r#"{
+                "vision": [ { "chunks": ["Save ", "the file."] }, { "chunks": ["Run the tests."] } ],
+                "audio": { "turns": [ { "afterChunks": 4, "chunks": ["Ask ", "why."] } ] }
+            }"#,
+        )
+        .unwrap(),
+    );
+    sm.configure_provider(provider.clone(), "vision", "audio");
+
+    let options = ReplayOptions {
+        speed: 0.0,
+        ..Default::default()
+    };
+    let replay = Replay::new(Recording::load(dir.path()).unwrap(), options).unwrap();
+    let stats = replay::run(replay, Arc::clone(&sm)).await.unwrap();
+    assert_eq!(stats.frames_analyzed, 2);
+    assert_eq!(stats.frames_skipped, 1);
+    assert_eq!(stats.audio_chunks, 4);
+    assert!(!sm.has_audio_session().await);
+    assert_eq!(provider.audio_chunks_received(), 4);
+
+    // Suggestion text by source and id, until all three answers are done
+    let mut texts: HashMap<(String, u64), String> = HashMap::new();
+    let mut frames = 0;
+    let mut done = 0;
+    while done < 3 {
+        let ev = next_event(&mut rx).await;
+        match ev.name.as_str() {
+            "capture:frame" => frames += 1,
+            "ai:suggestion" => {
+                let key = (
+                    ev.payload["source"].as_str().unwrap().to_string(),
+                    ev.payload["id"].as_u64().unwrap(),
+                );
+                let text = texts.entry(key).or_default();
+                text.push_str(ev.payload["text"].as_str().unwrap());
+                if ev.payload["done"].as_bool().unwrap() {
+                    done += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(frames, 2);
+
+    let mut screen: Vec<&str> = texts
+        .iter()
+        .filter(|((source, _), _)| source == "screen")
+        .map(|(_, text)| text.as_str())
+        .collect();
+    screen.sort();
+    assert_eq!(screen, vec!["Run the tests.", "Save the file."]);
+    let audio: Vec<&str> = texts
+        .iter()
+        .filter(|((source, _), _)| source == "audio")
+        .map(|(_, text)| text.as_str())
+        .collect();
+    assert_eq!(audio, vec!["Ask why."]);
+    assert_eq!(provider.frames_analyzed(), 2);
+}
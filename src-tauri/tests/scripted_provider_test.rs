@@ -0,0 +1,98 @@
+//! Integration tests for the fixture-driven `ScriptedProvider`.
+//! Fully deterministic — no Azure API, no audio hardware, no browser.
+//!
+//! Run: cargo test --test scripted_provider_test
+
+use beme_lib::ai::scripted::ScriptedProvider;
+use beme_lib::ai::{AiError, AiProvider, AudioResponse, GenerationConfig, VisionRequest};
+use beme_lib::stream_manager::StreamManager;
+use std::path::Path;
+use std::sync::Arc;
+
+fn demo_provider() -> ScriptedProvider {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/scripted-demo.json");
+    ScriptedProvider::from_file(&path).expect("demo fixture should parse")
+}
+
+/// The bundled demo fixture plays its vision script in order, including
+/// the rate-limit and mid-stream connection errors.
+#[tokio::test]
+async fn demo_fixture_vision_script() {
+    let provider = demo_provider();
+
+    let mut stream = provider
+        .analyze_frame(VisionRequest::new("", ""))
+        .await
+        .unwrap();
+    let mut text = String::new();
+    while let Some(chunk) = stream.next_chunk().await {
+        text.push_str(&chunk.unwrap());
+    }
+    assert_eq!(text, "You have unsaved changes — press Ctrl+S.");
+
+    provider
+        .analyze_frame(VisionRequest::new("", ""))
+        .await
+        .unwrap();
+
+    let err = provider
+        .analyze_frame(VisionRequest::new("", ""))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        AiError::RateLimited {
+            retry_after_ms: 2000
+        }
+    ));
+
+    let mut stream = provider
+        .analyze_frame(VisionRequest::new("", ""))
+        .await
+        .unwrap();
+    assert!(stream.next_chunk().await.unwrap().is_ok());
+    assert!(stream.next_chunk().await.unwrap().is_ok());
+    let err = stream.next_chunk().await.unwrap().unwrap_err();
+    assert!(matches!(err, AiError::ConnectionError(_)));
+    assert!(stream.next_chunk().await.is_none());
+
+    assert_eq!(provider.frames_analyzed(), 4);
+}
+
+/// Audio pushed through `StreamManager` reaches a scripted session, which
+/// answers once enough chunks have arrived.
+#[tokio::test]
+async fn scripted_audio_session_through_stream_manager() {
+    let provider = Arc::new(demo_provider());
+    let sm = StreamManager::new();
+    sm.configure_provider(provider.clone(), "vision prompt", "audio prompt");
+    assert!(sm.is_configured());
+    assert_eq!(
+        sm.get_prompts(),
+        ("vision prompt".to_string(), "audio prompt".to_string())
+    );
+
+    let (session, mut rx) = provider
+        .start_audio_stream("audio prompt", &GenerationConfig::default())
+        .await
+        .unwrap();
+    sm.inject_audio_session(session).await;
+
+    for _ in 0..8 {
+        sm.process_audio_chunk(&[0u8; 12000]).await.unwrap();
+    }
+    let mut texts = Vec::new();
+    let mut transcripts = Vec::new();
+    loop {
+        match rx.recv().await.unwrap().unwrap() {
+            AudioResponse::Delta(text) => texts.push(text),
+            AudioResponse::Transcript(text) => transcripts.push(text),
+            AudioResponse::Usage(_) => {}
+            AudioResponse::Done => break,
+        }
+    }
+    assert_eq!(texts.concat(), "Ask what their timeline is.");
+    assert_eq!(transcripts, ["We'd like to roll this out soon."]);
+    assert_eq!(provider.audio_chunks_received(), 8);
+}
@@ -4,8 +4,19 @@
 //! Run: cargo test --test stream_manager_test
 
 use async_trait::async_trait;
-use beme_lib::ai::{AiError, AudioResponseRx, AudioSession};
+use beme_lib::ai::scripted::ScriptedProvider;
+use beme_lib::ai::{
+    AiError, AiProvider, AudioResponse, AudioResponseRx, AudioSession, GenerationConfig,
+    ImageDetail, ReasoningEffort, SuggestionFormat, TextStream, VisionRequest,
+};
+use beme_lib::budget::{BudgetSettings, FrameSize};
+use beme_lib::events::{ChannelSink, EmittedEvent};
+use beme_lib::limits::LimitSettings;
+use beme_lib::novelty::NoveltySettings;
 use beme_lib::stream_manager::StreamManager;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::mpsc;
 
 // ---------------------------------------------------------------------------
@@ -87,7 +98,8 @@ async fn multiple_chunks_arrive_in_order() {
 async fn process_audio_chunk_fails_without_session() {
     let sm = StreamManager::new();
     let err = sm.process_audio_chunk(&[1, 2, 3]).await.unwrap_err();
-    assert_eq!(err, "No active audio session");
+    assert_eq!(err.kind(), "noAudioSession");
+    assert_eq!(err.to_string(), "No active audio session");
 }
 
 /// Session lifecycle: inject → active → clear → inactive.
@@ -114,37 +126,56 @@ async fn session_lifecycle() {
     assert!(sm.process_audio_chunk(&[1]).await.is_err());
 }
 
-/// The `AudioResponseRx` channel correctly delivers canned text responses,
+/// The `AudioResponseRx` channel correctly delivers canned responses,
 /// matching the pattern used by the reader task in `start_audio_session`.
 #[tokio::test]
 async fn response_channel_delivers_canned_responses() {
-    let (tx, mut rx): (mpsc::Sender<Result<String, AiError>>, AudioResponseRx) = mpsc::channel(16);
+    let (tx, mut rx): (
+        mpsc::Sender<Result<AudioResponse, AiError>>,
+        AudioResponseRx,
+    ) = mpsc::channel(16);
 
-    tx.send(Ok("Hello".into())).await.unwrap();
-    tx.send(Ok(" world".into())).await.unwrap();
-    tx.send(Ok(String::new())).await.unwrap(); // turn-done signal
+    tx.send(Ok(AudioResponse::Delta("Hello".into())))
+        .await
+        .unwrap();
+    tx.send(Ok(AudioResponse::Delta(" world".into())))
+        .await
+        .unwrap();
+    tx.send(Ok(AudioResponse::Done)).await.unwrap();
     drop(tx);
 
-    let mut texts = Vec::new();
+    let mut responses = Vec::new();
     while let Some(result) = rx.recv().await {
-        texts.push(result.unwrap());
+        responses.push(result.unwrap());
     }
-    assert_eq!(texts, vec!["Hello", " world", ""]);
+    assert_eq!(
+        responses,
+        vec![
+            AudioResponse::Delta("Hello".into()),
+            AudioResponse::Delta(" world".into()),
+            AudioResponse::Done
+        ]
+    );
 }
 
 /// Errors on the response channel are propagated correctly.
 #[tokio::test]
 async fn response_channel_propagates_errors() {
-    let (tx, mut rx): (mpsc::Sender<Result<String, AiError>>, AudioResponseRx) = mpsc::channel(16);
+    let (tx, mut rx): (
+        mpsc::Sender<Result<AudioResponse, AiError>>,
+        AudioResponseRx,
+    ) = mpsc::channel(16);
 
-    tx.send(Ok("partial".into())).await.unwrap();
+    tx.send(Ok(AudioResponse::Delta("partial".into())))
+        .await
+        .unwrap();
     tx.send(Err(AiError::ConnectionError("lost connection".into())))
         .await
         .unwrap();
     drop(tx);
 
     let first = rx.recv().await.unwrap().unwrap();
-    assert_eq!(first, "partial");
+    assert_eq!(first, AudioResponse::Delta("partial".into()));
 
     let second = rx.recv().await.unwrap();
     assert!(second.is_err());
@@ -162,3 +193,747 @@ async fn closed_session_rejects_audio() {
     let result = session.send_audio(&[3, 4]).await;
     assert!(result.is_err());
 }
+
+// ---------------------------------------------------------------------------
+// Full pipeline via EventSink
+// ---------------------------------------------------------------------------
+
+/// A manager wired to a scripted provider the test can inspect, reporting
+/// into a channel.
+fn scripted_manager(
+    script: &str,
+) -> (
+    StreamManager,
+    mpsc::UnboundedReceiver<EmittedEvent>,
+    Arc<ScriptedProvider>,
+) {
+    let (sink, mut rx) = ChannelSink::new();
+    let sm = StreamManager::with_event_sink(Arc::new(sink));
+    let provider = Arc::new(ScriptedProvider::from_json(script).unwrap());
+    sm.configure_provider(provider.clone(), "vision", "audio");
+    assert_eq!(rx.try_recv().unwrap().name, "ai:thread");
+    (sm, rx, provider)
+}
+
+async fn next_event(rx: &mut mpsc::UnboundedReceiver<EmittedEvent>) -> EmittedEvent {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("timed out waiting for event")
+        .expect("sink closed")
+}
+
+/// `analyze_frame` streams chunks as `ai:suggestion` events sharing one id,
+/// then a final `done` event.
+#[tokio::test]
+async fn analyze_frame_emits_suggestions() {
+    let (sm, mut rx, _) = scripted_manager(r#"{ "vision": [ { "chunks": ["Save ", "now."] } ] }"#);
+    sm.analyze_frame("aW1n".into(), None);
+
+    let mut texts = Vec::new();
+    let mut ids = Vec::new();
+    loop {
+        let ev = next_event(&mut rx).await;
+        assert_eq!(ev.name, "ai:suggestion");
+        assert_eq!(ev.payload["source"], "screen");
+        ids.push(ev.payload["id"].as_u64().unwrap());
+        if ev.payload["done"].as_bool().unwrap() {
+            break;
+        }
+        texts.push(ev.payload["text"].as_str().unwrap().to_string());
+    }
+    assert_eq!(texts.concat(), "Save now.");
+    assert!(ids.iter().all(|id| *id == ids[0]));
+}
+
+/// A failed request is reported as a typed `ai:error` with no suggestion events.
+#[tokio::test]
+async fn analyze_frame_reports_errors() {
+    let (sm, mut rx, _) = scripted_manager(
+        r#"{ "vision": [ { "error": { "kind": "auth", "message": "bad key" } } ] }"#,
+    );
+    sm.analyze_frame("aW1n".into(), None);
+
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:error");
+    assert!(ev.payload["message"].as_str().unwrap().contains("bad key"));
+    assert_eq!(ev.payload["kind"], "auth");
+    assert_eq!(ev.payload["retryable"], false);
+    assert_eq!(ev.payload["source"], "screen");
+    assert_eq!(ev.payload["suggestion_id"], 1);
+}
+
+/// Rate limits and provider error codes reach the `ai:error` payload.
+#[tokio::test]
+async fn analyze_frame_error_carries_retry_hint_and_code() {
+    let (sm, mut rx, _) = scripted_manager(
+        r#"{ "vision": [
+            { "error": { "kind": "rateLimited", "retryAfterMs": 2500 } },
+            { "error": { "kind": "api", "status": 503, "code": "server_error", "message": "busy" } }
+        ] }"#,
+    );
+    sm.analyze_frame("aW1n".into(), None);
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.payload["kind"], "rateLimited");
+    assert_eq!(ev.payload["retry_after_ms"], 2500);
+    assert_eq!(ev.payload["status"], 429);
+
+    sm.analyze_frame("aW1n".into(), None);
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.payload["kind"], "api");
+    assert_eq!(ev.payload["status"], 503);
+    assert_eq!(ev.payload["provider_code"], "server_error");
+    assert_eq!(ev.payload["retryable"], true);
+    assert_eq!(ev.payload["suggestion_id"], 2);
+}
+
+/// The audio session lifecycle emits status events, and responses arrive as
+/// `audio` suggestions terminated by a `done` event.
+#[tokio::test]
+async fn audio_session_emits_status_and_suggestions() {
+    let (sm, mut rx, _) = scripted_manager(
+        r#"{ "audio": { "turns": [ { "afterChunks": 2, "chunks": ["Ask ", "why."] } ] } }"#,
+    );
+    sm.start_audio_session().await.unwrap();
+    assert!(sm.has_audio_session().await);
+
+    for status in ["connecting", "connected"] {
+        let ev = next_event(&mut rx).await;
+        assert_eq!(ev.name, "ai:audio-status");
+        assert_eq!(ev.payload["status"], status);
+    }
+
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+
+    let mut texts = Vec::new();
+    loop {
+        let ev = next_event(&mut rx).await;
+        assert_eq!(ev.name, "ai:suggestion");
+        assert_eq!(ev.payload["source"], "audio");
+        if ev.payload["done"].as_bool().unwrap() {
+            break;
+        }
+        texts.push(ev.payload["text"].as_str().unwrap().to_string());
+    }
+    assert_eq!(texts.concat(), "Ask why.");
+
+    sm.stop_audio_session().await.unwrap();
+    assert!(!sm.has_audio_session().await);
+    let statuses: Vec<String> = std::iter::from_fn(|| rx.try_recv().ok())
+        .filter(|ev| ev.name == "ai:audio-status")
+        .map(|ev| ev.payload["status"].as_str().unwrap().to_string())
+        .collect();
+    assert!(statuses.contains(&"disconnected".to_string()));
+}
+
+/// While paused, frames aren't analyzed and audio isn't forwarded; resuming
+/// picks up again. Each change is reported once on `ai:paused`.
+#[tokio::test]
+async fn paused_ai_drops_frames_and_audio() {
+    let (sm, mut rx, _) = scripted_manager(r#"{ "vision": [ { "chunks": ["Go."] } ] }"#);
+    let (session, mut audio_rx) = mock_session();
+    sm.inject_audio_session(Box::new(session)).await;
+
+    sm.set_paused(true);
+    sm.set_paused(true);
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:paused");
+    assert_eq!(ev.payload["paused"], true);
+
+    sm.analyze_frame("aW1n".into(), None);
+    sm.process_audio_chunk(&[1, 2]).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(rx.try_recv().is_err());
+    assert!(audio_rx.try_recv().is_err());
+
+    sm.set_paused(false);
+    assert_eq!(next_event(&mut rx).await.payload["paused"], false);
+    sm.analyze_frame("aW1n".into(), None);
+    assert_eq!(next_event(&mut rx).await.name, "ai:suggestion");
+}
+
+/// The full text of a finished suggestion is kept for "copy last suggestion".
+#[tokio::test]
+async fn last_suggestion_holds_the_completed_text() {
+    let (sm, mut rx, _) = scripted_manager(r#"{ "vision": [ { "chunks": ["Save ", "now."] } ] }"#);
+    assert_eq!(sm.last_suggestion(), None);
+
+    sm.analyze_frame("aW1n".into(), None);
+    while !next_event(&mut rx).await.payload["done"].as_bool().unwrap() {}
+    assert_eq!(sm.last_suggestion().as_deref(), Some("Save now."));
+}
+
+/// "Analyze now" streams a normal suggestion tagged with the question, even
+/// while the AI is paused, and needs a configured provider.
+#[tokio::test]
+async fn analyze_now_answers_the_question_even_when_paused() {
+    let err = StreamManager::new()
+        .analyze_now("aW1n".into(), None, None)
+        .unwrap_err();
+    assert_eq!(err.kind(), "notConfigured");
+
+    let (sm, mut rx, _) = scripted_manager(r#"{ "vision": [ { "chunks": ["The red one."] } ] }"#);
+    sm.set_paused(true);
+    next_event(&mut rx).await; // ai:paused
+
+    let id = sm
+        .analyze_now("aW1n".into(), Some("  Which button?  ".into()), None)
+        .unwrap();
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:suggestion");
+    assert_eq!(ev.payload["id"], id);
+    assert_eq!(ev.payload["text"], "The red one.");
+    assert_eq!(ev.payload["question"], "Which button?");
+}
+
+/// A follow-up continues the response behind *that* suggestion, even after
+/// later frames, and its reply is linked back with `parent_id`.
+#[tokio::test]
+async fn followup_continues_the_chosen_suggestion() {
+    let (sm, mut rx, provider) = scripted_manager(
+        r#"{ "vision": [ { "chunks": ["Save."] }, { "chunks": ["Close."] }, { "chunks": ["Unsaved work."] } ] }"#,
+    );
+
+    // Not answered yet, so nothing to follow up on
+    assert_eq!(
+        sm.ask_followup(1, "why?".into()).unwrap_err().kind(),
+        "invalidInput"
+    );
+
+    for _ in 0..2 {
+        sm.analyze_frame("aW1n".into(), None);
+        while !next_event(&mut rx).await.payload["done"].as_bool().unwrap() {}
+    }
+    assert!(sm.ask_followup(1, "   ".into()).is_err());
+
+    let id = sm.ask_followup(1, "why?".into()).unwrap();
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.payload["id"], id);
+    assert_eq!(ev.payload["parent_id"], 1);
+    assert_eq!(ev.payload["question"], "why?");
+    assert_eq!(ev.payload["text"], "Unsaved work.");
+    assert_eq!(
+        provider.follow_ups(),
+        [("scripted_1".to_string(), "why?".to_string())]
+    );
+}
+
+/// `ask_followup` backs a sync Tauri command, which runs on the main thread
+/// outside any Tokio runtime; frames and follow-ups must still stream there.
+#[test]
+fn followup_works_outside_a_runtime() {
+    let (sm, mut rx, _) = scripted_manager(
+        r#"{ "vision": [ { "chunks": ["Save."] }, { "chunks": ["Unsaved work."] } ] }"#,
+    );
+
+    let next_done = |rx: &mut mpsc::UnboundedReceiver<EmittedEvent>| {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            match rx.try_recv() {
+                Ok(ev) if ev.name == "ai:suggestion" && ev.payload["done"] == true => {
+                    return ev.payload["id"].as_u64().unwrap()
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    assert!(std::time::Instant::now() < deadline, "timed out");
+                    std::thread::sleep(Duration::from_millis(5));
+                }
+            }
+        }
+    };
+
+    sm.analyze_frame("aW1n".into(), None);
+    let first = next_done(&mut rx);
+    let id = sm.ask_followup(first, "why?".into()).unwrap();
+    assert_eq!(next_done(&mut rx), id);
+    assert_eq!(sm.last_suggestion().as_deref(), Some("Unsaved work."));
+}
+
+/// Wait for suggestion `id` to finish, skipping other events.
+async fn wait_done(rx: &mut mpsc::UnboundedReceiver<EmittedEvent>, id: u64) {
+    loop {
+        let ev = next_event(rx).await;
+        if ev.name == "ai:suggestion" && ev.payload["id"] == id && ev.payload["done"] == true {
+            return;
+        }
+    }
+}
+
+/// Frames chain onto the previous reply in their thread. Resetting starts
+/// from nothing; forking continues from the chosen suggestion.
+#[tokio::test]
+async fn frames_chain_within_a_thread_until_reset_or_forked() {
+    let (sm, mut rx, provider) = scripted_manager(r#"{ "vision": [ { "chunks": ["Ok."] } ] }"#);
+    let first_thread = sm.thread_id();
+
+    let first = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    wait_done(&mut rx, first).await;
+    let second = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    assert_eq!(next_event(&mut rx).await.payload["thread_id"], first_thread);
+    wait_done(&mut rx, second).await;
+
+    let reset = sm.reset_thread("reset");
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:thread");
+    assert_eq!(ev.payload["thread_id"], reset);
+    assert_eq!(ev.payload["reason"], "reset");
+    let id = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    wait_done(&mut rx, id).await;
+
+    assert_eq!(sm.fork_thread(999).unwrap_err().kind(), "invalidInput");
+    let forked = sm.fork_thread(first).unwrap();
+    assert_eq!(next_event(&mut rx).await.payload["forked_from"], first);
+    let id = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    assert_eq!(next_event(&mut rx).await.payload["thread_id"], forked);
+    wait_done(&mut rx, id).await;
+
+    let s1 = Some("scripted_1".to_string());
+    assert_eq!(provider.frame_parents(), [None, s1.clone(), None, s1]);
+
+    // Changing the vision prompt starts over too; setting the same one doesn't
+    sm.update_prompt("vision", "vision");
+    assert!(rx.try_recv().is_err());
+    sm.update_prompt("vision", "new prompt");
+    assert_eq!(next_event(&mut rx).await.payload["reason"], "prompt");
+    let id = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    wait_done(&mut rx, id).await;
+    assert_eq!(provider.frame_parents()[4], None);
+}
+
+/// Overlapping frames both continue the same head. Whichever finishes first
+/// moves the thread on; the slower one doesn't overwrite it.
+#[tokio::test]
+async fn overlapping_frames_do_not_chain_out_of_order() {
+    let (sm, mut rx, provider) = scripted_manager(
+        r#"{ "vision": [ { "chunks": ["Slow."], "delayMs": 100 }, { "chunks": ["Fast."] } ] }"#,
+    );
+
+    let slow = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    let fast = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    wait_done(&mut rx, fast).await;
+    wait_done(&mut rx, slow).await;
+    let next = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    wait_done(&mut rx, next).await;
+
+    assert_eq!(
+        provider.frame_parents(),
+        [None, None, Some("scripted_2".to_string())]
+    );
+}
+
+/// In structured mode the JSON answer is reported once, as typed fields,
+/// and the action stands in as the suggestion text. An answer that doesn't
+/// parse is reported as an error but still shown.
+#[tokio::test]
+async fn structured_suggestions_are_parsed_when_complete() {
+    let (sm, mut rx, _) = scripted_manager(
+        r#"{ "vision": [
+            { "chunks": ["{\"action\":\"Press Ctrl+S\",\"rationale\":\"Unsaved ", "changes.\",\"confidence\":0.9,\"urgency\":\"high\",\"target_app\":\"VS Code\"}"] },
+            { "chunks": ["Not JSON"] }
+        ] }"#,
+    );
+    sm.set_suggestion_format(SuggestionFormat::Structured);
+
+    let id = sm
+        .analyze_now("aW1n".into(), Some("What now?".into()), None)
+        .unwrap();
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:structured-suggestion");
+    assert_eq!(ev.payload["id"], id);
+    assert_eq!(ev.payload["action"], "Press Ctrl+S");
+    assert_eq!(ev.payload["rationale"], "Unsaved changes.");
+    assert_eq!(ev.payload["urgency"], "high");
+    assert_eq!(ev.payload["target_app"], "VS Code");
+    assert_eq!(ev.payload["question"], "What now?");
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:suggestion");
+    assert_eq!(ev.payload["text"], "Press Ctrl+S");
+    assert_eq!(next_event(&mut rx).await.payload["done"], true);
+    assert_eq!(sm.last_suggestion().as_deref(), Some("Press Ctrl+S"));
+
+    sm.analyze_frame("aW1n".into(), None);
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:error");
+    assert_eq!(ev.payload["kind"], "invalidResponse");
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.payload["text"], "Not JSON");
+}
+
+/// A capture suggestion that repeats a recent one is reported on
+/// `suggestion:suppressed`; "analyze now" answers are never suppressed.
+#[tokio::test]
+async fn repeated_capture_suggestions_are_suppressed() {
+    let (sm, mut rx, _) = scripted_manager(
+        r#"{ "vision": [
+            { "chunks": ["Press Ctrl+S to save your changes."] },
+            { "chunks": ["press ctrl+s to save your changes"] },
+            { "chunks": ["Press Ctrl+S to save the changes."] }
+        ] }"#,
+    );
+    sm.set_novelty_settings(NoveltySettings {
+        enabled: true,
+        ..Default::default()
+    });
+
+    sm.analyze_frame("aW1n".into(), None);
+    let first = next_event(&mut rx).await.payload["id"].as_u64().unwrap();
+    assert_eq!(next_event(&mut rx).await.payload["done"], true);
+
+    sm.analyze_frame("aW1n".into(), None);
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:suggestion");
+    let second = ev.payload["id"].as_u64().unwrap();
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "suggestion:suppressed");
+    assert_eq!(ev.payload["id"], second);
+    assert_eq!(ev.payload["duplicate_of"], first);
+    assert_eq!(ev.payload["reason"], "identical");
+    assert_eq!(next_event(&mut rx).await.payload["done"], true);
+
+    let id = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    loop {
+        let ev = next_event(&mut rx).await;
+        assert_ne!(ev.name, "suggestion:suppressed");
+        if ev.payload["id"] == id && ev.payload["done"] == true {
+            break;
+        }
+    }
+}
+
+/// Structured repeats are withheld entirely, and a new thread starts with a
+/// clean slate.
+#[tokio::test]
+async fn structured_repeats_are_withheld() {
+    let answer = r#"{ "chunks": ["{\"action\":\"Save\",\"rationale\":\"\",\"confidence\":1,\"urgency\":\"low\",\"target_app\":null}"] }"#;
+    let (sm, mut rx, _) = scripted_manager(&format!(r#"{{ "vision": [ {answer} ] }}"#));
+    sm.set_suggestion_format(SuggestionFormat::Structured);
+    sm.set_novelty_settings(NoveltySettings {
+        enabled: true,
+        ..Default::default()
+    });
+
+    sm.analyze_frame("aW1n".into(), None);
+    assert_eq!(next_event(&mut rx).await.name, "ai:structured-suggestion");
+    next_event(&mut rx).await; // text
+    next_event(&mut rx).await; // done
+
+    sm.analyze_frame("aW1n".into(), None);
+    assert_eq!(next_event(&mut rx).await.name, "suggestion:suppressed");
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(rx.try_recv().is_err());
+
+    sm.reset_thread("reset");
+    assert_eq!(next_event(&mut rx).await.name, "ai:thread");
+    sm.analyze_frame("aW1n".into(), None);
+    assert_eq!(next_event(&mut rx).await.name, "ai:structured-suggestion");
+}
+
+/// Prompt variables are filled in when each request is made: audio
+/// sessions at the start, frames with what was heard and said since.
+#[tokio::test]
+async fn prompt_variables_are_filled_in_per_request() {
+    let (sm, mut rx, provider) = scripted_manager(
+        r#"{
+            "vision": [ { "chunks": ["Open the invoice."] } ],
+            "audio": { "turns": [
+                { "afterChunks": 1, "transcript": "What does it cost?", "chunks": ["Ask about pricing."] }
+            ] }
+        }"#,
+    );
+    sm.update_prompt("audio", "Heard: {{recent_transcript}}");
+    sm.update_prompt(
+        "vision",
+        "{{time}} {{user_notes}} | {{monitor_name}} | {{active_window}} | {{recent_transcript}} | {{last_suggestion}} | {{unknown}}",
+    );
+    sm.set_user_notes("Billing team");
+    sm.set_monitor_name(Some("DELL U2720Q".into()));
+    sm.set_window_probe(Arc::new(|| Some("Code — main.rs".into())));
+
+    sm.start_audio_session().await.unwrap();
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+    wait_done(&mut rx, 1).await;
+    assert_eq!(sm.recent_transcript(), ["What does it cost?"]);
+
+    let id = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    wait_done(&mut rx, id).await;
+
+    let prompts = provider.system_prompts();
+    assert_eq!(prompts[0], "Heard: ");
+    let (time, rest) = prompts[1].split_once(' ').unwrap();
+    assert!(time.ends_with('Z'), "{time}");
+    assert_eq!(
+        rest,
+        "Billing team | DELL U2720Q | Code — main.rs | What does it cost? | Ask about pricing. | {{unknown}}"
+    );
+    // What's stored (and edited) is the template
+    assert!(sm.get_prompts().0.starts_with("{{time}}"));
+}
+
+/// The generation config in effect when a request is made goes with it;
+/// changing it affects later requests only.
+#[tokio::test]
+async fn generation_config_goes_with_each_request() {
+    let (sm, mut rx, provider) = scripted_manager(
+        r#"{
+            "vision": [ { "chunks": ["Open the invoice."] } ],
+            "audio": { "turns": [] }
+        }"#,
+    );
+    let brief = GenerationConfig {
+        max_output_tokens: 60,
+        image_detail: ImageDetail::Low,
+        ..Default::default()
+    };
+    sm.set_generation_config(brief.clone());
+    sm.start_audio_session().await.unwrap();
+    let id = sm.analyze_now("aW1n".into(), None, None).unwrap();
+    wait_done(&mut rx, id).await;
+
+    let thorough = GenerationConfig {
+        max_output_tokens: 600,
+        reasoning_effort: Some(ReasoningEffort::High),
+        ..Default::default()
+    };
+    sm.set_generation_config(thorough.clone());
+    let follow_up = sm.ask_followup(id, "Why?".into()).unwrap();
+    wait_done(&mut rx, follow_up).await;
+
+    assert_eq!(sm.generation_config(), thorough);
+    assert_eq!(provider.generations(), [brief.clone(), brief, thorough]);
+}
+
+/// Frames sized by `plan_frame` count against the image token budget once
+/// sent (paused frames don't) and go out at the plan's detail level; later
+/// frames shrink to fit what's left.
+#[tokio::test]
+async fn planned_frames_are_counted_against_the_token_budget() {
+    let (sm, mut rx, provider) = scripted_manager(r#"{ "vision": [ { "chunks": ["Ok."] } ] }"#);
+    sm.set_budget_settings(BudgetSettings {
+        image_tokens_per_minute: 30_000,
+        ..Default::default()
+    });
+    let base = FrameSize {
+        max_width: 1024,
+        jpeg_quality: 75,
+    };
+    let interval = Duration::from_secs(2);
+
+    let full = sm.plan_frame((2560, 1440), base, interval);
+    assert_eq!((full.size, full.image_tokens), (base, 765));
+    sm.analyze_frame("aW1n".into(), Some(full));
+    wait_done(&mut rx, 1).await;
+
+    sm.set_paused(true);
+    sm.analyze_frame("aW1n".into(), Some(full));
+    sm.set_paused(false);
+    assert_eq!(sm.image_tokens_last_minute(), 765);
+
+    let low = full.with_detail(ImageDetail::Low);
+    let id = sm.analyze_now("aW1n".into(), None, Some(low)).unwrap();
+    wait_done(&mut rx, id).await;
+    assert_eq!(sm.image_tokens_last_minute(), 850);
+    assert_eq!(
+        provider.frame_details(),
+        [ImageDetail::Auto, ImageDetail::Low]
+    );
+
+    // With the budget nearly spent, the next frame is smaller
+    sm.set_budget_settings(BudgetSettings {
+        image_tokens_per_minute: 1300,
+        ..Default::default()
+    });
+    let next = sm.plan_frame((2560, 1440), base, interval);
+    assert!(next.image_tokens <= 450, "{next:?}");
+    assert!(next.size.max_width < 1024, "{next:?}");
+}
+
+/// Reported usage is priced, added to the session and the day, and sent on
+/// `ai:usage`; frames fill in their estimated image tokens. A new session
+/// starts from zero while the day carries on.
+#[tokio::test]
+async fn usage_is_priced_and_added_up() {
+    let (sm, mut rx, _) = scripted_manager(
+        r#"{
+            "vision": [ { "chunks": ["Ok."], "usage": { "input_tokens": 1000, "output_tokens": 100 } } ],
+            "audio": { "turns": [ { "afterChunks": 1, "chunks": ["Hi."],
+                "usage": { "input_tokens": 300, "output_tokens": 20,
+                           "audio_input_tokens": 200 } } ] }
+        }"#,
+    );
+    let base = FrameSize {
+        max_width: 1024,
+        jpeg_quality: 75,
+    };
+    let plan = sm.plan_frame((2560, 1440), base, Duration::from_secs(2));
+    sm.analyze_frame("aW1n".into(), Some(plan));
+
+    let usage = loop {
+        let ev = next_event(&mut rx).await;
+        if ev.name == "ai:usage" {
+            break ev.payload;
+        }
+    };
+    assert_eq!(usage["source"], "screen");
+    assert_eq!(usage["suggestion_id"], 1);
+    assert_eq!(usage["usage"]["image_tokens"], 765);
+    assert_eq!(usage["currency"], "USD");
+    // 1000 in at 2.50/M, 100 out at 10/M
+    assert!((usage["cost"].as_f64().unwrap() - 0.0035).abs() < 1e-9);
+    assert_eq!(usage["session"]["requests"], 1);
+    assert_eq!(usage["today"]["input_tokens"], 1000);
+
+    sm.start_audio_session().await.unwrap();
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+    let usage = loop {
+        let ev = next_event(&mut rx).await;
+        if ev.name == "ai:usage" {
+            break ev.payload;
+        }
+    };
+    assert_eq!(usage["source"], "audio");
+    // 100 text in at 5/M, 200 audio in at 40/M, 20 out at 20/M
+    assert!((usage["cost"].as_f64().unwrap() - 0.0089).abs() < 1e-9);
+    assert_eq!(usage["session"]["requests"], 2);
+
+    let report = sm.usage_report();
+    assert_eq!(report.session.tokens.input_tokens, 1300);
+    assert!((report.session.cost - 0.0124).abs() < 1e-9);
+    sm.start_usage_session();
+    let report = sm.usage_report();
+    assert_eq!(report.session.requests, 0);
+    assert_eq!(report.today.requests, 2);
+    sm.stop_audio_session().await.unwrap();
+}
+
+/// Once a spending limit is reached, capture frames are dropped and the
+/// audio session is closed; an override resumes both, reopening the session.
+#[tokio::test]
+async fn spending_limits_pause_frames_and_suspend_audio() {
+    let (sm, mut rx, provider) = scripted_manager(
+        r#"{
+            "vision": [ { "chunks": ["Ok."], "usage": { "input_tokens": 900, "output_tokens": 100 } } ],
+            "audio": { "turns": [ { "afterChunks": 100, "chunks": ["Hi."] } ] }
+        }"#,
+    );
+    sm.set_limits(LimitSettings {
+        tokens_per_hour: 1000,
+        ..Default::default()
+    });
+    sm.start_audio_session().await.unwrap();
+
+    sm.analyze_frame("aW1n".into(), None);
+    wait_done(&mut rx, 1).await;
+
+    sm.analyze_frame("aW1n".into(), None);
+    let limit = loop {
+        let ev = next_event(&mut rx).await;
+        if ev.name == "ai:limit" {
+            break ev.payload;
+        }
+    };
+    assert_eq!(limit["state"], "reached");
+    assert_eq!(limit["limit"]["kind"], "tokens_per_hour");
+    assert_eq!(limit["limit"]["used"], 1000.0);
+    assert_eq!(provider.frames_analyzed(), 1);
+
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+    assert!(!sm.has_audio_session().await);
+    assert!(sm.limit_status().audio_suspended);
+    assert_eq!(provider.audio_chunks_received(), 0);
+
+    sm.override_limits(Duration::from_secs(600));
+    let ev = next_event(&mut rx).await;
+    assert_eq!(ev.name, "ai:limit");
+    assert_eq!(ev.payload["state"], "overridden");
+    assert_eq!(ev.payload["override_secs"], 600);
+
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+    assert!(sm.has_audio_session().await);
+    assert_eq!(provider.audio_chunks_received(), 1);
+    sm.analyze_frame("aW1n".into(), None);
+    loop {
+        let ev = next_event(&mut rx).await;
+        if ev.name == "ai:suggestion" && ev.payload["done"] == true {
+            break;
+        }
+    }
+    assert_eq!(provider.frames_analyzed(), 2);
+
+    let status = sm.limit_status();
+    assert!(status.reached.is_none());
+    assert!(status.override_secs.is_some());
+    assert!(!status.audio_suspended);
+    sm.stop_audio_session().await.unwrap();
+}
+
+/// A scripted provider whose audio connection can be made to fail once.
+struct FlakyAudio {
+    inner: Arc<ScriptedProvider>,
+    fail_next: AtomicBool,
+}
+
+#[async_trait]
+impl AiProvider for FlakyAudio {
+    async fn analyze_frame(
+        &self,
+        request: VisionRequest<'_>,
+    ) -> Result<Box<dyn TextStream>, AiError> {
+        self.inner.analyze_frame(request).await
+    }
+
+    async fn start_audio_stream(
+        &self,
+        system_prompt: &str,
+        generation: &GenerationConfig,
+    ) -> Result<(Box<dyn AudioSession>, AudioResponseRx), AiError> {
+        if self.fail_next.swap(false, Ordering::SeqCst) {
+            return Err(AiError::ConnectionError("refused".into()));
+        }
+        self.inner
+            .start_audio_stream(system_prompt, generation)
+            .await
+    }
+
+    fn name(&self) -> &str {
+        "flaky"
+    }
+}
+
+/// A suspended audio session that fails to reopen stays suspended, and the
+/// next chunk tries again.
+#[tokio::test]
+async fn failed_reopen_keeps_audio_suspended() {
+    let (sm, mut rx, scripted) = scripted_manager(
+        r#"{
+            "vision": [ { "chunks": ["Ok."], "usage": { "input_tokens": 900, "output_tokens": 100 } } ],
+            "audio": { "turns": [ { "afterChunks": 100, "chunks": ["Hi."] } ] }
+        }"#,
+    );
+    let provider = Arc::new(FlakyAudio {
+        inner: scripted,
+        fail_next: AtomicBool::new(false),
+    });
+    sm.configure_provider(provider.clone(), "vision", "audio");
+    sm.set_limits(LimitSettings {
+        tokens_per_hour: 1000,
+        ..Default::default()
+    });
+    sm.start_audio_session().await.unwrap();
+    sm.analyze_frame("aW1n".into(), None);
+    wait_done(&mut rx, 1).await;
+
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+    assert!(sm.limit_status().audio_suspended);
+
+    sm.override_limits(Duration::from_secs(600));
+    provider.fail_next.store(true, Ordering::SeqCst);
+    assert!(sm.process_audio_chunk(&[0; 8]).await.is_err());
+    assert!(!sm.has_audio_session().await);
+    assert!(sm.limit_status().audio_suspended);
+
+    sm.process_audio_chunk(&[0; 8]).await.unwrap();
+    assert!(sm.has_audio_session().await);
+    assert!(!sm.limit_status().audio_suspended);
+    assert_eq!(provider.inner.audio_chunks_received(), 1);
+    sm.stop_audio_session().await.unwrap();
+}
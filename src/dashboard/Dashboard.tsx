@@ -1,4 +1,4 @@
-import { createSignal, onMount, onCleanup } from "solid-js";
+import { createSignal, onMount, onCleanup, Show } from "solid-js";
 import type { UnlistenFn } from "@tauri-apps/api/event";
 import CapturePreview from "./components/CapturePreview";
 import SuggestionPanel from "./components/SuggestionPanel";
@@ -8,29 +8,58 @@ import SettingsPanel from "./components/SettingsPanel";
 import {
   listenCaptureFrame,
   listenAudioLevel,
-  listenToggleCapture,
+  listenCaptureStatus,
   listenAiError,
+  listenAiPaused,
+  listenUsage,
+  listenLimit,
+  listenReplayError,
   type FramePayload,
 } from "../lib/events";
 import {
   toggleCapture,
-  toggleAudioCapture,
-  startAudioAi,
-  stopAudioAi,
+  setAudioEnabled as setBackendAudioEnabled,
+  captureStatus,
   listMonitors,
   selectMonitor,
   listAudioDevices,
   selectAudioDevice,
+  getUsage,
+  limitStatus,
+  overrideLimits,
+  errorMessage,
+  type LimitHit,
   type MonitorInfo,
+  type UsageReport,
   type AudioDeviceInfo,
 } from "../lib/commands";
-import { initSettings } from "./settingsStore";
+import { initSettings, settings, setSettings } from "./settingsStore";
+
+/** How long "Keep going" sets the spending limits aside. */
+const OVERRIDE_MINUTES = 30;
+
+function describeLimit(hit: LimitHit, currency: string): string {
+  const resets =
+    hit.resets_in_secs >= 3600
+      ? `${Math.round(hit.resets_in_secs / 3600)} h`
+      : `${Math.max(1, Math.round(hit.resets_in_secs / 60))} min`;
+  const what =
+    hit.kind === "cost_per_day"
+      ? `Daily cost limit reached (${hit.used.toFixed(2)} of ${hit.limit.toFixed(2)} ${currency})`
+      : hit.kind === "tokens_per_hour"
+        ? `Tokens per hour limit reached (${hit.used.toLocaleString()} of ${hit.limit.toLocaleString()})`
+        : `Requests per minute limit reached (${hit.used} of ${hit.limit})`;
+  return `${what}. The AI is paused; it resumes in about ${resets}.`;
+}
 
 /** Dashboard window — shows capture preview, AI suggestions, and status. */
 function Dashboard() {
   const [isCapturing, setIsCapturing] = createSignal(false);
   const [screenEnabled, setScreenEnabled] = createSignal(true);
   const [audioEnabled, setAudioEnabled] = createSignal(true);
+  const [aiPaused, setAiPaused] = createSignal(false);
+  const [usage, setUsage] = createSignal<UsageReport | null>(null);
+  const [limitHit, setLimitHit] = createSignal<LimitHit | null>(null);
   const [audioLevel, setAudioLevel] = createSignal(0);
   const [frameData, setFrameData] = createSignal<string | null>(null);
   const [filmstrip, setFilmstrip] = createSignal<string[]>([]);
@@ -52,8 +81,33 @@ function Dashboard() {
   const unlisteners: UnlistenFn[] = [];
   let frameTimestamps: number[] = [];
 
+  const refreshUsage = async () => {
+    try {
+      setUsage(await getUsage());
+    } catch (e) {
+      console.error("Failed to read usage:", e);
+    }
+  };
+
   onMount(async () => {
     await initSettings();
+    setSelectedAudioDevice(settings().audioDevice);
+    await refreshUsage();
+
+    try {
+      setLimitHit((await limitStatus()).reached);
+    } catch (e) {
+      console.error("Failed to read limit status:", e);
+    }
+
+    // Pick up capture that's already running (e.g. after a window reload)
+    try {
+      const status = await captureStatus();
+      setIsCapturing(status.screen);
+      setAudioEnabled(status.audio_enabled);
+    } catch (e) {
+      console.error("Failed to read capture status:", e);
+    }
 
     // Load available monitors
     try {
@@ -95,11 +149,23 @@ function Dashboard() {
     );
 
     unlisteners.push(
-      await listenToggleCapture(() => {
-        setIsCapturing((prev) => !prev);
+      // Tray, shortcut and dashboard toggles all report here
+      await listenCaptureStatus((status) => {
+        setIsCapturing(status.screen);
+        setAudioEnabled(status.audio_enabled);
+        // Starting capture starts a new usage session
+        refreshUsage();
       }),
     );
 
+    unlisteners.push(await listenAiPaused((p) => setAiPaused(p.paused)));
+    unlisteners.push(await listenUsage(() => refreshUsage()));
+    unlisteners.push(
+      await listenLimit((p) =>
+        setLimitHit(p.state === "reached" ? p.limit : null),
+      ),
+    );
+
     unlisteners.push(
       await listenAiError((p) => {
         setErrors((prev) => [
@@ -108,54 +174,42 @@ function Dashboard() {
         ]);
       }),
     );
+    unlisteners.push(
+      await listenReplayError((e) => {
+        setErrors((prev) => [
+          ...prev,
+          {
+            id: Date.now(),
+            timestamp: new Date().toISOString(),
+            message: errorMessage(e),
+          },
+        ]);
+      }),
+    );
   });
 
   onCleanup(() => {
     for (const u of unlisteners) u();
   });
 
+  // The backend starts/stops audio (capture + AI session) with the screen
   async function handleToggle() {
     const newState = await toggleCapture();
     setIsCapturing(newState);
-    // Also start/stop audio capture if audio is enabled
-    if (audioEnabled()) {
-      if (newState) {
-        await toggleAudioCapture();
-        try {
-          await startAudioAi();
-        } catch (e) {
-          console.error("Failed to start audio AI:", e);
-        }
-      } else {
-        try {
-          await stopAudioAi();
-        } catch (e) {
-          console.error("Failed to stop audio AI:", e);
-        }
-        await toggleAudioCapture();
-      }
+  }
+
+  async function handleOverride() {
+    try {
+      await overrideLimits(OVERRIDE_MINUTES);
+    } catch (e) {
+      console.error("Failed to override limits:", e);
     }
   }
 
   async function handleAudioToggle() {
     const newEnabled = !audioEnabled();
     setAudioEnabled(newEnabled);
-    if (!isCapturing()) return; // Only start/stop audio if currently capturing
-    if (newEnabled) {
-      await toggleAudioCapture();
-      try {
-        await startAudioAi();
-      } catch (e) {
-        console.error("Failed to start audio AI:", e);
-      }
-    } else {
-      try {
-        await stopAudioAi();
-      } catch (e) {
-        console.error("Failed to stop audio AI:", e);
-      }
-      await toggleAudioCapture();
-    }
+    await setBackendAudioEnabled(newEnabled);
   }
 
   return (
@@ -235,6 +289,8 @@ function Dashboard() {
                 const val = e.currentTarget.value || null;
                 setSelectedAudioDevice(val);
                 await selectAudioDevice(val);
+                // Saved by the backend; keep a later settings save from undoing it
+                setSettings((prev) => ({ ...prev, audioDevice: val }));
               }}
             >
               <option value="">Default Audio</option>
@@ -258,6 +314,21 @@ function Dashboard() {
         </div>
       </header>
 
+      {/* Spending limit banner */}
+      <Show when={limitHit()}>
+        {(hit) => (
+          <div class="flex items-center justify-between gap-4 px-6 py-2 text-sm bg-amber-100 dark:bg-amber-900/40 text-amber-900 dark:text-amber-200 border-b border-amber-300 dark:border-amber-800 shrink-0">
+            <span>{describeLimit(hit(), usage()?.currency ?? "")}</span>
+            <button
+              class="px-2.5 py-1 rounded-md text-xs font-medium bg-amber-600 text-white hover:bg-amber-700 transition-colors"
+              onClick={handleOverride}
+            >
+              Keep going for {OVERRIDE_MINUTES} min
+            </button>
+          </div>
+        )}
+      </Show>
+
       {/* Main content — two-column layout */}
       <main class="flex-1 min-h-0 grid grid-cols-[1fr_1fr_1fr] gap-4 p-4">
         {/* Left: Capture Preview */}
@@ -278,7 +349,14 @@ function Dashboard() {
       <ErrorPanel errors={errors} />
 
       {/* Status bar */}
-      <StatusBar fps={fps} diffPct={diffPct} isCapturing={isCapturing} />
+      <StatusBar
+        fps={fps}
+        diffPct={diffPct}
+        isCapturing={isCapturing}
+        aiPaused={aiPaused}
+        limitReached={() => limitHit() !== null}
+        usage={usage}
+      />
 
       {/* Settings slide-over */}
       <SettingsPanel
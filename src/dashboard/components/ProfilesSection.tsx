@@ -0,0 +1,310 @@
+import { createSignal, For, Index, Show, onMount, onCleanup } from "solid-js";
+import { settings, applyProfilesView } from "../settingsStore";
+import {
+  listProfiles,
+  saveProfile,
+  deleteProfile,
+  activateProfile,
+  setProfileAutoSwitch,
+  currentWindow,
+  errorMessage,
+  type PromptProfile,
+  type ProfilesView,
+} from "../../lib/commands";
+import { listenProfileChanged } from "../../lib/events";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+const inputClass =
+  "w-full bg-gray-700 border border-gray-600 text-white rounded px-3 py-2 focus:outline-none focus:ring-1 focus:ring-blue-500";
+const labelClass = "block text-sm font-medium text-gray-300 mb-1";
+const linkClass = "text-xs text-blue-400 hover:text-blue-300";
+
+/**
+ * Prompt profiles inside the settings panel. Unlike the rest of the panel,
+ * changes here are saved as they're made.
+ */
+function ProfilesSection() {
+  const [view, setView] = createSignal<ProfilesView>({
+    profiles: settings().profiles,
+    active: settings().activeProfile,
+    chosen: settings().activeProfile,
+    auto_switch: settings().autoSwitchProfiles,
+  });
+  // Profile being edited, and the name it was saved under (null = new)
+  const [editing, setEditing] = createSignal<PromptProfile | null>(null);
+  const [originalName, setOriginalName] = createSignal<string | null>(null);
+  const [error, setError] = createSignal("");
+
+  const run = async (action: () => Promise<ProfilesView>) => {
+    try {
+      const v = await action();
+      setView(v);
+      applyProfilesView(v);
+      setError("");
+      return true;
+    } catch (e) {
+      setError(errorMessage(e));
+      return false;
+    }
+  };
+
+  let unlisten: UnlistenFn | undefined;
+  onMount(async () => {
+    run(listProfiles);
+    unlisten = await listenProfileChanged((p) =>
+      setView((prev) => ({ ...prev, active: p.active })),
+    );
+  });
+  onCleanup(() => unlisten?.());
+
+  const startNew = () => {
+    setOriginalName(null);
+    setEditing({
+      name: "",
+      visionPrompt: settings().visionPrompt,
+      audioPrompt: settings().audioPrompt,
+      rules: [],
+    });
+  };
+
+  const startEdit = (profile: PromptProfile) => {
+    setOriginalName(profile.name);
+    setEditing({ ...profile, rules: profile.rules.map((r) => ({ ...r })) });
+  };
+
+  const patch = (partial: Partial<PromptProfile>) =>
+    setEditing((prev) => (prev ? { ...prev, ...partial } : prev));
+
+  const patchRule = (index: number, field: "app" | "title", value: string) =>
+    patch({
+      rules: editing()!.rules.map((r, i) =>
+        i === index ? { ...r, [field]: value } : r,
+      ),
+    });
+
+  const addCurrentWindow = async () => {
+    try {
+      const w = await currentWindow();
+      patch({
+        rules: [...editing()!.rules, { app: w?.app_name ?? "", title: "" }],
+      });
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  };
+
+  const save = async () => {
+    const profile = editing();
+    if (profile && (await run(() => saveProfile(profile, originalName())))) {
+      setEditing(null);
+    }
+  };
+
+  const remove = (name: string) => {
+    if (confirm(`Delete profile "${name}"?`)) run(() => deleteProfile(name));
+  };
+
+  return (
+    <>
+      <div>
+        <label class={labelClass}>Active Profile</label>
+        <select
+          class={inputClass}
+          value={view().chosen ?? ""}
+          onChange={(e) =>
+            run(() => activateProfile(e.currentTarget.value || null))
+          }
+        >
+          <option value="">Default prompts</option>
+          <For each={view().profiles}>
+            {(p) => <option value={p.name}>{p.name}</option>}
+          </For>
+        </select>
+        <Show when={view().auto_switch && view().active !== view().chosen}>
+          <p class="mt-1 text-xs text-gray-500">
+            Now using {view().active ?? "the default prompts"} for the
+            current window
+          </p>
+        </Show>
+      </div>
+
+      <div>
+        <label class="flex items-center gap-2 text-sm text-gray-300 cursor-pointer select-none">
+          <input
+            type="checkbox"
+            class="accent-blue-500"
+            checked={view().auto_switch}
+            onChange={() =>
+              run(() => setProfileAutoSwitch(!view().auto_switch))
+            }
+          />
+          Switch profiles automatically by the focused window
+        </label>
+      </div>
+
+      <For each={view().profiles}>
+        {(p) => (
+          <div class="flex items-center justify-between text-sm text-gray-300">
+            <span>
+              {p.name}
+              <Show when={p.rules.length}>
+                <span class="ml-2 text-xs text-gray-500">
+                  {p.rules.length} rule{p.rules.length === 1 ? "" : "s"}
+                </span>
+              </Show>
+            </span>
+            <span class="flex gap-3">
+              <button
+                type="button"
+                class={linkClass}
+                onClick={() => startEdit(p)}
+              >
+                Edit
+              </button>
+              <button
+                type="button"
+                class="text-xs text-red-400 hover:text-red-300"
+                onClick={() => remove(p.name)}
+              >
+                Delete
+              </button>
+            </span>
+          </div>
+        )}
+      </For>
+
+      <Show
+        when={editing()}
+        fallback={
+          <button type="button" class={linkClass} onClick={startNew}>
+            + New profile
+          </button>
+        }
+      >
+        {(profile) => (
+          <div class="space-y-3 rounded border border-gray-700 p-3">
+            <div>
+              <label class={labelClass}>Name</label>
+              <input
+                type="text"
+                class={inputClass}
+                placeholder="e.g. Sales call"
+                value={profile().name}
+                onInput={(e) => patch({ name: e.currentTarget.value })}
+              />
+            </div>
+
+            <div>
+              <label class={labelClass}>Vision Prompt</label>
+              <textarea
+                class={`${inputClass} min-h-[80px] resize-y`}
+                rows={3}
+                value={profile().visionPrompt}
+                onInput={(e) => patch({ visionPrompt: e.currentTarget.value })}
+              />
+            </div>
+
+            <div>
+              <label class={labelClass}>Audio Prompt</label>
+              <textarea
+                class={`${inputClass} min-h-[80px] resize-y`}
+                rows={3}
+                value={profile().audioPrompt}
+                onInput={(e) => patch({ audioPrompt: e.currentTarget.value })}
+              />
+            </div>
+
+            <div>
+              <label class={labelClass}>Auto-switch Rules</label>
+              <p class="mb-2 text-xs text-gray-500">
+                Used when the focused window's app and title contain these
+                (leave one empty to ignore it).
+              </p>
+              {/* Index keeps the inputs (and focus) while a rule is typed in */}
+              <Index each={profile().rules}>
+                {(rule, i) => (
+                  <div class="mb-2 flex gap-2">
+                    <input
+                      type="text"
+                      class={inputClass}
+                      placeholder="App"
+                      value={rule().app}
+                      onInput={(e) =>
+                        patchRule(i, "app", e.currentTarget.value)
+                      }
+                    />
+                    <input
+                      type="text"
+                      class={inputClass}
+                      placeholder="Title"
+                      value={rule().title}
+                      onInput={(e) =>
+                        patchRule(i, "title", e.currentTarget.value)
+                      }
+                    />
+                    <button
+                      type="button"
+                      class="text-xs text-red-400 hover:text-red-300"
+                      aria-label="Remove rule"
+                      onClick={() =>
+                        patch({
+                          rules: profile().rules.filter((_, j) => j !== i),
+                        })
+                      }
+                    >
+                      ✕
+                    </button>
+                  </div>
+                )}
+              </Index>
+              <div class="flex gap-3">
+                <button
+                  type="button"
+                  class={linkClass}
+                  onClick={() =>
+                    patch({
+                      rules: [...profile().rules, { app: "", title: "" }],
+                    })
+                  }
+                >
+                  + Add rule
+                </button>
+                <button
+                  type="button"
+                  class={linkClass}
+                  title="Add a rule for the app you were last using"
+                  onClick={addCurrentWindow}
+                >
+                  + Current window
+                </button>
+              </div>
+            </div>
+
+            <div class="flex justify-end gap-2">
+              <button
+                type="button"
+                class="px-3 py-1 text-sm rounded-md border border-gray-600 text-gray-300 hover:bg-gray-700"
+                onClick={() => setEditing(null)}
+              >
+                Cancel
+              </button>
+              <button
+                type="button"
+                class="px-3 py-1 text-sm rounded-md bg-blue-600 text-white hover:bg-blue-500"
+                onClick={save}
+              >
+                Save Profile
+              </button>
+            </div>
+          </div>
+        )}
+      </Show>
+
+      <Show when={error()}>
+        <p class="text-xs text-red-400">{error()}</p>
+      </Show>
+    </>
+  );
+}
+
+export default ProfilesSection;
@@ -1,13 +1,45 @@
-import { createSignal, Show } from "solid-js";
+import { createSignal, For, Show } from "solid-js";
 import {
   settings,
   setSettings,
   defaultSettings,
   DEFAULT_VISION_PROMPT,
   DEFAULT_AUDIO_PROMPT,
+  PROMPT_VARIABLES,
+  type BudgetSettings,
+  type GenerationConfig,
+  type LimitSettings,
+  type ModelPricing,
+  type PricingSettings,
   type Settings,
 } from "../settingsStore";
-import { saveSettings, configureAi } from "../../lib/commands";
+import ProfilesSection from "./ProfilesSection";
+import {
+  saveSettings,
+  configureAi,
+  listProfiles,
+  errorMessage,
+  validateShortcuts,
+  type ShortcutAction,
+  type ShortcutIssue,
+} from "../../lib/commands";
+
+const PRICE_FIELDS: { field: keyof ModelPricing; label: string }[] = [
+  { field: "input", label: "Input" },
+  { field: "cachedInput", label: "Cached input" },
+  { field: "output", label: "Output" },
+  { field: "audioInput", label: "Audio input" },
+  { field: "audioOutput", label: "Audio output" },
+];
+
+const SHORTCUT_ACTIONS: { action: ShortcutAction; label: string }[] = [
+  { action: "toggleScreen", label: "Toggle capture" },
+  { action: "toggleAudio", label: "Toggle audio" },
+  { action: "analyzeNow", label: "Analyze now" },
+  { action: "pauseAi", label: "Pause AI" },
+  { action: "copyLastSuggestion", label: "Copy last suggestion" },
+  { action: "toggleDashboard", label: "Show/hide dashboard" },
+];
 
 interface SettingsPanelProps {
   open: () => boolean;
@@ -20,35 +52,89 @@ function SettingsPanel(props: SettingsPanelProps) {
   const [draft, setDraft] = createSignal<Settings>({ ...settings() });
   const [showApiKey, setShowApiKey] = createSignal(false);
   const [toast, setToast] = createSignal("");
+  const [shortcutIssues, setShortcutIssues] = createSignal<ShortcutIssue[]>(
+    [],
+  );
 
   // Reset draft whenever the panel opens
   const isOpen = () => {
     const open = props.open();
-    if (open) setDraft({ ...settings() });
+    if (open) {
+      setDraft({ ...settings() });
+      setShortcutIssues([]);
+    }
     return open;
   };
 
   const patch = (partial: Partial<Settings>) =>
     setDraft((prev) => ({ ...prev, ...partial }));
 
+  const patchShortcut = async (action: ShortcutAction, value: string) => {
+    const shortcuts = { ...draft().shortcuts, [action]: value };
+    patch({ shortcuts });
+    try {
+      setShortcutIssues(await validateShortcuts(shortcuts));
+    } catch (e) {
+      console.error("Failed to validate shortcuts:", e);
+    }
+  };
+
+  const patchGeneration = (partial: Partial<GenerationConfig>) =>
+    patch({ generation: { ...draft().generation, ...partial } });
+
+  const patchBudget = (partial: Partial<BudgetSettings>) =>
+    patch({ budget: { ...draft().budget, ...partial } });
+
+  const patchLimits = (partial: Partial<LimitSettings>) =>
+    patch({ limits: { ...draft().limits, ...partial } });
+
+  const patchPrice = (
+    model: keyof Omit<PricingSettings, "currency">,
+    field: keyof ModelPricing,
+    value: number,
+  ) =>
+    patch({
+      pricing: {
+        ...draft().pricing,
+        [model]: { ...draft().pricing[model], [field]: value },
+      },
+    });
+
+  const issueFor = (action: ShortcutAction) =>
+    shortcutIssues().find((i) => i.action === action);
+
   const handleSave = async () => {
-    const current = { ...draft() };
-    setSettings(current);
     try {
-      await saveSettings(current);
+      // Profiles are saved as they're edited (prompts included, from the
+      // prompt boxes), so take the backend's copy over the draft's
+      const view = await listProfiles();
+      const current: Settings = {
+        ...draft(),
+        profiles: view.profiles,
+        activeProfile: view.chosen,
+        autoSwitchProfiles: view.auto_switch,
+      };
+      setSettings(current);
+      const refused = await saveSettings(current);
       // Also configure the AI provider with the new settings
       if (current.endpoint && (current.apiKey || current.useBearer)) {
+        const live = view.profiles.find((p) => p.name === view.active);
         await configureAi(
           current.endpoint,
           current.apiKey,
           current.visionDeployment,
-          current.visionPrompt,
+          live?.visionPrompt ?? current.visionPrompt,
           current.useBearer,
         );
       }
-      setToast("Saved!");
+      setToast(
+        refused.length
+          ? "Saved, but couldn't register " +
+              refused.map((i) => i.shortcut).join(", ")
+          : "Saved!",
+      );
     } catch (e) {
-      setToast("Save failed: " + String(e));
+      setToast("Save failed: " + errorMessage(e));
     }
     setTimeout(() => setToast(""), 2000);
     props.onClose();
@@ -162,6 +248,318 @@ function SettingsPanel(props: SettingsPanelProps) {
                 Use Bearer Token (Entra ID) instead of API Key
               </label>
             </div>
+
+            <div>
+              <label class="flex items-center gap-2 text-sm text-gray-300 cursor-pointer select-none">
+                <input
+                  type="checkbox"
+                  class="accent-blue-500"
+                  checked={draft().suggestionFormat === "structured"}
+                  onChange={() =>
+                    patch({
+                      suggestionFormat:
+                        draft().suggestionFormat === "structured"
+                          ? "text"
+                          : "structured",
+                    })
+                  }
+                />
+                Structured suggestions (action, rationale, confidence,
+                urgency)
+              </label>
+            </div>
+          </section>
+
+          {/* ── Generation ───────────────────────────────────── */}
+          <section class={sectionClass}>
+            <h3 class={headingClass}>Generation</h3>
+            <p class="text-xs text-gray-500">
+              Sent with every request. Lower output tokens and image detail
+              cut cost and latency.
+            </p>
+
+            <div>
+              <label class={labelClass}>Max Output Tokens</label>
+              <input
+                type="number"
+                class={inputClass}
+                min={16}
+                max={4096}
+                step={16}
+                value={draft().generation.maxOutputTokens}
+                onInput={(e) =>
+                  patchGeneration({
+                    maxOutputTokens:
+                      parseInt(e.currentTarget.value, 10) ||
+                      defaultSettings.generation.maxOutputTokens,
+                  })
+                }
+              />
+            </div>
+
+            <div>
+              <label class={labelClass}>Temperature</label>
+              <input
+                type="number"
+                class={inputClass}
+                min={0}
+                max={2}
+                step={0.1}
+                placeholder="Model default"
+                value={draft().generation.temperature ?? ""}
+                onInput={(e) => {
+                  const t = parseFloat(e.currentTarget.value);
+                  patchGeneration({ temperature: isNaN(t) ? null : t });
+                }}
+              />
+            </div>
+
+            <div>
+              <label class={labelClass}>Reasoning Effort</label>
+              <select
+                class={inputClass}
+                value={draft().generation.reasoningEffort ?? ""}
+                onChange={(e) =>
+                  patchGeneration({
+                    reasoningEffort: (e.currentTarget.value ||
+                      null) as GenerationConfig["reasoningEffort"],
+                  })
+                }
+              >
+                <option value="">Not sent (non-reasoning models)</option>
+                <option value="low">Low</option>
+                <option value="medium">Medium</option>
+                <option value="high">High</option>
+              </select>
+            </div>
+
+            <div>
+              <label class={labelClass}>Image Detail</label>
+              <select
+                class={inputClass}
+                value={draft().generation.imageDetail}
+                onChange={(e) =>
+                  patchGeneration({
+                    imageDetail: e.currentTarget
+                      .value as GenerationConfig["imageDetail"],
+                  })
+                }
+              >
+                <option value="auto">Auto</option>
+                <option value="low">Low (cheapest)</option>
+                <option value="high">High</option>
+              </select>
+            </div>
+
+            <div>
+              <label class={labelClass}>Text Sent With Each Frame</label>
+              <input
+                type="text"
+                class={inputClass}
+                value={draft().generation.userText}
+                onInput={(e) =>
+                  patchGeneration({ userText: e.currentTarget.value })
+                }
+              />
+            </div>
+
+            <div>
+              <label class={labelClass}>Transcription Model</label>
+              <input
+                type="text"
+                class={inputClass}
+                placeholder="Off"
+                value={draft().generation.transcriptionModel}
+                onInput={(e) =>
+                  patchGeneration({
+                    transcriptionModel: e.currentTarget.value,
+                  })
+                }
+              />
+            </div>
+
+            <div>
+              <label class={labelClass}>Image Token Budget (per minute)</label>
+              <input
+                type="number"
+                class={inputClass}
+                min={0}
+                step={500}
+                placeholder="No budget"
+                value={draft().budget.imageTokensPerMinute || ""}
+                onInput={(e) =>
+                  patchBudget({
+                    imageTokensPerMinute:
+                      parseInt(e.currentTarget.value, 10) || 0,
+                  })
+                }
+              />
+              <p class="mt-1 text-xs text-gray-500">
+                Frames are made smaller, then sent at low detail, to stay
+                under it. A 1024 px frame costs about 765 tokens.
+              </p>
+            </div>
+
+            <Show when={draft().budget.imageTokensPerMinute > 0}>
+              <div class="flex gap-2">
+                <div class="flex-1">
+                  <label class={labelClass}>Smallest Width (px)</label>
+                  <input
+                    type="number"
+                    class={inputClass}
+                    min={64}
+                    step={64}
+                    value={draft().budget.minWidth}
+                    onInput={(e) =>
+                      patchBudget({
+                        minWidth:
+                          parseInt(e.currentTarget.value, 10) ||
+                          defaultSettings.budget.minWidth,
+                      })
+                    }
+                  />
+                </div>
+                <div class="flex-1">
+                  <label class={labelClass}>Lowest JPEG Quality</label>
+                  <input
+                    type="number"
+                    class={inputClass}
+                    min={1}
+                    max={100}
+                    value={draft().budget.minJpegQuality}
+                    onInput={(e) =>
+                      patchBudget({
+                        minJpegQuality:
+                          parseInt(e.currentTarget.value, 10) ||
+                          defaultSettings.budget.minJpegQuality,
+                      })
+                    }
+                  />
+                </div>
+              </div>
+            </Show>
+          </section>
+
+          {/* ── Pricing ──────────────────────────────────────── */}
+          <section class={sectionClass}>
+            <h3 class={headingClass}>Pricing</h3>
+
+            <div>
+              <label class={labelClass}>Currency</label>
+              <input
+                type="text"
+                class={inputClass}
+                value={draft().pricing.currency}
+                onInput={(e) =>
+                  patch({
+                    pricing: {
+                      ...draft().pricing,
+                      currency: e.currentTarget.value,
+                    },
+                  })
+                }
+              />
+              <p class="mt-1 text-xs text-gray-500">
+                Prices are per million tokens, as on your Azure price sheet.
+              </p>
+            </div>
+
+            <For each={["vision", "audio"] as const}>
+              {(model) => (
+                <div>
+                  <label class={labelClass}>
+                    {model === "vision" ? "Vision Model" : "Audio Model"}
+                  </label>
+                  <div class="grid grid-cols-3 gap-2">
+                    <For
+                      each={PRICE_FIELDS.filter(
+                        (p) => model === "audio" || !p.field.startsWith("audio"),
+                      )}
+                    >
+                      {({ field, label }) => (
+                        <div>
+                          <span class="text-xs text-gray-500">{label}</span>
+                          <input
+                            type="number"
+                            class={inputClass}
+                            min={0}
+                            step={0.01}
+                            value={draft().pricing[model][field]}
+                            onInput={(e) =>
+                              patchPrice(
+                                model,
+                                field,
+                                parseFloat(e.currentTarget.value) || 0,
+                              )
+                            }
+                          />
+                        </div>
+                      )}
+                    </For>
+                  </div>
+                </div>
+              )}
+            </For>
+          </section>
+
+          {/* ── Spending Limits ──────────────────────────────── */}
+          <section class={sectionClass}>
+            <h3 class={headingClass}>Spending Limits</h3>
+            <p class="text-xs text-gray-500">
+              Once a limit is reached, frames are no longer sent and the audio
+              session is closed until it lifts or you override it. 0 turns a
+              limit off.
+            </p>
+
+            <div class="grid grid-cols-3 gap-2">
+              <div>
+                <span class="text-xs text-gray-500">Requests / minute</span>
+                <input
+                  type="number"
+                  class={inputClass}
+                  min={0}
+                  step={1}
+                  value={draft().limits.requestsPerMinute}
+                  onInput={(e) =>
+                    patchLimits({
+                      requestsPerMinute: parseInt(e.currentTarget.value) || 0,
+                    })
+                  }
+                />
+              </div>
+              <div>
+                <span class="text-xs text-gray-500">Tokens / hour</span>
+                <input
+                  type="number"
+                  class={inputClass}
+                  min={0}
+                  step={1000}
+                  value={draft().limits.tokensPerHour}
+                  onInput={(e) =>
+                    patchLimits({
+                      tokensPerHour: parseInt(e.currentTarget.value) || 0,
+                    })
+                  }
+                />
+              </div>
+              <div>
+                <span class="text-xs text-gray-500">
+                  Cost / day ({draft().pricing.currency})
+                </span>
+                <input
+                  type="number"
+                  class={inputClass}
+                  min={0}
+                  step={0.5}
+                  value={draft().limits.costPerDay}
+                  onInput={(e) =>
+                    patchLimits({
+                      costPerDay: parseFloat(e.currentTarget.value) || 0,
+                    })
+                  }
+                />
+              </div>
+            </div>
           </section>
 
           {/* ── Capture Settings ─────────────────────────────── */}
@@ -231,25 +629,88 @@ function SettingsPanel(props: SettingsPanelProps) {
                 <span>20%</span>
               </div>
             </div>
+
+            <div>
+              <label class="flex items-center gap-2 text-sm text-gray-300 cursor-pointer select-none">
+                <input
+                  type="checkbox"
+                  class="accent-blue-500"
+                  checked={draft().novelty.enabled}
+                  onChange={() =>
+                    patch({
+                      novelty: {
+                        ...draft().novelty,
+                        enabled: !draft().novelty.enabled,
+                      },
+                    })
+                  }
+                />
+                Hide suggestions that repeat one of the last{" "}
+                {draft().novelty.window}
+              </label>
+            </div>
+
+            <Show when={draft().novelty.enabled}>
+              <div>
+                <label class={labelClass}>
+                  Repeat when at least{" "}
+                  {Math.round(draft().novelty.threshold * 100)}% of words match
+                </label>
+                <input
+                  type="range"
+                  class="w-full accent-blue-500"
+                  min={0.5}
+                  max={1}
+                  step={0.05}
+                  value={draft().novelty.threshold}
+                  onInput={(e) =>
+                    patch({
+                      novelty: {
+                        ...draft().novelty,
+                        threshold: parseFloat(e.currentTarget.value),
+                      },
+                    })
+                  }
+                />
+              </div>
+            </Show>
           </section>
 
           {/* ── Shortcuts ────────────────────────────────────── */}
           <section class={sectionClass}>
             <h3 class={headingClass}>Shortcuts</h3>
-            <div>
-              <label class={labelClass}>Global Shortcut</label>
-              <input
-                type="text"
-                class={`${inputClass} cursor-not-allowed opacity-70`}
-                value="Ctrl+Shift+B"
-                readOnly
-              />
-            </div>
+            <p class="text-xs text-gray-500">
+              e.g. ctrl+shift+b or alt+F9. Leave empty to unbind.
+            </p>
+            <For each={SHORTCUT_ACTIONS}>
+              {({ action, label }) => (
+                <div>
+                  <label class={labelClass}>{label}</label>
+                  <input
+                    type="text"
+                    class={inputClass}
+                    placeholder="Not set"
+                    value={draft().shortcuts[action]}
+                    onInput={(e) => patchShortcut(action, e.currentTarget.value)}
+                  />
+                  <Show when={issueFor(action)}>
+                    {(issue) => (
+                      <p class="mt-1 text-xs text-red-400">{issue().message}</p>
+                    )}
+                  </Show>
+                </div>
+              )}
+            </For>
           </section>
 
           {/* ── System Prompts ───────────────────────────────── */}
           <section class={sectionClass}>
             <h3 class={headingClass}>System Prompts</h3>
+            <p class="text-xs text-gray-500">
+              Used when no prompt profile is active. Prompts can include{" "}
+              {PROMPT_VARIABLES.map((v) => `{{${v}}}`).join(", ")}, filled in
+              with each request.
+            </p>
 
             <div>
               <div class="flex items-center justify-between mb-1">
@@ -292,6 +753,23 @@ function SettingsPanel(props: SettingsPanelProps) {
                 onInput={(e) => patch({ audioPrompt: e.currentTarget.value })}
               />
             </div>
+
+            <div>
+              <label class={labelClass}>Notes</label>
+              <textarea
+                class={`${inputClass} min-h-[60px] resize-y`}
+                rows={2}
+                placeholder="About you or your work, for {{user_notes}}"
+                value={draft().userNotes}
+                onInput={(e) => patch({ userNotes: e.currentTarget.value })}
+              />
+            </div>
+          </section>
+
+          {/* ── Prompt Profiles ──────────────────────────────── */}
+          <section class={sectionClass}>
+            <h3 class={headingClass}>Prompt Profiles</h3>
+            <ProfilesSection />
           </section>
         </div>
 
@@ -315,7 +793,8 @@ function SettingsPanel(props: SettingsPanelProps) {
             </button>
             <button
               type="button"
-              class="px-4 py-2 text-sm rounded-md bg-blue-600 text-white hover:bg-blue-500 transition-colors"
+              class="px-4 py-2 text-sm rounded-md bg-blue-600 text-white hover:bg-blue-500 transition-colors disabled:opacity-50"
+              disabled={shortcutIssues().length > 0}
               onClick={handleSave}
             >
               Save
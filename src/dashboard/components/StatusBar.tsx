@@ -1,9 +1,21 @@
 import type { Accessor } from "solid-js";
+import type { UsageReport } from "../../lib/commands";
 
 interface StatusBarProps {
   fps: Accessor<number>;
   diffPct: Accessor<number>;
   isCapturing: Accessor<boolean>;
+  aiPaused: Accessor<boolean>;
+  limitReached: Accessor<boolean>;
+  usage: Accessor<UsageReport | null>;
+}
+
+function formatTokens(n: number): string {
+  return n >= 10_000 ? `${(n / 1000).toFixed(0)}k` : n.toLocaleString();
+}
+
+function formatCost(amount: number, currency: string): string {
+  return `${amount.toFixed(amount < 1 ? 3 : 2)} ${currency}`;
 }
 
 /** Fixed status bar at the bottom of the dashboard. */
@@ -11,14 +23,30 @@ function StatusBar(props: StatusBarProps) {
   return (
     <footer class="flex items-center justify-between px-6 py-1.5 text-xs text-zinc-500 dark:text-zinc-400 border-t border-zinc-200 dark:border-zinc-700 bg-white dark:bg-zinc-900 shrink-0 select-none">
       <span>
-        FPS: {props.fps()} | Diff: {props.diffPct().toFixed(1)}% | Tokens: — |
-        Cost: —
+        FPS: {props.fps()} | Diff: {props.diffPct().toFixed(1)}% | Tokens:{" "}
+        {(() => {
+          const u = props.usage();
+          if (!u) return "—";
+          return formatTokens(u.session.input_tokens + u.session.output_tokens);
+        })()}{" "}
+        | Cost:{" "}
+        {(() => {
+          const u = props.usage();
+          if (!u) return "—";
+          const rate =
+            u.cost_per_hour === null
+              ? ""
+              : ` (${formatCost(u.cost_per_hour, u.currency)}/h)`;
+          return `${formatCost(u.session.cost, u.currency)}${rate} · today ${formatCost(u.today.cost, u.currency)}`;
+        })()}
       </span>
       <span class="flex items-center gap-1.5">
         <span
           class={`inline-block w-2 h-2 rounded-full ${props.isCapturing() ? "bg-green-500" : "bg-orange-400"}`}
         />
         {props.isCapturing() ? "Capturing" : "Idle"}
+        {props.aiPaused() ? " · AI paused" : ""}
+        {props.limitReached() ? " · limit reached" : ""}
       </span>
       <span>beme v0.1.0</span>
     </footer>
@@ -4,11 +4,29 @@ import {
   listenAiSuggestion,
   listenAiError,
   listenAudioStatus,
+  listenThread,
+  listenProfileChanged,
+  listenStructuredSuggestion,
+  listenSuggestionSuppressed,
   type SuggestionPayload,
   type AiErrorPayload,
   type AudioStatusPayload,
+  type ThreadPayload,
+  type StructuredSuggestionPayload,
+  type SuggestionSuppressedPayload,
 } from "../../lib/events";
-import { getPrompts, updatePrompt } from "../../lib/commands";
+import {
+  getPrompts,
+  updatePrompt,
+  listProfiles,
+  analyzeNow,
+  askFollowup,
+  resetThread,
+  forkThread,
+  errorHint,
+  errorMessage,
+  type ImageDetail,
+} from "../../lib/commands";
 import type { UnlistenFn } from "@tauri-apps/api/event";
 
 interface Suggestion {
@@ -17,20 +35,56 @@ interface Suggestion {
   text: string;
   source: "screen" | "audio";
   done: boolean;
+  question?: string;
+  parentId?: number;
+  threadId?: number;
+  /** Typed fields, when structured suggestions are on. */
+  structured?: Pick<
+    StructuredSuggestionPayload,
+    "rationale" | "confidence" | "urgency" | "target_app"
+  >;
+  /** Set when this repeats an earlier suggestion; shown collapsed. */
+  repeats?: Pick<SuggestionSuppressedPayload, "duplicate_of" | "similarity">;
+}
+
+const URGENCY_RANK = { high: 2, medium: 1, low: 0 } as const;
+
+/** Most urgent first, then most confident; free-text suggestions last. */
+function byPriority(a: Suggestion, b: Suggestion): number {
+  if (!a.structured || !b.structured) {
+    return Number(!!b.structured) - Number(!!a.structured);
+  }
+  return (
+    URGENCY_RANK[b.structured.urgency] - URGENCY_RANK[a.structured.urgency] ||
+    b.structured.confidence - a.structured.confidence
+  );
 }
 
+const THREAD_REASONS: Record<ThreadPayload["reason"], string> = {
+  capture: "capture started",
+  monitor: "monitor changed",
+  prompt: "prompt changed",
+  provider: "provider changed",
+  reset: "started fresh",
+  fork: "forked",
+};
+
 const MAX_SUGGESTIONS = 20;
 
 /** Right columns: side-by-side Screen & Audio AI suggestion panels. */
 function SuggestionPanel() {
   const [suggestions, setSuggestions] = createSignal<Suggestion[]>([]);
   const [aiErrors, setAiErrors] = createSignal<
-    { message: string; timestamp: string }[]
+    { message: string; timestamp: string; hint: string | null }[]
   >([]);
   const [audioStatus, setAudioStatus] = createSignal<AudioStatusPayload>({
     status: "disconnected",
     message: null,
   });
+  const [thread, setThread] = createSignal<ThreadPayload | null>(null);
+  const [sortByPriority, setSortByPriority] = createSignal(false);
+  /** Live prompt profile; null = the default prompts. */
+  const [profile, setProfile] = createSignal<string | null>(null);
   const [visionPrompt, setVisionPrompt] = createSignal("");
   const [audioPrompt, setAudioPrompt] = createSignal("");
   const [visionSaveStatus, setVisionSaveStatus] = createSignal<
@@ -39,6 +93,9 @@ function SuggestionPanel() {
   const [audioSaveStatus, setAudioSaveStatus] = createSignal<
     "idle" | "saving" | "saved"
   >("idle");
+  // Why the last save was refused (e.g. an unknown {{variable}})
+  const [visionSaveError, setVisionSaveError] = createSignal("");
+  const [audioSaveError, setAudioSaveError] = createSignal("");
 
   let visionTimer: ReturnType<typeof setTimeout> | undefined;
   let audioTimer: ReturnType<typeof setTimeout> | undefined;
@@ -56,10 +113,12 @@ function SuggestionPanel() {
       visionTimer = setTimeout(async () => {
         try {
           await updatePrompt(source, value);
+          setVisionSaveError("");
           setVisionSaveStatus("saved");
           setTimeout(() => setVisionSaveStatus("idle"), 2000);
         } catch (e) {
           console.error("Failed to save prompt:", e);
+          setVisionSaveError(errorMessage(e));
           setVisionSaveStatus("idle");
         }
       }, 500);
@@ -68,19 +127,19 @@ function SuggestionPanel() {
       audioTimer = setTimeout(async () => {
         try {
           await updatePrompt(source, value);
+          setAudioSaveError("");
           setAudioSaveStatus("saved");
           setTimeout(() => setAudioSaveStatus("idle"), 2000);
         } catch (e) {
           console.error("Failed to save prompt:", e);
+          setAudioSaveError(errorMessage(e));
           setAudioSaveStatus("idle");
         }
       }, 500);
     }
   }
 
-  const unlisteners: UnlistenFn[] = [];
-
-  onMount(async () => {
+  async function loadPrompts() {
     try {
       const prompts = await getPrompts();
       setVisionPrompt(prompts.vision);
@@ -88,44 +147,117 @@ function SuggestionPanel() {
     } catch (e) {
       console.warn("Failed to load prompts:", e);
     }
+  }
+
+  const unlisteners: UnlistenFn[] = [];
+
+  onMount(async () => {
+    await loadPrompts();
+    listProfiles()
+      .then((v) => setProfile(v.active))
+      .catch((e) => console.warn("Failed to load profiles:", e));
+
+    /** Update suggestion `id`, adding it (from `create`) if it's new. */
+    const upsert = (
+      id: number,
+      create: () => Suggestion,
+      update: (s: Suggestion) => Suggestion,
+    ) =>
+      setSuggestions((prev) => {
+        const updated = prev.some((s) => s.id === id)
+          ? prev.map((s) => (s.id === id ? update(s) : s))
+          : [create(), ...prev];
+        return updated.slice(0, MAX_SUGGESTIONS);
+      });
 
     unlisteners.push(
       await listenAiSuggestion((p: SuggestionPayload) => {
-        setSuggestions((prev) => {
-          const existing = prev.find((s) => s.id === p.id);
-          let updated: Suggestion[];
-          if (existing) {
-            updated = prev.map((s) =>
-              s.id === p.id ? { ...s, text: s.text + p.text, done: p.done } : s,
-            );
-          } else {
-            updated = [
-              {
-                id: p.id,
-                timestamp: p.timestamp,
-                text: p.text,
-                source: p.source as "screen" | "audio",
-                done: p.done,
-              },
-              ...prev,
-            ];
-          }
-          return updated.slice(0, MAX_SUGGESTIONS);
-        });
+        upsert(
+          p.id,
+          () => ({
+            id: p.id,
+            timestamp: p.timestamp,
+            text: p.text,
+            source: p.source as "screen" | "audio",
+            done: p.done,
+            question: p.question,
+            parentId: p.parent_id,
+            threadId: p.thread_id,
+          }),
+          (s) => ({ ...s, text: s.text + p.text, done: p.done }),
+        );
+      }),
+    );
+
+    // Arrives just before the suggestion's text
+    unlisteners.push(
+      await listenStructuredSuggestion((p: StructuredSuggestionPayload) => {
+        const structured = {
+          rationale: p.rationale,
+          confidence: p.confidence,
+          urgency: p.urgency,
+          target_app: p.target_app,
+        };
+        upsert(
+          p.id,
+          () => ({
+            id: p.id,
+            timestamp: p.timestamp,
+            text: "",
+            source: "screen",
+            done: false,
+            question: p.question,
+            threadId: p.thread_id,
+            structured,
+          }),
+          (s) => ({ ...s, structured }),
+        );
       }),
     );
 
     unlisteners.push(
       await listenAiError((p: AiErrorPayload) => {
         setAiErrors((prev) =>
-          [{ message: p.message, timestamp: p.timestamp }, ...prev].slice(
-            0,
-            10,
+          [
+            { message: p.message, timestamp: p.timestamp, hint: errorHint(p) },
+            ...prev,
+          ].slice(0, 10),
+        );
+      }),
+    );
+
+    // Withheld structured repeats never reached the list; free-text ones
+    // already streamed and are collapsed.
+    unlisteners.push(
+      await listenSuggestionSuppressed((p: SuggestionSuppressedPayload) => {
+        setSuggestions((prev) =>
+          prev.map((s) =>
+            s.id === p.id
+              ? {
+                  ...s,
+                  repeats: {
+                    duplicate_of: p.duplicate_of,
+                    similarity: p.similarity,
+                  },
+                }
+              : s,
           ),
         );
       }),
     );
 
+    unlisteners.push(await listenThread(setThread));
+
+    unlisteners.push(
+      await listenProfileChanged((p) => {
+        // Edits still pending were meant for the previous profile
+        clearTimeout(visionTimer);
+        clearTimeout(audioTimer);
+        setProfile(p.active);
+        loadPrompts();
+      }),
+    );
+
     unlisteners.push(
       await listenAudioStatus((p: AudioStatusPayload) => {
         setAudioStatus(p);
@@ -137,8 +269,74 @@ function SuggestionPanel() {
     for (const u of unlisteners) u();
   });
 
-  const screenSuggestions = () =>
-    suggestions().filter((s) => s.source === "screen");
+  function reportError(e: unknown) {
+    setAiErrors((prev) =>
+      [
+        {
+          message: errorMessage(e),
+          timestamp: new Date().toISOString(),
+          hint: null,
+        },
+        ...prev,
+      ].slice(0, 10),
+    );
+  }
+
+  async function handleAsk(question: string, detail: ImageDetail | null) {
+    try {
+      await analyzeNow(question, detail);
+    } catch (e) {
+      reportError(e);
+    }
+  }
+
+  async function handleFollowup(suggestionId: number, text: string) {
+    try {
+      await askFollowup(suggestionId, text);
+    } catch (e) {
+      setAiErrors((prev) =>
+        [
+          {
+            message: errorMessage(e),
+            timestamp: new Date().toISOString(),
+            hint: null,
+          },
+          ...prev,
+        ].slice(0, 10),
+      );
+    }
+  }
+
+  async function handleNewThread() {
+    try {
+      await resetThread();
+    } catch (e) {
+      reportError(e);
+    }
+  }
+
+  async function handleFork(suggestionId: number) {
+    try {
+      await forkThread(suggestionId);
+    } catch (e) {
+      reportError(e);
+    }
+  }
+
+  const threadLabel = () => {
+    const t = thread();
+    if (!t) return null;
+    const from = t.forked_from ? ` from #${t.forked_from}` : "";
+    return `Thread ${t.thread_id} · ${THREAD_REASONS[t.reason]}${from}`;
+  };
+
+  const profileLabel = (label: string) =>
+    profile() ? `${label} · ${profile()}` : label;
+
+  const screenSuggestions = () => {
+    const screen = suggestions().filter((s) => s.source === "screen");
+    return sortByPriority() ? [...screen].sort(byPriority) : screen;
+  };
   const audioSuggestions = () =>
     suggestions().filter((s) => s.source === "audio");
 
@@ -150,14 +348,41 @@ function SuggestionPanel() {
           prompt={visionPrompt()}
           onPromptChange={(v) => handlePromptChange("vision", v)}
           saveStatus={visionSaveStatus()}
-          label="Screen Prompt"
+          saveError={visionSaveError()}
+          label={profileLabel("Screen Prompt")}
           accentColor="text-purple-600 dark:text-purple-400"
         />
+        <AskBox onAsk={handleAsk} />
         <SuggestionColumn
           title="Screen"
           titleColor="text-purple-600 dark:text-purple-400"
           items={screenSuggestions()}
           errors={aiErrors()}
+          onFollowup={handleFollowup}
+          onFork={handleFork}
+          headerAction={
+            <>
+              <Show when={threadLabel()}>
+                <span class="text-[10px] text-zinc-400">{threadLabel()}</span>
+              </Show>
+              <Show when={screenSuggestions().some((s) => s.structured)}>
+                <button
+                  class="text-[10px] px-1.5 py-0.5 rounded border border-zinc-300 dark:border-zinc-600 text-zinc-600 dark:text-zinc-300 hover:bg-zinc-100 dark:hover:bg-zinc-700"
+                  title="Order by urgency and confidence, or by time"
+                  onClick={() => setSortByPriority(!sortByPriority())}
+                >
+                  {sortByPriority() ? "Priority" : "Newest"}
+                </button>
+              </Show>
+              <button
+                class="text-[10px] px-1.5 py-0.5 rounded border border-zinc-300 dark:border-zinc-600 text-zinc-600 dark:text-zinc-300 hover:bg-zinc-100 dark:hover:bg-zinc-700"
+                title="Analyze the next frame without earlier context"
+                onClick={handleNewThread}
+              >
+                New thread
+              </button>
+            </>
+          }
         />
       </div>
 
@@ -167,7 +392,8 @@ function SuggestionPanel() {
           prompt={audioPrompt()}
           onPromptChange={(v) => handlePromptChange("audio", v)}
           saveStatus={audioSaveStatus()}
-          label="Audio Prompt"
+          saveError={audioSaveError()}
+          label={profileLabel("Audio Prompt")}
           accentColor="text-teal-600 dark:text-teal-400"
         />
         <SuggestionColumn
@@ -192,6 +418,7 @@ function PromptEditor(props: {
   prompt: string;
   onPromptChange: (value: string) => void;
   saveStatus: "idle" | "saving" | "saved";
+  saveError: string;
   label: string;
   accentColor: string;
 }) {
@@ -220,21 +447,79 @@ function PromptEditor(props: {
           rows={2}
           value={props.prompt}
           onInput={(e) => props.onPromptChange(e.currentTarget.value)}
-          placeholder="System prompt..."
+          placeholder="System prompt... ({{active_window}}, {{time}}, … are filled in)"
         />
+        <Show when={props.saveError}>
+          <p class="mt-1 text-[10px] text-red-500">{props.saveError}</p>
+        </Show>
       </div>
     </div>
   );
 }
 
+/**
+ * Ask about the current screen now, with an optional question and image
+ * detail level for just this frame.
+ */
+function AskBox(props: {
+  onAsk: (question: string, detail: ImageDetail | null) => Promise<void>;
+}) {
+  const [question, setQuestion] = createSignal("");
+  const [detail, setDetail] = createSignal<ImageDetail | null>(null);
+  const [busy, setBusy] = createSignal(false);
+
+  const submit = async (e: Event) => {
+    e.preventDefault();
+    setBusy(true);
+    await props.onAsk(question().trim(), detail());
+    setQuestion("");
+    setBusy(false);
+  };
+
+  return (
+    <form class="shrink-0 flex gap-2" onSubmit={submit}>
+      <input
+        type="text"
+        class="flex-1 min-w-0 text-xs bg-white dark:bg-zinc-900 border border-zinc-300 dark:border-zinc-600 rounded px-2 py-1.5 focus:outline-none focus:ring-1 focus:ring-blue-400"
+        placeholder="Ask about the screen (optional)…"
+        value={question()}
+        onInput={(e) => setQuestion(e.currentTarget.value)}
+      />
+      <select
+        class="shrink-0 text-xs bg-white dark:bg-zinc-900 border border-zinc-300 dark:border-zinc-600 rounded px-1 py-1.5 focus:outline-none focus:ring-1 focus:ring-blue-400"
+        title="Image detail for this frame"
+        value={detail() ?? ""}
+        onChange={(e) =>
+          setDetail((e.currentTarget.value || null) as ImageDetail | null)
+        }
+      >
+        <option value="">Default detail</option>
+        <option value="low">Low detail</option>
+        <option value="high">High detail</option>
+        <option value="auto">Auto detail</option>
+      </select>
+      <button
+        type="submit"
+        class="shrink-0 px-3 py-1.5 text-xs font-medium rounded bg-purple-600 text-white hover:bg-purple-500 disabled:opacity-50"
+        disabled={busy()}
+      >
+        Analyze now
+      </button>
+    </form>
+  );
+}
+
 /** A single suggestion column with header, optional status, and scrollable list. */
 function SuggestionColumn(props: {
   title: string;
   titleColor: string;
   items: Suggestion[];
-  errors: { message: string; timestamp: string }[];
+  errors: { message: string; timestamp: string; hint: string | null }[];
   statusDot?: any;
   statusBanner?: string | null;
+  headerAction?: any;
+  onFollowup?: (suggestionId: number, text: string) => Promise<void>;
+  onFork?: (suggestionId: number) => Promise<void>;
 }) {
   return (
     <section class="flex flex-col min-h-0 flex-1 rounded-lg border border-zinc-200 dark:border-zinc-700 bg-zinc-50 dark:bg-zinc-800/50">
@@ -244,6 +529,11 @@ function SuggestionColumn(props: {
           {props.title}
         </span>
         {props.statusDot}
+        <Show when={props.headerAction}>
+          <div class="ml-auto flex items-center gap-2">
+            {props.headerAction}
+          </div>
+        </Show>
       </div>
 
       {/* Status error banner */}
@@ -261,6 +551,11 @@ function SuggestionColumn(props: {
               <div class="text-xs text-red-600 dark:text-red-400 mb-1">
                 <span class="text-zinc-400 mr-2">{err.timestamp}</span>
                 {err.message}
+                <Show when={err.hint}>
+                  <span class="ml-2 text-zinc-500 dark:text-zinc-400">
+                    {err.hint}
+                  </span>
+                </Show>
               </div>
             )}
           </For>
@@ -279,7 +574,13 @@ function SuggestionColumn(props: {
         >
           <ul class="flex flex-col gap-2">
             <For each={props.items}>
-              {(item) => <SuggestionEntry suggestion={item} />}
+              {(item) => (
+                <SuggestionEntry
+                  suggestion={item}
+                  onFollowup={props.onFollowup}
+                  onFork={props.onFork}
+                />
+              )}
             </For>
           </ul>
         </Show>
@@ -311,37 +612,129 @@ function AudioStatusDot(props: { status: string }) {
   );
 }
 
-function SuggestionEntry(props: { suggestion: Suggestion }) {
+function SuggestionEntry(props: {
+  suggestion: Suggestion;
+  onFollowup?: (suggestionId: number, text: string) => Promise<void>;
+  onFork?: (suggestionId: number) => Promise<void>;
+}) {
+  const [followup, setFollowup] = createSignal("");
+  const [expanded, setExpanded] = createSignal(false);
+
+  const submitFollowup = async (e: Event) => {
+    e.preventDefault();
+    const text = followup().trim();
+    if (!text || !props.onFollowup) return;
+    setFollowup("");
+    await props.onFollowup(props.suggestion.id, text);
+  };
+
   return (
-    <li class="rounded-md border border-zinc-200 dark:border-zinc-700 bg-white dark:bg-zinc-800 p-3 text-sm">
-      <div class="flex items-center justify-between mb-1">
-        <span class="text-[10px] text-zinc-400">
-          {props.suggestion.timestamp}
-        </span>
-        <div class="flex items-center gap-1.5">
-          <Show when={!props.suggestion.done}>
-            <span class="text-[10px] text-yellow-600 dark:text-yellow-400 animate-pulse">
-              streaming…
-            </span>
-          </Show>
-          <span
-            class={`text-[10px] font-medium px-1.5 py-0.5 rounded ${
-              props.suggestion.source === "screen"
-                ? "bg-purple-100 text-purple-700 dark:bg-purple-900 dark:text-purple-300"
-                : "bg-teal-100 text-teal-700 dark:bg-teal-900 dark:text-teal-300"
-            }`}
+    <Show
+      when={!props.suggestion.repeats || expanded()}
+      fallback={
+        <li class="rounded-md border border-dashed border-zinc-200 dark:border-zinc-700 px-3 py-1 text-[10px] text-zinc-400">
+          <button
+            class="hover:text-zinc-600 dark:hover:text-zinc-300"
+            title="Show the repeated suggestion"
+            onClick={() => setExpanded(true)}
           >
-            {props.suggestion.source}
+            Repeats #{props.suggestion.repeats!.duplicate_of} (
+            {Math.round(props.suggestion.repeats!.similarity * 100)}% similar)
+          </button>
+        </li>
+      }
+    >
+      <li class="rounded-md border border-zinc-200 dark:border-zinc-700 bg-white dark:bg-zinc-800 p-3 text-sm">
+        <div class="flex items-center justify-between mb-1">
+          <span class="text-[10px] text-zinc-400">
+            {props.suggestion.timestamp}
+            <Show when={props.suggestion.threadId}>
+              <span class="ml-1.5">thread {props.suggestion.threadId}</span>
+            </Show>
+            <Show when={props.suggestion.parentId}>
+              <span class="ml-1.5">↳ follow-up to #{props.suggestion.parentId}</span>
+            </Show>
           </span>
+          <div class="flex items-center gap-1.5">
+            <Show when={props.onFork && props.suggestion.done}>
+              <button
+                class="text-[10px] text-zinc-400 hover:text-purple-600 dark:hover:text-purple-400"
+                title="Continue capture from this answer"
+                onClick={() => props.onFork?.(props.suggestion.id)}
+              >
+                continue from here
+              </button>
+            </Show>
+            <Show when={!props.suggestion.done}>
+              <span class="text-[10px] text-yellow-600 dark:text-yellow-400 animate-pulse">
+                streaming…
+              </span>
+            </Show>
+            <span
+              class={`text-[10px] font-medium px-1.5 py-0.5 rounded ${
+                props.suggestion.source === "screen"
+                  ? "bg-purple-100 text-purple-700 dark:bg-purple-900 dark:text-purple-300"
+                  : "bg-teal-100 text-teal-700 dark:bg-teal-900 dark:text-teal-300"
+              }`}
+            >
+              {props.suggestion.source}
+            </span>
+          </div>
         </div>
-      </div>
-      <div
-        class="text-zinc-700 dark:text-zinc-300 suggestion-prose text-sm"
-        innerHTML={
-          marked.parse(props.suggestion.text, { async: false }) as string
-        }
-      />
-    </li>
+        <Show when={props.suggestion.question}>
+          <p class="mb-1 text-xs italic text-zinc-500 dark:text-zinc-400">
+            “{props.suggestion.question}”
+          </p>
+        </Show>
+        <div
+          class="text-zinc-700 dark:text-zinc-300 suggestion-prose text-sm"
+          innerHTML={
+            marked.parse(props.suggestion.text, { async: false }) as string
+          }
+        />
+        <Show when={props.suggestion.structured}>
+          {(fields) => (
+            <div class="mt-1.5 flex flex-col gap-1">
+              <Show when={fields().rationale}>
+                <p class="text-xs text-zinc-500 dark:text-zinc-400">
+                  {fields().rationale}
+                </p>
+              </Show>
+              <div class="flex flex-wrap items-center gap-1.5 text-[10px]">
+                <span
+                  class={`px-1.5 py-0.5 rounded font-medium ${
+                    fields().urgency === "high"
+                      ? "bg-red-100 text-red-700 dark:bg-red-900 dark:text-red-300"
+                      : fields().urgency === "medium"
+                        ? "bg-amber-100 text-amber-700 dark:bg-amber-900 dark:text-amber-300"
+                        : "bg-zinc-100 text-zinc-600 dark:bg-zinc-700 dark:text-zinc-300"
+                  }`}
+                >
+                  {fields().urgency}
+                </span>
+                <span class="text-zinc-400">
+                  {Math.round(fields().confidence * 100)}% sure
+                </span>
+                <Show when={fields().target_app}>
+                  <span class="text-zinc-400">· {fields().target_app}</span>
+                </Show>
+              </div>
+            </div>
+          )}
+        </Show>
+        <Show when={props.onFollowup && props.suggestion.done}>
+          <form class="mt-2" onSubmit={submitFollowup}>
+            <input
+              type="text"
+              class="w-full text-xs bg-zinc-50 dark:bg-zinc-900 border border-zinc-200 dark:border-zinc-700 rounded px-2 py-1 focus:outline-none focus:ring-1 focus:ring-blue-400"
+              placeholder="Ask a follow-up… (why? how exactly?)"
+              value={followup()}
+              onInput={(e) => setFollowup(e.currentTarget.value)}
+            />
+          </form>
+        </Show>
+      </li>
+    </Show>
   );
 }
 
@@ -1,5 +1,10 @@
 import { createSignal } from "solid-js";
-import { loadSettings as loadSettingsCmd } from "../lib/commands";
+import {
+  loadSettings as loadSettingsCmd,
+  type AudioStreamConfig,
+  type PromptProfile,
+  type ProfilesView,
+} from "../lib/commands";
 
 export const DEFAULT_VISION_PROMPT =
   "You are an AI assistant observing my screen. Analyze what you see and suggest the single best next action I should take. Be specific and actionable.";
@@ -7,7 +12,93 @@ export const DEFAULT_VISION_PROMPT =
 export const DEFAULT_AUDIO_PROMPT =
   "You are listening to a conversation. Suggest the best response or follow-up question.";
 
+/** Variables prompts can use as `{{name}}`, filled in per request. */
+export const PROMPT_VARIABLES = [
+  "active_window",
+  "time",
+  "monitor_name",
+  "recent_transcript",
+  "last_suggestion",
+  "user_notes",
+] as const;
+
+export interface RecordingSettings {
+  enabled: boolean;
+  maxSegmentSecs: number;
+  maxSegmentMb: number;
+  maxSegments: number;
+}
+
+/** Global shortcut per action, e.g. "ctrl+shift+b"; "" = unbound. */
+export interface ShortcutSettings {
+  toggleScreen: string;
+  toggleAudio: string;
+  analyzeNow: string;
+  pauseAi: string;
+  copyLastSuggestion: string;
+  toggleDashboard: string;
+}
+
+export interface NoveltySettings {
+  enabled: boolean;
+  /** Word overlap (0–1) at or above which a suggestion is a repeat. */
+  threshold: number;
+  /** How many recent suggestions to compare against. */
+  window: number;
+}
+
+/** Output length, sampling, image detail and transcription per request. */
+export interface GenerationConfig {
+  maxOutputTokens: number;
+  /** 0–2; null uses the model's default (reasoning models take none). */
+  temperature: number | null;
+  /** Reasoning models only; null leaves it out. */
+  reasoningEffort: "low" | "medium" | "high" | null;
+  imageDetail: "auto" | "low" | "high";
+  /** Sent with each frame when no question was asked. */
+  userText: string;
+  /** Audio transcription model; "" turns transcription off. */
+  transcriptionModel: string;
+}
+
+/** Per-minute image token budget frames are shrunk to fit. */
+export interface BudgetSettings {
+  /** 0 = no budget. */
+  imageTokensPerMinute: number;
+  /** Frames are never shrunk below this width. */
+  minWidth: number;
+  /** JPEG quality for the smallest frames (1–100). */
+  minJpegQuality: number;
+}
+
+/** Prices per million tokens. */
+export interface ModelPricing {
+  input: number;
+  cachedInput: number;
+  output: number;
+  audioInput: number;
+  audioOutput: number;
+}
+
+/** What tokens cost, for usage reports. */
+export interface PricingSettings {
+  currency: string;
+  vision: ModelPricing;
+  audio: ModelPricing;
+}
+
+/** Caps that pause the AI pipeline once reached; 0 turns one off. */
+export interface LimitSettings {
+  requestsPerMinute: number;
+  tokensPerHour: number;
+  /** In the pricing currency, per UTC day. */
+  costPerDay: number;
+}
+
 export interface Settings {
+  // Provider: "azure", or "scripted" to replay a JSON fixture
+  provider: string;
+  scriptedFixture: string;
   // Azure Connection
   endpoint: string;
   apiKey: string;
@@ -18,12 +109,36 @@ export interface Settings {
   captureInterval: number;
   screenshotMaxWidth: number;
   frameDiffThreshold: number;
-  // System Prompts
+  // System Prompts (the defaults, when no profile is active)
   visionPrompt: string;
   audioPrompt: string;
+  // Free-form notes prompts can include as {{user_notes}}
+  userNotes: string;
+  // Named prompt presets; activeProfile is the one chosen by hand
+  profiles: PromptProfile[];
+  activeProfile: string | null;
+  autoSwitchProfiles: boolean;
+  // "structured" asks for JSON-schema answers (action, rationale, …)
+  suggestionFormat: "text" | "structured";
+  // Model request parameters
+  generation: GenerationConfig;
+  budget: BudgetSettings;
+  pricing: PricingSettings;
+  limits: LimitSettings;
+  // Hiding capture suggestions that repeat recent ones
+  novelty: NoveltySettings;
+  // Audio device (null = system default) and config chosen per device
+  audioDevice: string | null;
+  audioConfigs: Record<string, AudioStreamConfig>;
+  // Audio recording
+  recording: RecordingSettings;
+  // Global shortcuts
+  shortcuts: ShortcutSettings;
 }
 
 export const defaultSettings: Settings = {
+  provider: "azure",
+  scriptedFixture: "",
   endpoint: "",
   apiKey: "",
   visionDeployment: "gpt-4o",
@@ -34,6 +149,67 @@ export const defaultSettings: Settings = {
   frameDiffThreshold: 5,
   visionPrompt: DEFAULT_VISION_PROMPT,
   audioPrompt: DEFAULT_AUDIO_PROMPT,
+  userNotes: "",
+  profiles: [],
+  activeProfile: null,
+  autoSwitchProfiles: false,
+  suggestionFormat: "text",
+  generation: {
+    maxOutputTokens: 300,
+    temperature: null,
+    reasoningEffort: null,
+    imageDetail: "auto",
+    userText: "What do you see?",
+    transcriptionModel: "whisper-1",
+  },
+  budget: {
+    imageTokensPerMinute: 0,
+    minWidth: 512,
+    minJpegQuality: 40,
+  },
+  pricing: {
+    currency: "USD",
+    vision: {
+      input: 2.5,
+      cachedInput: 1.25,
+      output: 10,
+      audioInput: 0,
+      audioOutput: 0,
+    },
+    audio: {
+      input: 5,
+      cachedInput: 2.5,
+      output: 20,
+      audioInput: 40,
+      audioOutput: 80,
+    },
+  },
+  limits: {
+    requestsPerMinute: 0,
+    tokensPerHour: 0,
+    costPerDay: 0,
+  },
+  novelty: {
+    enabled: false,
+    threshold: 0.75,
+    window: 5,
+  },
+  audioDevice: null,
+  audioConfigs: {},
+  recording: {
+    enabled: false,
+    maxSegmentSecs: 600,
+    maxSegmentMb: 100,
+    maxSegments: 0,
+  },
+  shortcuts: {
+    toggleScreen: "ctrl+shift+b",
+    toggleAudio: "",
+    analyzeNow: "",
+    pauseAi: "",
+    copyLastSuggestion: "",
+    toggleDashboard: "",
+  },
 };
 
 const [settings, setSettings] = createSignal<Settings>({ ...defaultSettings });
@@ -48,4 +224,14 @@ export async function initSettings(): Promise<void> {
   }
 }
 
+/** Mirror what a profile command saved, so a later settings save keeps it. */
+export function applyProfilesView(view: ProfilesView): void {
+  setSettings((prev) => ({
+    ...prev,
+    profiles: view.profiles,
+    activeProfile: view.chosen,
+    autoSwitchProfiles: view.auto_switch,
+  }));
+}
+
 export { settings, setSettings };
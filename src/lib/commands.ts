@@ -1,5 +1,68 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Settings } from "../dashboard/settingsStore";
+import type {
+  GenerationConfig,
+  LimitSettings,
+  Settings,
+  ShortcutSettings,
+} from "../dashboard/settingsStore";
+
+/** Stable error kinds reported by the backend. */
+export type BemeErrorKind =
+  | "notConfigured"
+  | "noAudioSession"
+  | "connection"
+  | "auth"
+  | "rateLimited"
+  | "model"
+  | "invalidResponse"
+  | "api"
+  | "capture"
+  | "audioDevice"
+  | "settings"
+  | "replay"
+  | "invalidInput"
+  | "io";
+
+/** Error every backend command rejects with (also carried on `ai:error`). */
+export interface BemeError {
+  kind: BemeErrorKind;
+  message: string;
+  retryable: boolean;
+  retry_after_ms?: number;
+  /** HTTP status from the AI service, when known. */
+  status?: number;
+  /** The AI service's own error code (e.g. `rate_limit_exceeded`). */
+  provider_code?: string;
+}
+
+export function isBemeError(e: unknown): e is BemeError {
+  return (
+    typeof e === "object" && e !== null && "kind" in e && "message" in e
+  );
+}
+
+/** Human-readable message for anything a command rejected with. */
+export function errorMessage(e: unknown): string {
+  return isBemeError(e) ? e.message : String(e);
+}
+
+/** What the user can do about an error, if anything specific. */
+export function errorHint(e: BemeError): string | null {
+  switch (e.kind) {
+    case "auth":
+      return "Check the API key in Settings.";
+    case "notConfigured":
+      return "Add your endpoint and API key in Settings.";
+    case "audioDevice":
+      return "Pick another audio device.";
+    case "rateLimited":
+      return e.retry_after_ms
+        ? `Rate limited, retry in ${Math.ceil(e.retry_after_ms / 1000)}s.`
+        : "Rate limited, try again shortly.";
+    default:
+      return e.retryable ? "This may resolve on its own." : null;
+  }
+}
 
 /** Toggle capture on/off. Returns the new capturing state. */
 export function toggleCapture(): Promise<boolean> {
@@ -25,9 +88,29 @@ export function selectMonitor(monitorId: number | null): Promise<void> {
   return invoke<void>("select_monitor", { monitorId });
 }
 
-/** Persist settings to TOML config file via Rust backend. */
-export function saveSettings(settings: Settings): Promise<void> {
-  return invoke<void>("save_settings", { settings });
+export type ShortcutAction = keyof ShortcutSettings;
+
+/** A shortcut that can't be used, and why. */
+export interface ShortcutIssue {
+  action: ShortcutAction;
+  shortcut: string;
+  message: string;
+}
+
+/**
+ * Persist settings to TOML config file via Rust backend and re-register
+ * global shortcuts. Rejects on invalid/conflicting shortcuts; resolves with
+ * the shortcuts the OS refused (e.g. held by another app).
+ */
+export function saveSettings(settings: Settings): Promise<ShortcutIssue[]> {
+  return invoke<ShortcutIssue[]>("save_settings", { settings });
+}
+
+/** Check shortcuts for bad syntax and conflicts without saving. */
+export function validateShortcuts(
+  shortcuts: ShortcutSettings,
+): Promise<ShortcutIssue[]> {
+  return invoke<ShortcutIssue[]>("validate_shortcuts", { shortcuts });
 }
 
 /** Load settings from TOML config file. Returns defaults if none saved. */
@@ -57,9 +140,132 @@ export function isAiConfigured(): Promise<boolean> {
   return invoke<boolean>("is_ai_configured");
 }
 
-/** Toggle audio capture on/off. Returns the new capturing state. */
-export function toggleAudioCapture(): Promise<boolean> {
-  return invoke<boolean>("toggle_audio_capture");
+/**
+ * Include or exclude audio. While capturing it starts/stops immediately;
+ * otherwise it applies to the next start.
+ */
+export function setAudioEnabled(enabled: boolean): Promise<CaptureStatus> {
+  return invoke<CaptureStatus>("set_audio_enabled", { enabled });
+}
+
+export type ImageDetail = GenerationConfig["imageDetail"];
+
+/**
+ * Capture the selected monitor right now, regardless of the frame diff, and
+ * stream the answer as a normal suggestion. `question` replaces the text
+ * configured to go with frames, and `detail` the configured image detail.
+ * Resolves with the suggestion id.
+ */
+export function analyzeNow(
+  question?: string,
+  detail?: ImageDetail | null,
+): Promise<number> {
+  return invoke<number>("analyze_now", {
+    question: question || null,
+    detail: detail ?? null,
+  });
+}
+
+/**
+ * Ask a follow-up ("why?", "how exactly?") about a screen suggestion. The
+ * reply streams as a new suggestion with `parent_id` set; resolves with its id.
+ */
+export function askFollowup(
+  suggestionId: number,
+  text: string,
+): Promise<number> {
+  return invoke<number>("ask_followup", { suggestionId, text });
+}
+
+/** Start a fresh conversation thread; resolves with its id. */
+export function resetThread(): Promise<number> {
+  return invoke<number>("reset_thread");
+}
+
+/**
+ * Continue capture from an earlier screen suggestion instead of the latest
+ * one; resolves with the new thread id.
+ */
+export function forkThread(suggestionId: number): Promise<number> {
+  return invoke<number>("fork_thread", { suggestionId });
+}
+
+/** Tokens one response used; input and output are totals. */
+export interface TokenUsage {
+  input_tokens: number;
+  cached_input_tokens: number;
+  image_tokens: number;
+  audio_input_tokens: number;
+  output_tokens: number;
+  reasoning_tokens: number;
+  audio_output_tokens: number;
+}
+
+/** Usage added up over a session or a day. */
+export interface UsageTotals extends TokenUsage {
+  requests: number;
+  cost: number;
+}
+
+export interface UsageReport {
+  currency: string;
+  session_started: string;
+  session_secs: number;
+  /** Since capture last started. */
+  session: UsageTotals;
+  /** null for the first minute of a session. */
+  cost_per_hour: number | null;
+  /** Today's UTC date, and its totals. */
+  day: string;
+  today: UsageTotals;
+  days: Record<string, UsageTotals>;
+}
+
+/** Token usage and cost: this session, today and each day on record. */
+export function getUsage(): Promise<UsageReport> {
+  return invoke<UsageReport>("get_usage");
+}
+
+/** A spending limit that has been reached. */
+export interface LimitHit {
+  kind: "requests_per_minute" | "tokens_per_hour" | "cost_per_day";
+  limit: number;
+  /** What has been used in the limit's window. */
+  used: number;
+  /** Seconds until the limit lifts by itself. */
+  resets_in_secs: number;
+}
+
+export interface LimitStatus {
+  limits: LimitSettings;
+  reached: LimitHit | null;
+  /** Seconds left on an override. */
+  override_secs: number | null;
+  /** The audio session was closed for a limit and reopens when it lifts. */
+  audio_suspended: boolean;
+}
+
+/** The spending limits, which one (if any) is reached, and any override. */
+export function limitStatus(): Promise<LimitStatus> {
+  return invoke<LimitStatus>("limit_status");
+}
+
+/** Keep capturing past the spending limits for `minutes`. */
+export function overrideLimits(minutes: number): Promise<void> {
+  return invoke<void>("override_limits", { minutes });
+}
+
+/** Which capture loops are actually running. */
+export interface CaptureStatus {
+  screen: boolean;
+  audio: boolean;
+  /** Whether audio is included when capture starts. */
+  audio_enabled: boolean;
+}
+
+/** Ask the backend which capture loops are running right now. */
+export function captureStatus(): Promise<CaptureStatus> {
+  return invoke<CaptureStatus>("capture_status");
 }
 
 /** Start the audio AI WebSocket session. */
@@ -72,10 +278,26 @@ export async function stopAudioAi(): Promise<void> {
   return invoke("stop_audio_ai");
 }
 
+/** A sample rate / channel combination for an audio device. */
+export interface AudioStreamConfig {
+  sample_rate: number;
+  channels: number;
+}
+
+/** One supported config range reported by an audio device. */
+export interface SupportedAudioConfig {
+  channels: number;
+  min_sample_rate: number;
+  max_sample_rate: number;
+  sample_format: string;
+}
+
 /** Audio device descriptor returned by the backend. */
 export interface AudioDeviceInfo {
   name: string;
   is_default: boolean;
+  default_config: AudioStreamConfig | null;
+  supported_configs: SupportedAudioConfig[];
 }
 
 /** List all available audio output devices. */
@@ -88,6 +310,19 @@ export function selectAudioDevice(deviceName: string | null): Promise<void> {
   return invoke<void>("select_audio_device", { deviceName });
 }
 
+/** Choose the capture config for a device. Pass null for its default. */
+export function selectAudioConfig(
+  deviceName: string,
+  config: AudioStreamConfig | null,
+): Promise<void> {
+  return invoke<void>("select_audio_config", { deviceName, config });
+}
+
+/** Turn recording of captured audio to WAV files on or off. */
+export function setAudioRecording(enabled: boolean): Promise<void> {
+  return invoke<void>("set_audio_recording", { enabled });
+}
+
 /** Prompt info returned by get_prompts. */
 export interface PromptsInfo {
   vision: string;
@@ -103,3 +338,77 @@ export function getPrompts(): Promise<PromptsInfo> {
 export function updatePrompt(source: string, text: string): Promise<void> {
   return invoke<void>("update_prompt", { source, text });
 }
+
+/**
+ * Replay a recorded directory (JPEG frames + WAV) through the AI pipeline.
+ * `speed` 1 = real time, 0 = as fast as possible.
+ */
+export function startReplay(dir: string, speed?: number): Promise<void> {
+  return invoke<void>("start_replay", { dir, speed: speed ?? null });
+}
+
+/**
+ * Window that activates a profile: every non-empty field must appear
+ * (any case) in the window's app name / title.
+ */
+export interface ProfileRule {
+  app: string;
+  title: string;
+}
+
+/** A named pair of prompts. */
+export interface PromptProfile {
+  name: string;
+  visionPrompt: string;
+  audioPrompt: string;
+  rules: ProfileRule[];
+}
+
+/**
+ * Every profile, plus which is live (`active`) and which was picked by hand
+ * (`chosen`). null = the default prompts.
+ */
+export interface ProfilesView {
+  profiles: PromptProfile[];
+  active: string | null;
+  chosen: string | null;
+  auto_switch: boolean;
+}
+
+/** The frontmost window, as seen by auto-switching. */
+export interface FocusedWindow {
+  app_name: string;
+  title: string;
+  process_id: number;
+}
+
+export function listProfiles(): Promise<ProfilesView> {
+  return invoke<ProfilesView>("list_profiles");
+}
+
+/** Add a profile, or replace (and possibly rename) the one named `originalName`. */
+export function saveProfile(
+  profile: PromptProfile,
+  originalName: string | null,
+): Promise<ProfilesView> {
+  return invoke<ProfilesView>("save_profile", { profile, originalName });
+}
+
+export function deleteProfile(name: string): Promise<ProfilesView> {
+  return invoke<ProfilesView>("delete_profile", { name });
+}
+
+/** Switch to a profile by hand; null goes back to the default prompts. */
+export function activateProfile(name: string | null): Promise<ProfilesView> {
+  return invoke<ProfilesView>("activate_profile", { name });
+}
+
+/** Turn automatic switching by the frontmost window on or off. */
+export function setProfileAutoSwitch(enabled: boolean): Promise<ProfilesView> {
+  return invoke<ProfilesView>("set_profile_auto_switch", { enabled });
+}
+
+/** The frontmost window, for filling in a rule. */
+export function currentWindow(): Promise<FocusedWindow | null> {
+  return invoke<FocusedWindow | null>("current_window");
+}
@@ -1,4 +1,11 @@
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type {
+  BemeError,
+  FocusedWindow,
+  LimitHit,
+  TokenUsage,
+  UsageTotals,
+} from "./commands";
 
 export interface FramePayload {
   data: string;
@@ -19,8 +26,19 @@ export interface AudioChunkPayload {
   sample_count: number;
 }
 
-export interface ToggleCapturePayload {
-  source: "shortcut" | "tray";
+export interface AudioStatsPayload {
+  dropped_samples: number;
+  dropped_since_last: number;
+  buffered_samples: number;
+  capacity: number;
+  timestamp: string;
+}
+
+/** Which capture loops are running; emitted whenever capture starts/stops. */
+export interface CaptureStatusPayload {
+  screen: boolean;
+  audio: boolean;
+  audio_enabled: boolean;
 }
 
 export function listenCaptureFrame(
@@ -41,10 +59,16 @@ export function listenAudioChunk(
   return listen<AudioChunkPayload>("capture:audio-chunk", (e) => cb(e.payload));
 }
 
-export function listenToggleCapture(
-  cb: (payload: ToggleCapturePayload) => void,
+export function listenAudioStats(
+  cb: (payload: AudioStatsPayload) => void,
 ): Promise<UnlistenFn> {
-  return listen<ToggleCapturePayload>("toggle:capture", (e) => cb(e.payload));
+  return listen<AudioStatsPayload>("capture:audio-stats", (e) => cb(e.payload));
+}
+
+export function listenCaptureStatus(
+  cb: (payload: CaptureStatusPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<CaptureStatusPayload>("capture:status", (e) => cb(e.payload));
 }
 
 export interface SuggestionPayload {
@@ -53,10 +77,63 @@ export interface SuggestionPayload {
   done: boolean;
   id: number;
   source: string;
+  /** What the user asked, for "analyze now" requests and follow-ups. */
+  question?: string;
+  /** The suggestion this one answers a follow-up about. */
+  parent_id?: number;
+  /** The conversation thread a screen suggestion belongs to. */
+  thread_id?: number;
+}
+
+/**
+ * Emitted when a structured screen suggestion completes, just before its
+ * `ai:suggestion` events (which carry the action as text).
+ */
+export interface StructuredSuggestionPayload {
+  id: number;
+  timestamp: string;
+  action: string;
+  rationale: string;
+  /** 0–1. */
+  confidence: number;
+  urgency: "low" | "medium" | "high";
+  target_app: string | null;
+  question?: string;
+  thread_id: number;
+}
+
+export function listenStructuredSuggestion(
+  cb: (payload: StructuredSuggestionPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<StructuredSuggestionPayload>("ai:structured-suggestion", (e) =>
+    cb(e.payload),
+  );
+}
+
+/**
+ * Emitted when a capture suggestion repeats a recent one. Structured
+ * suggestions are withheld entirely; free-text ones have already streamed.
+ */
+export interface SuggestionSuppressedPayload {
+  id: number;
+  duplicate_of: number;
+  /** Word overlap with `duplicate_of`, 0–1. */
+  similarity: number;
+  reason: "identical" | "similar";
+  timestamp: string;
 }
 
-export interface AiErrorPayload {
-  message: string;
+export function listenSuggestionSuppressed(
+  cb: (payload: SuggestionSuppressedPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<SuggestionSuppressedPayload>("suggestion:suppressed", (e) =>
+    cb(e.payload),
+  );
+}
+
+export interface AiErrorPayload extends BemeError {
+  source: "screen" | "audio";
+  suggestion_id: number | null;
   timestamp: string;
 }
 
@@ -72,6 +149,85 @@ export function listenAiError(
   return listen<AiErrorPayload>("ai:error", (e) => cb(e.payload));
 }
 
+/**
+ * Emitted when a response reports the tokens it used, with the session and
+ * day totals it was added to.
+ */
+export interface UsagePayload {
+  source: "screen" | "audio";
+  suggestion_id: number | null;
+  usage: TokenUsage;
+  cost: number;
+  currency: string;
+  session: UsageTotals;
+  today: UsageTotals;
+  timestamp: string;
+}
+
+export function listenUsage(
+  cb: (payload: UsagePayload) => void,
+): Promise<UnlistenFn> {
+  return listen<UsagePayload>("ai:usage", (e) => cb(e.payload));
+}
+
+/**
+ * Emitted when a spending limit is reached (frames are dropped and the audio
+ * session closed), lifts, or is overridden.
+ */
+export interface LimitPayload {
+  state: "reached" | "cleared" | "overridden";
+  /** The limit reached, or set aside by an override. */
+  limit: LimitHit | null;
+  override_secs: number | null;
+  timestamp: string;
+}
+
+export function listenLimit(
+  cb: (payload: LimitPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<LimitPayload>("ai:limit", (e) => cb(e.payload));
+}
+
+/** Emitted when the AI is paused or resumed (capture keeps running). */
+export interface AiPausedPayload {
+  paused: boolean;
+}
+
+export function listenAiPaused(
+  cb: (payload: AiPausedPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<AiPausedPayload>("ai:paused", (e) => cb(e.payload));
+}
+
+/** Emitted when screen suggestions move to a new conversation thread. */
+export interface ThreadPayload {
+  thread_id: number;
+  reason: "capture" | "monitor" | "prompt" | "provider" | "reset" | "fork";
+  /** The suggestion a forked thread continues from. */
+  forked_from: number | null;
+}
+
+export function listenThread(
+  cb: (payload: ThreadPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<ThreadPayload>("ai:thread", (e) => cb(e.payload));
+}
+
+/** Emitted when a different prompt profile (or the defaults) takes over. */
+export interface ProfileChangedPayload {
+  /** null = the default prompts. */
+  active: string | null;
+  reason: "manual" | "auto" | "edited" | "deleted";
+  /** The window that triggered an automatic switch. */
+  window: FocusedWindow | null;
+}
+
+export function listenProfileChanged(
+  cb: (payload: ProfileChangedPayload) => void,
+): Promise<UnlistenFn> {
+  return listen<ProfileChangedPayload>("profile:changed", (e) => cb(e.payload));
+}
+
 export interface AudioStatusPayload {
   status: "connecting" | "connected" | "disconnected" | "error";
   message: string | null;
@@ -82,3 +238,23 @@ export function listenAudioStatus(
 ): Promise<UnlistenFn> {
   return listen<AudioStatusPayload>("ai:audio-status", (e) => cb(e.payload));
 }
+
+export interface ReplayStats {
+  frames_analyzed: number;
+  frames_skipped: number;
+  audio_chunks: number;
+  duration_ms: number;
+}
+
+export function listenReplayFinished(
+  cb: (payload: ReplayStats) => void,
+): Promise<UnlistenFn> {
+  return listen<ReplayStats>("replay:finished", (e) => cb(e.payload));
+}
+
+/** A replay that failed after `startReplay` returned. */
+export function listenReplayError(
+  cb: (payload: BemeError) => void,
+): Promise<UnlistenFn> {
+  return listen<BemeError>("replay:error", (e) => cb(e.payload));
+}